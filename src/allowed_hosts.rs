@@ -0,0 +1,143 @@
+use std::net::IpAddr;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header,
+};
+use futures::prelude::*;
+use log::error;
+
+use crate::errors::ContextualError;
+use crate::MiniserveConfig;
+
+/// Hosts requests may be addressed to, when `--allowed-hosts` is in use
+///
+/// Checking the Host header of requests protects against DNS rebinding, where a malicious page
+/// resolves its own domain to the address of miniserve to read from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedHosts {
+    /// Allowed host names, in lowercase
+    names: Vec<String>,
+
+    /// Allowed IP addresses
+    addresses: Vec<IpAddr>,
+}
+
+impl AllowedHosts {
+    /// Allows the given hosts, along with `localhost` and the addresses listened on
+    ///
+    /// Wildcard addresses stand for all the addresses of the local interfaces.
+    pub fn new(hosts: &[String], interfaces: &[IpAddr]) -> Self {
+        let mut allowed = AllowedHosts {
+            names: vec!["localhost".to_string()],
+            addresses: Vec::new(),
+        };
+        for host in hosts {
+            match host.parse::<IpAddr>() {
+                Ok(address) => allowed.addresses.push(address),
+                Err(_) => allowed
+                    .names
+                    .push(host.trim_end_matches('.').to_lowercase()),
+            }
+        }
+
+        allowed.addresses.extend(interfaces.iter().copied());
+        if interfaces.iter().any(|address| address.is_unspecified()) {
+            let local_addresses = get_if_addrs::get_if_addrs().unwrap_or_else(|e| {
+                error!("Failed to get local interface addresses: {}", e);
+                Default::default()
+            });
+            allowed
+                .addresses
+                .extend(local_addresses.into_iter().map(|iface| iface.ip()));
+        }
+        allowed
+    }
+
+    /// Returns `true` if requests may be addressed to `host`, the value of a Host header
+    pub fn allows(&self, host: &str) -> bool {
+        let name = host_name(host);
+        match name.parse::<IpAddr>() {
+            Ok(address) => self.addresses.contains(&address),
+            Err(_) => {
+                let name = name.trim_end_matches('.').to_lowercase();
+                self.names.contains(&name)
+            }
+        }
+    }
+}
+
+/// Returns the host of a Host header without its port, and IPv6 addresses without brackets
+fn host_name(host: &str) -> &str {
+    match host.strip_prefix('[') {
+        Some(address) => address.split(']').next().unwrap_or(address),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    }
+}
+
+/// Middleware refusing requests addressed to hosts which aren't allowed with `--allowed-hosts`
+pub fn allowed_hosts_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let result = match &conf.allowed_hosts {
+        Some(allowed_hosts) => check_host(&req, allowed_hosts),
+        None => Ok(()),
+    };
+
+    let fut = result.map(|()| srv.call(req));
+    async move { fut?.await }
+}
+
+fn check_host(req: &ServiceRequest, allowed_hosts: &AllowedHosts) -> Result<(), ContextualError> {
+    // Forwarding headers are left aside, as the page of an attacker can set them
+    let host = match req.uri().authority() {
+        Some(authority) => Some(authority.as_str()),
+        None => req
+            .headers()
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok()),
+    };
+
+    match host {
+        Some(host) if allowed_hosts.allows(host) => Ok(()),
+        Some(host) => Err(ContextualError::InvalidHttpRequestError(format!(
+            "Host '{host}' is not allowed"
+        ))),
+        None => Err(ContextualError::InvalidHttpRequestError(
+            "Missing Host header".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("localhost", true)]
+    #[case("LOCALHOST:8080", true)]
+    #[case("files.example.com", true)]
+    #[case("Files.Example.com.:443", true)]
+    #[case("127.0.0.1:8080", true)]
+    #[case("[::1]:8080", true)]
+    #[case("192.168.1.20", true)]
+    #[case("192.168.1.21", false)]
+    #[case("example.com", false)]
+    #[case("evil.com:8080", false)]
+    fn test_allows(#[case] host: &str, #[case] expected: bool) {
+        let allowed_hosts = AllowedHosts::new(
+            &["files.example.com".to_string(), "192.168.1.20".to_string()],
+            &["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()],
+        );
+        assert_eq!(allowed_hosts.allows(host), expected);
+    }
+}
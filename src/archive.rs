@@ -1,15 +1,22 @@
+use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{Cursor, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use actix_web::http::header::ContentEncoding;
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use libflate::gzip::Encoder;
-use serde::Deserialize;
-use strum::{Display, EnumIter, EnumString};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 use tar::Builder;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 use zip::{write, ZipWriter};
 
 use crate::errors::ContextualError;
+use crate::exclude::Exclusions;
+use crate::rate_limit::WalkThrottle;
 
 /// Available archive methods
 #[derive(Deserialize, Clone, Copy, EnumIter, EnumString, Display)]
@@ -19,6 +26,9 @@ pub enum ArchiveMethod {
     /// Gzipped tarball
     TarGz,
 
+    /// Zstandard-compressed tarball
+    TarZst,
+
     /// Regular tarball
     Tar,
 
@@ -26,10 +36,156 @@ pub enum ArchiveMethod {
     Zip,
 }
 
+/// Settings of the generated archives
+#[derive(Clone, Debug)]
+pub struct ArchiveSettings {
+    /// Compression level of zstd-compressed tarballs
+    pub zstd_level: i32,
+
+    /// Compression of the files in zip archives
+    pub zip: ZipCompression,
+
+    /// If false, files which are already compressed are stored as is in zip archives, whatever
+    /// their compression
+    pub zip_deflate_compressed: bool,
+
+    /// If false, entry names are written with the Unicode normalization of the file names
+    /// instead of NFC
+    pub normalize_names: bool,
+
+    /// If false, files and directories whose name starts with a dot are left out, as they are
+    /// from listings
+    pub include_hidden: bool,
+
+    /// Files and directories left out with --exclude
+    pub exclusions: Option<Arc<Exclusions>>,
+
+    /// Names of the files configuring miniserve, left out as they are from listings, such as
+    /// auth files with --auth-files
+    pub reserved_names: Vec<&'static str>,
+
+    /// Number of directory levels archived below the archived directory, set with --max-depth
+    ///
+    /// Directories at the last level are archived empty.
+    pub max_depth: Option<usize>,
+}
+
+impl ArchiveSettings {
+    /// Returns the options of a file of a zip archive, given its name and content
+    ///
+    /// Deflating already compressed data (photos, videos, archives, ...) costs time for next to
+    /// no gain, so such files are stored unless `zip_deflate_compressed` is set.
+    fn zip_file_options(&self, name: &OsStr, content: &[u8]) -> write::FileOptions {
+        if !self.zip_deflate_compressed && is_compressed(name, content) {
+            ZipCompression::Store.file_options()
+        } else {
+            self.zip.file_options()
+        }
+    }
+
+    /// Whether the file or directory at `path` goes into archives
+    fn includes(&self, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        (self.include_hidden || !name.starts_with('.'))
+            && !self.reserved_names.contains(&&*name)
+            && !self
+                .exclusions
+                .as_ref()
+                .is_some_and(|exclusions| exclusions.excludes_absolute(path))
+    }
+
+    /// Whether the content of a directory `depth` levels below the archived one goes into
+    /// archives
+    fn walks(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max_depth| depth < max_depth)
+    }
+
+    /// Returns the name of the archive entry for `path`, in NFC unless `normalize_names` is unset
+    ///
+    /// File names with decomposed characters, as written by macOS, are otherwise extracted as
+    /// distinct names from their composed form on other platforms.
+    fn entry_name(&self, path: PathBuf) -> PathBuf {
+        match path.to_str() {
+            Some(name) if self.normalize_names && !is_nfc(name) => {
+                name.nfc().collect::<String>().into()
+            }
+            _ => path,
+        }
+    }
+}
+
+/// Extensions of the file formats whose content is already compressed
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "aac", "apk", "avif", "br", "bz2", "docx", "epub", "flac", "gif", "gz", "heic", "jar",
+    "jpeg", "jpg", "lz4", "m4a", "m4v", "mkv", "mov", "mp3", "mp4", "odt", "ogg", "opus", "png",
+    "pptx", "rar", "tgz", "webm", "webp", "woff", "woff2", "xlsx", "xz", "zip", "zst",
+];
+
+/// Signatures at the start of the content of already compressed file formats
+const COMPRESSED_SIGNATURES: &[&[u8]] = &[
+    b"\xFF\xD8\xFF",       // JPEG
+    b"\x89PNG",            // PNG
+    b"GIF8",               // GIF
+    b"PK\x03\x04",         // Zip and formats based on it
+    b"\x1F\x8B",           // Gzip
+    b"\x28\xB5\x2F\xFD",   // Zstandard
+    b"\xFD7zXZ\x00",       // XZ
+    b"BZh",                // Bzip2
+    b"7z\xBC\xAF\x27\x1C", // 7-Zip
+    b"Rar!",               // RAR
+    b"\x1A\x45\xDF\xA3",   // Matroska, WebM
+    b"OggS",               // Ogg
+    b"fLaC",               // FLAC
+    b"ID3",                // MP3
+];
+
+/// Returns `true` if the file named `name` with the given content is already compressed,
+/// judging by its extension or the signature of its format
+pub fn is_compressed(name: &OsStr, content: &[u8]) -> bool {
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if extension.is_some_and(|extension| COMPRESSED_EXTENSIONS.contains(&extension.as_str())) {
+        return true;
+    }
+
+    // MP4, MOV and HEIF files start with the size of their "ftyp" box
+    COMPRESSED_SIGNATURES
+        .iter()
+        .any(|signature| content.starts_with(signature))
+        || content.get(4..8) == Some(b"ftyp")
+}
+
+/// Compression of the files in zip archives
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZipCompression {
+    /// Files are stored as is, which is the fastest for already compressed files
+    Store,
+
+    /// Files are deflated at the given level, from 0 to 9
+    Deflate(i32),
+}
+
+impl ZipCompression {
+    /// Highest compression level of deflate
+    pub const MAX_DEFLATE_LEVEL: i32 = 9;
+
+    fn file_options(self) -> write::FileOptions {
+        let options = write::FileOptions::default();
+        match self {
+            ZipCompression::Store => options.compression_method(zip::CompressionMethod::Stored),
+            ZipCompression::Deflate(level) => options
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(Some(level)),
+        }
+    }
+}
+
 impl ArchiveMethod {
     pub fn extension(self) -> String {
         match self {
             ArchiveMethod::TarGz => "tar.gz",
+            ArchiveMethod::TarZst => "tar.zst",
             ArchiveMethod::Tar => "tar",
             ArchiveMethod::Zip => "zip",
         }
@@ -39,23 +195,37 @@ impl ArchiveMethod {
     pub fn content_type(self) -> String {
         match self {
             ArchiveMethod::TarGz => "application/gzip",
+            ArchiveMethod::TarZst => "application/zstd",
             ArchiveMethod::Tar => "application/tar",
             ArchiveMethod::Zip => "application/zip",
         }
         .to_string()
     }
 
+    /// Returns the archive method producing archives of type `content_type`, if any
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        Self::iter().find(|method| method.content_type() == content_type)
+    }
+
     pub fn content_encoding(self) -> ContentEncoding {
         match self {
             ArchiveMethod::TarGz => ContentEncoding::Gzip,
+            ArchiveMethod::TarZst => ContentEncoding::Identity,
             ArchiveMethod::Tar => ContentEncoding::Identity,
             ArchiveMethod::Zip => ContentEncoding::Identity,
         }
     }
 
-    pub fn is_enabled(self, tar_enabled: bool, tar_gz_enabled: bool, zip_enabled: bool) -> bool {
+    pub fn is_enabled(
+        self,
+        tar_enabled: bool,
+        tar_gz_enabled: bool,
+        tar_zst_enabled: bool,
+        zip_enabled: bool,
+    ) -> bool {
         match self {
             ArchiveMethod::TarGz => tar_gz_enabled,
+            ArchiveMethod::TarZst => tar_zst_enabled,
             ArchiveMethod::Tar => tar_enabled,
             ArchiveMethod::Zip => zip_enabled,
         }
@@ -63,13 +233,20 @@ impl ArchiveMethod {
 
     /// Make an archive out of the given directory, and write the output to the given writer.
     ///
-    /// Recursively includes all files and subdirectories.
+    /// Recursively includes all files and subdirectories. Tarballs are written as the directory is
+    /// walked, so that `out` can stream them with bounded memory, while zip archives are built in
+    /// memory first since the zip writer needs to seek.
     ///
     /// If `skip_symlinks` is `true`, symlinks fill not be followed and will just be ignored.
+    /// If `throttle` is set, it is waited for before accessing each file or directory.
+    /// `settings` set how much the archive is compressed, for the formats which allow it, and how
+    /// its entries are named.
     pub fn create_archive<T, W>(
         self,
         dir: T,
         skip_symlinks: bool,
+        throttle: Option<&WalkThrottle>,
+        settings: &ArchiveSettings,
         out: W,
     ) -> Result<(), ContextualError>
     where
@@ -78,21 +255,28 @@ impl ArchiveMethod {
     {
         let dir = dir.as_ref();
         match self {
-            ArchiveMethod::TarGz => tar_gz(dir, skip_symlinks, out),
-            ArchiveMethod::Tar => tar_dir(dir, skip_symlinks, out),
-            ArchiveMethod::Zip => zip_dir(dir, skip_symlinks, out),
+            ArchiveMethod::TarGz => tar_gz(dir, skip_symlinks, throttle, settings, out),
+            ArchiveMethod::TarZst => tar_zst(dir, skip_symlinks, throttle, settings, out),
+            ArchiveMethod::Tar => tar_dir(dir, skip_symlinks, throttle, settings, out),
+            ArchiveMethod::Zip => zip_dir(dir, skip_symlinks, throttle, settings, out),
         }
     }
 }
 
 /// Write a gzipped tarball of `dir` in `out`.
-fn tar_gz<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+fn tar_gz<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
     let mut out = Encoder::new(out).map_err(|e| ContextualError::IoError("GZIP".to_string(), e))?;
 
-    tar_dir(dir, skip_symlinks, &mut out)?;
+    tar_dir(dir, skip_symlinks, throttle, settings, &mut out)?;
 
     out.finish()
         .into_result()
@@ -101,6 +285,28 @@ where
     Ok(())
 }
 
+/// Write a Zstandard-compressed tarball of `dir` in `out`, at the level of `settings`.
+fn tar_zst<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    out: W,
+) -> Result<(), ContextualError>
+where
+    W: std::io::Write,
+{
+    let mut out = zstd::stream::write::Encoder::new(out, settings.zstd_level)
+        .map_err(|e| ContextualError::IoError("Zstandard".to_string(), e))?;
+
+    tar_dir(dir, skip_symlinks, throttle, settings, &mut out)?;
+
+    out.finish()
+        .map_err(|e| ContextualError::IoError("Zstandard finish".to_string(), e))?;
+
+    Ok(())
+}
+
 /// Write a tarball of `dir` in `out`.
 ///
 /// The target directory will be saved as a top-level directory in the archive.
@@ -124,7 +330,13 @@ where
 /// ├── f
 /// └── g
 /// ```
-fn tar_dir<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+fn tar_dir<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
@@ -138,8 +350,15 @@ where
         )
     })?;
 
-    tar(dir, directory.to_string(), skip_symlinks, out)
-        .map_err(|e| ContextualError::ArchiveCreationError("tarball".to_string(), Box::new(e)))
+    tar(
+        dir,
+        directory.to_string(),
+        skip_symlinks,
+        throttle,
+        settings,
+        out,
+    )
+    .map_err(|e| ContextualError::ArchiveCreationError("tarball".to_string(), Box::new(e)))
 }
 
 /// Writes a tarball of `dir` in `out`.
@@ -149,6 +368,8 @@ fn tar<W>(
     src_dir: &Path,
     inner_folder: String,
     skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
     out: W,
 ) -> Result<(), ContextualError>
 where
@@ -159,17 +380,23 @@ where
     tar_builder.follow_symlinks(!skip_symlinks);
 
     // Recursively adds the content of src_dir into the archive stream
-    tar_builder
-        .append_dir_all(inner_folder, src_dir)
-        .map_err(|e| {
-            ContextualError::IoError(
-                format!(
-                    "Failed to append the content of {} to the TAR archive",
-                    src_dir.to_str().unwrap_or("file")
-                ),
-                e,
-            )
-        })?;
+    append_dir_all(
+        &mut tar_builder,
+        Path::new(&inner_folder),
+        src_dir,
+        !skip_symlinks,
+        throttle,
+        settings,
+    )
+    .map_err(|e| {
+        ContextualError::IoError(
+            format!(
+                "Failed to append the content of {} to the TAR archive",
+                src_dir.to_str().unwrap_or("file")
+            ),
+            e,
+        )
+    })?;
 
     // Finish the archive
     tar_builder.into_inner().map_err(|e| {
@@ -179,6 +406,141 @@ where
     Ok(())
 }
 
+/// Adds `src_dir` and its content to `tar_builder` in a folder named `inner_folder`.
+fn append_dir_all<W>(
+    tar_builder: &mut Builder<W>,
+    inner_folder: &Path,
+    src_dir: &Path,
+    follow_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    walk_dir(
+        src_dir,
+        follow_symlinks,
+        throttle,
+        settings,
+        |src, is_dir| {
+            let dest = settings.entry_name(inner_folder.join(src.strip_prefix(src_dir).unwrap()));
+            if is_dir {
+                tar_builder.append_dir(&dest, src)
+            } else {
+                tar_builder.append_path_with_name(src, &dest)
+            }
+        },
+    )
+}
+
+/// Calls `visit` with `src_dir` and each file or directory in it, along with whether it is
+/// archived as a directory.
+///
+/// This walks the directory like `Builder::append_dir_all`, but leaves symlinks out unless they
+/// are followed, as well as the entries `settings` exclude or which are deeper than its maximum
+/// depth, and waits for `throttle` before accessing each file or directory.
+fn walk_dir<F>(
+    src_dir: &Path,
+    follow_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    mut visit: F,
+) -> std::io::Result<()>
+where
+    F: FnMut(&Path, bool) -> std::io::Result<()>,
+{
+    let mut stack = vec![(src_dir.to_path_buf(), true, false, 0)];
+    while let Some((src, is_dir, is_symlink, depth)) = stack.pop() {
+        if is_symlink && !follow_symlinks {
+            continue;
+        }
+        if let Some(throttle) = throttle {
+            throttle.wait();
+        }
+
+        // A symlink to a directory is only walked if symlinks are followed
+        if is_dir || (is_symlink && src.is_dir()) {
+            if settings.walks(depth) {
+                for entry in std::fs::read_dir(&src)? {
+                    let entry = entry?;
+                    if !settings.includes(&entry.path()) {
+                        continue;
+                    }
+                    let file_type = entry.file_type()?;
+                    stack.push((
+                        entry.path(),
+                        file_type.is_dir(),
+                        file_type.is_symlink(),
+                        depth + 1,
+                    ));
+                }
+            }
+            visit(&src, true)?;
+        } else {
+            visit(&src, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Files an archive of a directory would include, as reported with `?archive=plan`
+#[derive(Debug, Serialize)]
+pub struct ArchivePlan {
+    /// The files, sorted by their path in the archive
+    pub files: Vec<PlannedFile>,
+
+    /// Sum of the sizes of the files, in bytes
+    pub total_size: u64,
+}
+
+/// A file an archive would include
+#[derive(Debug, Serialize)]
+pub struct PlannedFile {
+    /// Path of the file in the archive, under the top-level directory
+    pub path: String,
+
+    /// Size of the file, in bytes
+    pub size: u64,
+}
+
+/// Lists the files an archive of `dir` would include, without reading nor compressing them
+///
+/// `dir` is walked the same way as when creating a tarball, with the same arguments.
+pub fn plan_archive(
+    dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+) -> Result<ArchivePlan, ContextualError> {
+    let inner_folder = dir.file_name().ok_or_else(|| {
+        ContextualError::InvalidPathError("Directory name terminates in \"..\"".to_string())
+    })?;
+
+    let mut files = Vec::new();
+    walk_dir(dir, !skip_symlinks, throttle, settings, |src, is_dir| {
+        if !is_dir {
+            let path =
+                settings.entry_name(Path::new(inner_folder).join(src.strip_prefix(dir).unwrap()));
+            files.push(PlannedFile {
+                path: path
+                    .iter()
+                    .map(|segment| segment.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size: std::fs::metadata(src)?.len(),
+            });
+        }
+        Ok(())
+    })
+    .map_err(|e| ContextualError::IoError(format!("Failed to walk {}", dir.display()), e))?;
+
+    files.sort_by(|f1, f2| f1.path.cmp(&f2.path));
+    let total_size = files.iter().map(|file| file.size).sum();
+    Ok(ArchivePlan { files, total_size })
+}
+
 /// Write a zip of `dir` in `out`.
 ///
 /// The target directory will be saved as a top-level directory in the archive.
@@ -206,12 +568,13 @@ fn create_zip_from_directory<W>(
     out: W,
     directory: &Path,
     skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
 ) -> Result<(), ContextualError>
 where
     W: std::io::Write + std::io::Seek,
 {
-    let options = write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-    let mut paths_queue: Vec<PathBuf> = vec![directory.to_path_buf()];
+    let mut paths_queue: Vec<(PathBuf, usize)> = vec![(directory.to_path_buf(), 0)];
     let zip_root_folder_name = directory.file_name().ok_or_else(|| {
         ContextualError::InvalidPathError("Directory name terminates in \"..\"".to_string())
     })?;
@@ -219,7 +582,7 @@ where
     let mut zip_writer = ZipWriter::new(out);
     let mut buffer = Vec::new();
     while !paths_queue.is_empty() {
-        let next = paths_queue.pop().ok_or_else(|| {
+        let (next, depth) = paths_queue.pop().ok_or_else(|| {
             ContextualError::ArchiveCreationDetailError("Could not get path from queue".to_string())
         })?;
         let current_dir = next.as_path();
@@ -234,31 +597,42 @@ where
         );
 
         for entry in directory_entry_iterator {
-            let entry_path = entry
-                .ok()
-                .ok_or_else(|| {
-                    ContextualError::InvalidPathError(
-                        "Directory name terminates in \"..\"".to_string(),
-                    )
-                })?
-                .path();
-            let entry_metadata = std::fs::metadata(entry_path.clone()).map_err(|e| {
-                ContextualError::IoError("Could not get file metadata".to_string(), e)
+            if let Some(throttle) = throttle {
+                throttle.wait();
+            }
+            let entry = entry.ok().ok_or_else(|| {
+                ContextualError::InvalidPathError("Directory name terminates in \"..\"".to_string())
             })?;
-
-            if entry_metadata.file_type().is_symlink() && skip_symlinks {
+            // The type of the entry itself, as the metadata below follows symlinks
+            let is_symlink = entry
+                .file_type()
+                .map(|file_type| file_type.is_symlink())
+                .unwrap_or(false);
+            let entry_path = entry.path();
+            if (is_symlink && skip_symlinks) || !settings.includes(&entry_path) {
                 continue;
             }
+            let entry_metadata = std::fs::metadata(entry_path.clone()).map_err(|e| {
+                ContextualError::IoError("Could not get file metadata".to_string(), e)
+            })?;
             let current_entry_name = entry_path.file_name().ok_or_else(|| {
                 ContextualError::InvalidPathError("Invalid file or directory name".to_string())
             })?;
+            let date_time = entry_metadata.modified().ok().and_then(zip_date_time);
+            let with_date = |options: write::FileOptions| match date_time {
+                Some(date_time) => options.last_modified_time(date_time),
+                None => options,
+            };
             if entry_metadata.is_file() {
                 let mut f = File::open(&entry_path)
                     .map_err(|e| ContextualError::IoError("Could not open file".to_string(), e))?;
                 f.read_to_end(&mut buffer).map_err(|e| {
                     ContextualError::IoError("Could not read from file".to_string(), e)
                 })?;
-                let relative_path = zip_directory.join(current_entry_name).into_os_string();
+                let options = with_date(settings.zip_file_options(current_entry_name, &buffer));
+                let relative_path = settings
+                    .entry_name(zip_directory.join(current_entry_name))
+                    .into_os_string();
                 zip_writer
                     .start_file(relative_path.to_string_lossy(), options)
                     .map_err(|_| {
@@ -273,15 +647,22 @@ where
                 })?;
                 buffer.clear();
             } else if entry_metadata.is_dir() {
-                let relative_path = zip_directory.join(current_entry_name).into_os_string();
+                let relative_path = settings
+                    .entry_name(zip_directory.join(current_entry_name))
+                    .into_os_string();
                 zip_writer
-                    .add_directory(relative_path.to_string_lossy(), options)
+                    .add_directory(
+                        relative_path.to_string_lossy(),
+                        with_date(settings.zip.file_options()),
+                    )
                     .map_err(|_| {
                         ContextualError::ArchiveCreationDetailError(
                             "Could not add directory path to ZIP".to_string(),
                         )
                     })?;
-                paths_queue.push(entry_path.clone());
+                if settings.walks(depth + 1) {
+                    paths_queue.push((entry_path.clone(), depth + 1));
+                }
             }
         }
     }
@@ -294,21 +675,47 @@ where
     Ok(())
 }
 
+/// Converts a modification date to the date and time of a ZIP entry, in UTC
+///
+/// ZIP entries have no time zone, so UTC is used whatever the time zone of the server, like the
+/// modification dates of TAR entries which are UNIX timestamps. Dates which can't be represented
+/// (before 1980) are left out.
+fn zip_date_time(modified: SystemTime) -> Option<zip::DateTime> {
+    let date_time = DateTime::<Utc>::from(modified);
+    zip::DateTime::from_date_and_time(
+        date_time.year().try_into().ok()?,
+        date_time.month() as u8,
+        date_time.day() as u8,
+        date_time.hour() as u8,
+        date_time.minute() as u8,
+        date_time.second() as u8,
+    )
+    .ok()
+}
+
 /// Writes a zip of `dir` in `out`.
 ///
 /// The content of `src_dir` will be saved in the archive as the  folder named .
-fn zip_data<W>(src_dir: &Path, skip_symlinks: bool, mut out: W) -> Result<(), ContextualError>
+fn zip_data<W>(
+    src_dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    mut out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
     let mut data = Vec::new();
     let memory_file = Cursor::new(&mut data);
-    create_zip_from_directory(memory_file, src_dir, skip_symlinks).map_err(|e| {
-        ContextualError::ArchiveCreationError(
-            "Failed to create the ZIP archive".to_string(),
-            Box::new(e),
-        )
-    })?;
+    create_zip_from_directory(memory_file, src_dir, skip_symlinks, throttle, settings).map_err(
+        |e| {
+            ContextualError::ArchiveCreationError(
+                "Failed to create the ZIP archive".to_string(),
+                Box::new(e),
+            )
+        },
+    )?;
 
     out.write_all(data.as_mut_slice())
         .map_err(|e| ContextualError::IoError("Failed to write the ZIP archive".to_string(), e))?;
@@ -316,7 +723,13 @@ where
     Ok(())
 }
 
-fn zip_dir<W>(dir: &Path, skip_symlinks: bool, out: W) -> Result<(), ContextualError>
+fn zip_dir<W>(
+    dir: &Path,
+    skip_symlinks: bool,
+    throttle: Option<&WalkThrottle>,
+    settings: &ArchiveSettings,
+    out: W,
+) -> Result<(), ContextualError>
 where
     W: std::io::Write,
 {
@@ -330,6 +743,47 @@ where
         )
     })?;
 
-    zip_data(dir, skip_symlinks, out)
+    zip_data(dir, skip_symlinks, throttle, settings, out)
         .map_err(|e| ContextualError::ArchiveCreationError("zip".to_string(), Box::new(e)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::time::Duration;
+
+    #[rstest]
+    #[case(1_623_760_496, Some((2021, 6, 15, 12, 34, 56)))]
+    #[case(315_532_800, Some((1980, 1, 1, 0, 0, 0)))]
+    #[case(0, None)]
+    fn test_zip_date_time(
+        #[case] timestamp: u64,
+        #[case] expected: Option<(u16, u8, u8, u8, u8, u8)>,
+    ) {
+        let date_time = zip_date_time(SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp));
+        assert_eq!(
+            date_time.map(|d| (
+                d.year(),
+                d.month(),
+                d.day(),
+                d.hour(),
+                d.minute(),
+                d.second()
+            )),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("photo.JPG", b"", true)]
+    #[case("video.mp4", b"", true)]
+    #[case("photo", b"\xFF\xD8\xFF\xE0", true)]
+    #[case("video", b"\x00\x00\x00\x20ftypisom", true)]
+    #[case("notes.txt", b"Hello", false)]
+    #[case("empty", b"", false)]
+    fn test_is_compressed(#[case] name: &str, #[case] content: &[u8], #[case] expected: bool) {
+        assert_eq!(is_compressed(OsStr::new(name), content), expected);
+    }
+}
@@ -0,0 +1,245 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use sha2::{Digest, Sha256};
+
+use crate::archive::{ArchiveMethod, ArchiveSettings};
+use crate::errors::ContextualError;
+use crate::rate_limit::WalkThrottle;
+
+/// On-disk cache of generated archives
+///
+/// Archives are stored under a name derived from the served directory they were made of and from
+/// a signature of its content (paths, sizes and modification dates of everything inside), so a
+/// cached archive is only used as long as the directory hasn't changed.
+#[derive(Clone)]
+pub struct ArchiveCache {
+    /// Directory holding the cached archives
+    dir: PathBuf,
+
+    /// Maximum total size of the cached archives, the least recently used ones are evicted first
+    max_size: u64,
+
+    /// If set, archives unused for longer than this are evicted
+    max_age: Option<Duration>,
+}
+
+impl ArchiveCache {
+    pub fn new(dir: PathBuf, max_size: u64, max_age: Option<Duration>) -> Self {
+        ArchiveCache {
+            dir,
+            max_size,
+            max_age,
+        }
+    }
+
+    /// Returns the path of the cache entry for the current content of `dir`
+    ///
+    /// Archives including hidden files are cached apart from the ones leaving them out.
+    pub fn entry_path(
+        &self,
+        dir: &Path,
+        archive_method: ArchiveMethod,
+        skip_symlinks: bool,
+        include_hidden: bool,
+    ) -> io::Result<PathBuf> {
+        let mut signature = Sha256::new();
+        hash_tree(dir, Path::new(""), skip_symlinks, &mut signature)?;
+
+        Ok(self.dir.join(format!(
+            "{}-{}.{}",
+            entry_prefix(dir, archive_method, skip_symlinks, include_hidden),
+            hex::encode(signature.finalize()),
+            archive_method.extension()
+        )))
+    }
+
+    /// Opens the archive at `entry_path` if it is in the cache, marking it as recently used
+    pub fn lookup(&self, entry_path: &Path) -> Option<File> {
+        self.evict_expired();
+        let file = File::options()
+            .read(true)
+            .append(true)
+            .open(entry_path)
+            .ok()?;
+        file.set_modified(SystemTime::now()).ok()?;
+        Some(file)
+    }
+
+    /// Creates an archive of `dir` in `out`, storing a copy of it at `entry_path`.
+    ///
+    /// Older archives of the same directory are removed, since they can't be used anymore.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_archive<W>(
+        &self,
+        entry_path: &Path,
+        archive_method: ArchiveMethod,
+        dir: &Path,
+        skip_symlinks: bool,
+        throttle: Option<&WalkThrottle>,
+        settings: &ArchiveSettings,
+        out: W,
+    ) -> Result<(), ContextualError>
+    where
+        W: Write,
+    {
+        // Concurrent requests for the same archive each write their own copy
+        static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let tmp_path = entry_path.with_extension(format!(
+            "{}.tmp",
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = File::create(&tmp_path).map_err(|e| {
+            ContextualError::IoError(format!("Failed to create {}", tmp_path.display()), e)
+        })?;
+
+        let result = archive_method.create_archive(
+            dir,
+            skip_symlinks,
+            throttle,
+            settings,
+            Tee { out, file },
+        );
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+            return result;
+        }
+
+        fs::rename(&tmp_path, entry_path).map_err(|e| {
+            ContextualError::IoError(format!("Failed to create {}", entry_path.display()), e)
+        })?;
+
+        let prefix = entry_prefix(dir, archive_method, skip_symlinks, settings.include_hidden);
+        for (path, _, _) in self.entries() {
+            let stale = path.file_name().is_some_and(|name| {
+                name.to_string_lossy().starts_with(&prefix) && path != entry_path
+            });
+            if stale {
+                let _ = fs::remove_file(path);
+            }
+        }
+        self.evict_oversized();
+
+        Ok(())
+    }
+
+    /// Lists the cached archives along with their size and last use
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return Vec::new(),
+        };
+
+        read_dir
+            .flatten()
+            .filter(|entry| entry.path().extension().is_none_or(|ext| ext != "tmp"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect()
+    }
+
+    /// Removes the archives unused for longer than `max_age`
+    fn evict_expired(&self) {
+        let max_age = match self.max_age {
+            Some(max_age) => max_age,
+            None => return,
+        };
+        let now = SystemTime::now();
+        for (path, _, last_use) in self.entries() {
+            if now.duration_since(last_use).unwrap_or_default() > max_age {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Removes the least recently used archives until the cache fits in `max_size`
+    fn evict_oversized(&self) {
+        let mut entries = self.entries();
+        entries.sort_by_key(|(_, _, last_use)| *last_use);
+
+        let mut total_size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        for (path, size, _) in entries {
+            if total_size <= self.max_size {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                total_size -= size;
+            }
+        }
+    }
+}
+
+/// Returns the part of the name of cache entries which identifies the archived directory
+fn entry_prefix(
+    dir: &Path,
+    archive_method: ArchiveMethod,
+    skip_symlinks: bool,
+    include_hidden: bool,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(dir.to_string_lossy().as_bytes());
+    hasher.update([skip_symlinks as u8, include_hidden as u8]);
+    format!(
+        "{}-{}",
+        &hex::encode(hasher.finalize())[..16],
+        archive_method
+    )
+}
+
+/// Feeds the path, size and modification date of everything in `root.join(relative)` to `hasher`
+fn hash_tree(
+    root: &Path,
+    relative: &Path,
+    skip_symlinks: bool,
+    hasher: &mut Sha256,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(root.join(relative))?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if skip_symlinks && entry.file_type()?.is_symlink() {
+            continue;
+        }
+        let path = relative.join(entry.file_name());
+        let metadata = fs::metadata(entry.path())?;
+        let modified = metadata
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(metadata.len().to_le_bytes());
+        hasher.update(modified.as_nanos().to_le_bytes());
+
+        if metadata.is_dir() {
+            hash_tree(root, &path, skip_symlinks, hasher)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writer copying everything written to it to a file
+struct Tee<W> {
+    out: W,
+    file: File,
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf)?;
+        self.file.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        self.file.flush()
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use serde::Serialize;
+
+use crate::archive::ArchiveMethod;
+use crate::archive_ranges::archive_response;
+use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::listing;
+use crate::MiniserveConfig;
+
+/// Finished jobs are forgotten after this long
+const JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Registry of the archives being generated in the background, by job id
+#[derive(Default)]
+pub struct ArchiveJobs {
+    jobs: Mutex<HashMap<String, Arc<ArchiveJob>>>,
+}
+
+/// Archive generated in the background into the archive cache
+struct ArchiveJob {
+    /// Name of the downloaded archive
+    file_name: String,
+
+    archive_method: ArchiveMethod,
+
+    /// Path of the archive in the cache
+    entry_path: PathBuf,
+
+    /// Number of bytes of the archive written so far
+    bytes_written: AtomicU64,
+
+    /// Current state, along with the time the job finished
+    state: Mutex<(JobState, Option<Instant>)>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum JobState {
+    Running,
+    Done,
+    Failed { error: String },
+}
+
+/// Status of a job, as returned by the API
+#[derive(Serialize)]
+struct JobStatus {
+    id: String,
+
+    #[serde(flatten)]
+    state: JobState,
+
+    bytes_written: u64,
+
+    /// Where the archive can be downloaded once done
+    download_url: String,
+}
+
+impl ArchiveJobs {
+    fn insert(&self, job: Arc<ArchiveJob>) -> String {
+        let id = nanoid::nanoid!();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.retain(|_, job| {
+            let (_, finished) = *job.state.lock().unwrap();
+            finished.is_none_or(|finished| finished.elapsed() < JOB_RETENTION)
+        });
+        jobs.insert(id.clone(), job);
+        id
+    }
+
+    fn get(&self, id: &str) -> Option<Arc<ArchiveJob>> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+}
+
+impl ArchiveJob {
+    fn finish(&self, state: JobState) {
+        *self.state.lock().unwrap() = (state, Some(Instant::now()));
+    }
+
+    fn status(&self, id: &str, route_prefix: &str) -> JobStatus {
+        JobStatus {
+            id: id.to_string(),
+            state: self.state.lock().unwrap().0.clone(),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            download_url: format!("{route_prefix}/archive-jobs/{id}/download"),
+        }
+    }
+}
+
+/// Writer discarding the archive, which is stored in the cache, while counting its bytes
+struct ProgressWriter<'a>(&'a AtomicU64);
+
+impl Write for ProgressWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.fetch_add(buf.len() as u64, Ordering::Relaxed);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Starts generating an archive of the directory given as `path` query parameter, in the format
+/// given as `download` query parameter.
+///
+/// Responds with 202 Accepted and the status of the job, whose URL is in the `Location` header.
+pub async fn start_job(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let (jobs, cache) = match (&conf.archive_jobs, &conf.archive_cache) {
+        (Some(jobs), Some(cache)) => (jobs, cache),
+        _ => return Err(ContextualError::RouteNotFoundError(req.path().to_string())),
+    };
+
+    let query_params = listing::extract_query_parameters(&req);
+    let archive_method = query_params.download.ok_or_else(|| {
+        ContextualError::InvalidHttpRequestError("Missing query parameter 'download'".to_string())
+    })?;
+    if let Some(refusal) = listing::refuse_archive(&req, conf, archive_method) {
+        return Ok(refusal);
+    }
+
+    let dir = resolve_dir(
+        query_params.path.as_deref().unwrap_or(Path::new("")),
+        conf,
+        query_params.shows_hidden(conf),
+    )?;
+    let skip_symlinks = conf.no_symlinks;
+    let settings = query_params.archive_settings(conf);
+    let entry_path = cache
+        .entry_path(&dir, archive_method, skip_symlinks, settings.include_hidden)
+        .map_err(|e| ContextualError::IoError("Failed to read the directory".to_string(), e))?;
+
+    let job = Arc::new(ArchiveJob {
+        file_name: format!(
+            "{}.{}",
+            dir.file_name().unwrap_or_default().to_string_lossy(),
+            archive_method.extension()
+        ),
+        archive_method,
+        entry_path,
+        bytes_written: AtomicU64::new(0),
+        state: Mutex::new((JobState::Running, None)),
+    });
+    let id = jobs.insert(job.clone());
+
+    if let Some(file) = cache.lookup(&job.entry_path) {
+        let size = file.metadata().map(|m| m.len()).unwrap_or_default();
+        job.bytes_written.store(size, Ordering::Relaxed);
+        job.finish(JobState::Done);
+    } else {
+        log::info!(
+            "Creating an archive ({extension}) of {path} in the background...",
+            extension = archive_method.extension(),
+            path = dir.display()
+        );
+        let cache = cache.clone();
+        let throttle = conf.walk_throttle.clone();
+        let job = job.clone();
+        std::thread::spawn(move || {
+            let result = cache.create_archive(
+                &job.entry_path,
+                archive_method,
+                &dir,
+                skip_symlinks,
+                throttle.as_deref(),
+                &settings,
+                ProgressWriter(&job.bytes_written),
+            );
+            match result {
+                Ok(()) => job.finish(JobState::Done),
+                Err(err) => {
+                    log::error!("Error during archive creation: {:?}", err);
+                    job.finish(JobState::Failed {
+                        error: err.to_string(),
+                    });
+                }
+            }
+        });
+    }
+
+    let status = job.status(&id, &conf.route_prefix);
+    Ok(HttpResponse::Accepted()
+        .append_header((
+            header::LOCATION,
+            format!("{}/archive-jobs/{id}", conf.route_prefix),
+        ))
+        .content_type(mime::APPLICATION_JSON)
+        .body(serde_json::to_string(&status).unwrap()))
+}
+
+/// Returns the status of a job as JSON
+pub async fn job_status(
+    req: HttpRequest,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let job = find_job(&req, &id)?;
+    Ok(HttpResponse::Ok()
+        .content_type(mime::APPLICATION_JSON)
+        .body(serde_json::to_string(&job.status(&id, &conf.route_prefix)).unwrap()))
+}
+
+/// Downloads the archive of a job, once it's done
+pub async fn download(
+    req: HttpRequest,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ContextualError> {
+    let job = find_job(&req, &id)?;
+    if !matches!(job.state.lock().unwrap().0, JobState::Done) {
+        return Ok(HttpResponse::Conflict()
+            .content_type(mime::TEXT_PLAIN_UTF_8)
+            .body("The archive is not ready yet."));
+    }
+
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    // The archive may have been evicted from the cache in the meantime
+    let file = conf
+        .archive_cache
+        .as_ref()
+        .and_then(|cache| cache.lookup(&job.entry_path))
+        .ok_or_else(|| ContextualError::RouteNotFoundError(req.path().to_string()))?;
+
+    archive_response(file, &job.file_name, job.archive_method, &req)
+}
+
+fn find_job(req: &HttpRequest, id: &str) -> Result<Arc<ArchiveJob>, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    conf.archive_jobs
+        .as_ref()
+        .and_then(|jobs| jobs.get(id))
+        .ok_or_else(|| ContextualError::RouteNotFoundError(req.path().to_string()))
+}
+
+/// Resolves `path`, relative to the served directory, to the directory to archive, which may only
+/// be hidden with `show_hidden`
+pub fn resolve_dir(
+    path: &Path,
+    conf: &MiniserveConfig,
+    show_hidden: bool,
+) -> Result<PathBuf, ContextualError> {
+    let invalid_path =
+        || ContextualError::InvalidHttpRequestError("Invalid directory to archive".to_string());
+
+    let relative_path = sanitize_path(path, show_hidden).ok_or_else(invalid_path)?;
+    let app_root_dir = conf.path.canonicalize().map_err(|e| {
+        ContextualError::IoError("Failed to resolve path served by miniserve".to_string(), e)
+    })?;
+    let dir = app_root_dir.join(relative_path);
+
+    // Disallow going through symlinks out of the served directory when they are not shown
+    let canonical_dir = dir.canonicalize().map_err(|_| invalid_path())?;
+    if !canonical_dir.is_dir() || (conf.no_symlinks && canonical_dir != dir) {
+        return Err(invalid_path());
+    }
+
+    Ok(dir)
+}
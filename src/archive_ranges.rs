@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use actix_files::NamedFile;
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::Method,
+    web, HttpRequest, HttpResponse,
+};
+use futures::future::{Either, FutureExt, TryFutureExt};
+use futures::prelude::*;
+use percent_encoding::percent_decode_str;
+
+use crate::archive::ArchiveMethod;
+use crate::archive_jobs::resolve_dir;
+use crate::errors::ContextualError;
+use crate::listing::{self, attachment_disposition};
+use crate::MiniserveConfig;
+
+/// Responds with the archive `file` stored in the cache, named `file_name`
+///
+/// Range requests and conditional requests are supported, as for any other file.
+pub fn archive_response(
+    file: File,
+    file_name: &str,
+    archive_method: ArchiveMethod,
+    req: &HttpRequest,
+) -> Result<HttpResponse, ContextualError> {
+    let named_file = NamedFile::from_file(file, file_name)
+        .map_err(|e| ContextualError::IoError("Failed to read the archive".to_string(), e))?
+        .set_content_type(archive_method.content_type().parse().unwrap())
+        .set_content_encoding(archive_method.content_encoding())
+        .set_content_disposition(attachment_disposition(file_name));
+    Ok(named_file.into_response(req))
+}
+
+/// Middleware creating archives in the archive cache before sending them as files, when
+/// `--archive-ranges` is enabled
+///
+/// Unlike archives streamed while they are created, these have a known size and support range
+/// requests, so that interrupted downloads can be resumed. The archive must be wrapped by the
+/// authentication middlewares, and the usual access checks of directories apply.
+pub fn archive_ranges_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    match requested_archive(&req) {
+        Some((dir, archive_method)) => {
+            Either::Left(serve_archive(req, dir, archive_method).boxed_local())
+        }
+        None => Either::Right(srv.call(req).map_ok(|res| res.map_into_boxed_body())),
+    }
+}
+
+/// Returns the directory and format of the archive requested by `req`, if it is to be created in
+/// the archive cache first
+fn requested_archive(req: &ServiceRequest) -> Option<(PathBuf, ArchiveMethod)> {
+    let conf = req.app_data::<MiniserveConfig>()?;
+    if !conf.archive_ranges || ![Method::GET, Method::HEAD].contains(req.method()) {
+        return None;
+    }
+    let query_params = listing::extract_query_parameters(req.request());
+    let archive_method = query_params.download?;
+
+    let path = req.path();
+    let path = path.strip_prefix(&conf.route_prefix).unwrap_or(path);
+    let path = percent_decode_str(path).decode_utf8_lossy();
+    // Invalid directories are left to the directory listing, which rejects them
+    let dir = resolve_dir(Path::new(&*path), conf, query_params.shows_hidden(conf)).ok()?;
+    Some((dir, archive_method))
+}
+
+async fn serve_archive(
+    req: ServiceRequest,
+    dir: PathBuf,
+    archive_method: ArchiveMethod,
+) -> actix_web::Result<ServiceResponse> {
+    let (req, _) = req.into_parts();
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    if let Some(refusal) = listing::refuse_archive(&req, conf, archive_method) {
+        return Ok(ServiceResponse::new(req, refusal));
+    }
+
+    let file_name = format!(
+        "{}.{}",
+        dir.file_name().unwrap_or_default().to_string_lossy(),
+        archive_method.extension()
+    );
+    let cache = conf.archive_cache.clone().unwrap();
+    let skip_symlinks = conf.no_symlinks;
+    let throttle = conf.walk_throttle.clone();
+    let settings = listing::extract_query_parameters(&req).archive_settings(conf);
+
+    // Hashing the directory and creating the archive take a while
+    let (file, cache_status) = web::block(move || {
+        let entry_path = cache
+            .entry_path(&dir, archive_method, skip_symlinks, settings.include_hidden)
+            .map_err(|e| ContextualError::IoError("Failed to read the directory".to_string(), e))?;
+        if let Some(file) = cache.lookup(&entry_path) {
+            return Ok((file, "hit"));
+        }
+
+        log::info!(
+            "Creating an archive ({extension}) of {path} in the archive cache...",
+            extension = archive_method.extension(),
+            path = dir.display()
+        );
+        cache.create_archive(
+            &entry_path,
+            archive_method,
+            &dir,
+            skip_symlinks,
+            throttle.as_deref(),
+            &settings,
+            io::sink(),
+        )?;
+        // The archive may already have been evicted if it is bigger than the cache
+        let file = cache.lookup(&entry_path).ok_or_else(|| {
+            ContextualError::ArchiveCreationDetailError(
+                "The archive doesn't fit in the archive cache".to_string(),
+            )
+        })?;
+        Ok::<_, ContextualError>((file, "miss"))
+    })
+    .await
+    .map_err(|e| {
+        ContextualError::IoError(
+            "Failed to create the archive".to_string(),
+            io::Error::other(e),
+        )
+    })??;
+
+    let mut res = archive_response(file, &file_name, archive_method, &req)?;
+    res.headers_mut().insert(
+        "X-Archive-Cache".parse().unwrap(),
+        cache_status.parse().unwrap(),
+    );
+    Ok(ServiceResponse::new(req, res))
+}
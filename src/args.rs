@@ -1,12 +1,23 @@
 use std::net::IpAddr;
 use std::path::PathBuf;
 
+use bytesize::ByteSize;
 use clap::{Parser, ValueEnum, ValueHint};
 use http::header::{HeaderMap, HeaderName, HeaderValue};
+use regex::Regex;
 
+use crate::archive::ZipCompression;
 use crate::auth;
+use crate::cache_control;
+use crate::case_collisions::CaseCollisions;
+use crate::csp;
 use crate::errors::ContextualError;
+use crate::etag::EtagStrength;
+use crate::file_upload::OnDuplicate;
+use crate::listing::GroupDirs;
+use crate::number_format::NumberFormat;
 use crate::renderer::ThemeSlug;
+use crate::sitemap;
 
 #[derive(ValueEnum, Clone)]
 pub enum MediaType {
@@ -26,6 +37,14 @@ pub struct CliArgs {
     #[arg(value_hint = ValueHint::AnyPath)]
     pub path: Option<PathBuf>,
 
+    /// Read options from this TOML file
+    ///
+    /// Its keys are the long names of the options, and "path" for the path to serve (e.g.
+    /// `port = 8080`, `upload-files = true`, `auth = ["joe:123", "bob:456"]`). Options given on
+    /// the command line override those of the file, and unknown keys are refused.
+    #[arg(long = "config", value_name = "path", value_hint = ValueHint::FilePath)]
+    pub config: Option<PathBuf>,
+
     /// The name of a directory index file to serve, like "index.html"
     ///
     /// Normally, when miniserve serves a directory, it creates a listing for that directory.
@@ -33,6 +52,18 @@ pub struct CliArgs {
     #[arg(long, value_hint = ValueHint::FilePath)]
     pub index: Option<PathBuf>,
 
+    /// Serve the files of a git repository as of this ref (branch, tag, commit id, ...)
+    ///
+    /// The served path must be the root of the repository. Its files are extracted once on
+    /// startup, so changes to the working directory are not visible, and served read-only.
+    #[cfg(feature = "git")]
+    #[arg(
+        long = "git-ref",
+        value_name = "ref",
+        conflicts_with_all = ["allowed_upload_dir", "delete_enabled"]
+    )]
+    pub git_ref: Option<String>,
+
     /// Activate SPA (Single Page Application) mode
     ///
     /// This will cause the file given by --index to be served for all non-existing file paths. In
@@ -54,12 +85,64 @@ pub struct CliArgs {
     )]
     pub interfaces: Vec<IpAddr>,
 
+    /// Only answer requests addressed to these hosts, to protect against DNS rebinding
+    ///
+    /// "localhost" and the addresses listened on are always allowed. Requests with another Host
+    /// header, including IP addresses not listened on, are refused with 400 Bad Request.
+    #[arg(
+        long = "allowed-hosts",
+        value_name = "host",
+        num_args(1..),
+        value_delimiter(',')
+    )]
+    pub allowed_hosts: Vec<String>,
+
+    /// Listen on a Unix domain socket at this path, e.g. behind a reverse proxy
+    ///
+    /// TCP is then only listened on for the interfaces given with --interfaces. The socket serves
+    /// plain HTTP, and is removed when miniserve stops.
+    #[cfg(unix)]
+    #[arg(long = "unix-socket", value_hint = ValueHint::FilePath, value_name = "path")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Permissions of the Unix domain socket, in octal
+    #[cfg(unix)]
+    #[arg(
+        long = "unix-socket-mode",
+        value_name = "mode",
+        default_value = "660",
+        value_parser(parse_mode),
+        requires = "unix_socket"
+    )]
+    pub unix_socket_mode: u32,
+
     /// Set authentication. Currently supported formats:
     /// username:password, username:sha256:hash, username:sha512:hash
     /// (e.g. joe:123, joe:sha256:a665a45920422f9d417e4867efdc4fb8a04a1f3fff1fa07e998e86f7f7a27ae3)
     #[arg(short = 'a', long = "auth", value_parser(parse_auth), num_args(1))]
     pub auth: Vec<auth::RequiredAuth>,
 
+    /// Let requests without credentials through, only showing them matching files
+    ///
+    /// Takes a regular expression matched against the path of files relative to the served
+    /// directory, e.g. '\.pdf$'. Can be provided multiple times. Directories are always listed,
+    /// uploads and archives still require credentials.
+    #[arg(
+        long = "anonymous-allow",
+        requires = "auth",
+        value_parser(parse_regex),
+        num_args(1)
+    )]
+    pub anonymous_allow: Vec<Regex>,
+
+    /// Require the credentials listed in `.miniserve-auth` files to access their directories
+    ///
+    /// Each line of such a file is a credential in the same format as --auth, with a hashed
+    /// password (e.g. username:sha256:hash). They apply to the directory of the file and its
+    /// descendants, on top of --auth. The files themselves are never served.
+    #[arg(long = "auth-files")]
+    pub auth_files: bool,
+
     /// Use a specific route prefix
     #[arg(long = "route-prefix")]
     pub route_prefix: Option<String>,
@@ -72,10 +155,49 @@ pub struct CliArgs {
     #[arg(short = 'P', long = "no-symlinks")]
     pub no_symlinks: bool,
 
-    /// Show hidden files
-    #[arg(short = 'H', long = "hidden")]
+    /// Show hidden files, whose name starts with a dot
+    ///
+    /// They are otherwise left out of listings and archives, and cannot be downloaded. Requests
+    /// can override this with `?hidden=true` or `?hidden=false`, which links of listings keep.
+    /// Auth files and sensitive files stay refused with --auth-files and --hide-sensitive.
+    #[arg(short = 'H', long = "hidden", visible_alias = "show-hidden")]
     pub hidden: bool,
 
+    /// Hide files and directories which usually hold secrets (.env, .git, .ssh, id_rsa, ...)
+    ///
+    /// They are left out of listings and answered with 404 Not Found, and archives of directories
+    /// containing them are refused. Without this option, a warning is logged at startup for
+    /// each of them found near the root of the served directory.
+    #[arg(long = "hide-sensitive")]
+    pub hide_sensitive: bool,
+
+    /// Hide files and directories matching this glob, relative to the served directory
+    ///
+    /// As in .gitignore files, patterns without a slash match names at any depth (e.g. "*.log" or
+    /// "node_modules"), while the other ones match from the served directory (e.g. "docs/*.md").
+    /// `*` doesn't match slashes, unlike `**`. Excluded paths and their content are left out of
+    /// listings and archives, and answered with 404 Not Found.
+    ///
+    /// This option can be provided multiple times.
+    #[arg(long = "exclude", value_name = "glob")]
+    pub exclude: Vec<String>,
+
+    /// Read the title, color scheme and columns of the listing of directories from the
+    /// .miniserve.toml file in them, if any
+    ///
+    /// For example, `title = "Photos"`, `theme = "monokai"` and `columns = ["date"]` (out of
+    /// "size" and "date") override the global settings in the listing of the directory holding the
+    /// file, but not in its subdirectories.
+    #[arg(long = "dir-config")]
+    pub dir_config: bool,
+
+    /// Serve bare listings without any CSS, JavaScript or icons, for very limited browsers
+    ///
+    /// Every listing is then rendered as if ?raw=true was requested: a plain table of links,
+    /// sizes and dates.
+    #[arg(long = "minimal")]
+    pub minimal: bool,
+
     /// Default color scheme
     #[arg(
         short = 'c',
@@ -119,10 +241,89 @@ pub struct CliArgs {
     )]
     pub media_type_raw: Option<String>,
 
-    /// Enable overriding existing files during file upload
+    /// Maximum total size of uploaded files (e.g. 10GiB)
+    ///
+    /// The size of all uploads is tracked in a .miniserve-upload-quota file at the root of the
+    /// served directory, which is neither listed nor served, and can't be uploaded or deleted.
+    /// Uploads which would exceed the quota are rejected with 507 Insufficient Storage. Deleting
+    /// files with --rm-files gives their size back to the quota.
+    #[arg(
+        long = "upload-quota",
+        value_name = "size",
+        requires = "allowed_upload_dir"
+    )]
+    pub upload_quota: Option<ByteSize>,
+
+    /// Maximum number of uploads a single client can have in progress at the same time
+    ///
+    /// Further uploads from the same IP address are answered with 429 Too Many Requests.
+    #[arg(
+        long = "upload-concurrency",
+        value_name = "count",
+        requires = "allowed_upload_dir"
+    )]
+    pub upload_concurrency: Option<usize>,
+
+    /// Maximum upload bandwidth of a single client, per second (e.g. 10MiB)
+    ///
+    /// Uploads from the same IP address share this bandwidth, and are slowed down when exceeding
+    /// it.
+    #[arg(
+        long = "upload-rate-per-ip",
+        value_name = "size",
+        value_parser(parse_rate),
+        requires = "allowed_upload_dir"
+    )]
+    pub upload_rate_per_ip: Option<ByteSize>,
+
+    /// Maximum size of the body of a single upload request (e.g. 10M, 1G)
+    ///
+    /// Larger uploads are rejected with 413 Payload Too Large, as soon as their Content-Length
+    /// header exceeds the limit or as soon as the received data does.
+    #[arg(
+        long = "max-upload-size",
+        value_name = "size",
+        requires = "allowed_upload_dir"
+    )]
+    pub max_upload_size: Option<ByteSize>,
+
+    /// Enable deleting files and directories, several at once by selecting them in listings
+    #[arg(short = 'R', long = "rm-files")]
+    pub delete_enabled: bool,
+
+    /// Move deleted files to this directory instead of removing them
+    ///
+    /// Deleted files keep their path relative to the served directory, in a folder named after
+    /// the time of their deletion. The directory must be outside of the served directory, on the
+    /// same filesystem.
+    #[arg(
+        long = "trash-dir",
+        value_hint = ValueHint::DirPath,
+        value_name = "dir",
+        requires = "delete_enabled"
+    )]
+    pub trash_dir: Option<PathBuf>,
+
+    /// Remove deleted files from the trash after this many seconds
+    #[arg(long = "trash-max-age", value_name = "seconds", requires = "trash_dir")]
+    pub trash_max_age: Option<u64>,
+
+    /// Enable overriding existing files during file upload, as with --on-duplicate overwrite
     #[arg(short = 'o', long = "overwrite-files")]
     pub overwrite_files: bool,
 
+    /// What to do when uploading a file whose name is already taken
+    ///
+    /// Files are renamed by appending a number, as in "file (1).txt". Otherwise, uploads are
+    /// refused with 409 Conflict, unless --overwrite-files is given.
+    #[arg(
+        long = "on-duplicate",
+        value_name = "action",
+        conflicts_with = "overwrite_files",
+        ignore_case = true
+    )]
+    pub on_duplicate: Option<OnDuplicate>,
+
     /// Enable uncompressed tar archive generation
     #[arg(short = 'r', long = "enable-tar")]
     pub enable_tar: bool,
@@ -131,6 +332,19 @@ pub struct CliArgs {
     #[arg(short = 'g', long = "enable-tar-gz")]
     pub enable_tar_gz: bool,
 
+    /// Enable zstd-compressed tar archive generation
+    #[arg(long = "enable-tar-zst")]
+    pub enable_tar_zst: bool,
+
+    /// Compression level of zstd-compressed tar archives, from 1 (fastest) to 22 (smallest)
+    #[arg(
+        long = "tar-zst-level",
+        value_name = "level",
+        default_value = "3",
+        value_parser = clap::value_parser!(i32).range(1..=22)
+    )]
+    pub tar_zst_level: i32,
+
     /// Enable zip archive generation
     ///
     /// WARNING: Zipping large directories can result in out-of-memory exception
@@ -138,10 +352,130 @@ pub struct CliArgs {
     #[arg(short = 'z', long = "enable-zip")]
     pub enable_zip: bool,
 
+    /// Compression of the files in zip archives: "store" or "deflate:LEVEL"
+    ///
+    /// Storing is the fastest, and best for directories of already compressed files such as
+    /// photos or videos. Deflate levels go from 0 (fastest) to 9 (smallest), higher levels being
+    /// lowered to 9.
+    #[arg(
+        long = "zip-compression",
+        value_name = "method",
+        default_value = "store",
+        value_parser = parse_zip_compression
+    )]
+    pub zip_compression: ZipCompression,
+
+    /// Deflate files which are already compressed, such as photos, videos or archives, too
+    ///
+    /// By default, such files are stored as is in zip archives with deflate compression, as
+    /// deflating them takes time for next to no gain. They are recognized by their extension or
+    /// the signature of their format.
+    #[arg(long = "zip-deflate-compressed")]
+    pub zip_deflate_compressed: bool,
+
+    /// Keep the Unicode normalization of file names in archive entry names
+    ///
+    /// By default, entry names are normalized to NFC, so that names with decomposed characters,
+    /// such as those written by macOS, are extracted the same on all platforms.
+    #[arg(long = "no-archive-nfc")]
+    pub no_archive_nfc: bool,
+
+    /// Maximum number of directory levels walked below the requested directory by archives,
+    /// recursive listings, /recent, the feed and the sitemap
+    ///
+    /// Files deeper than this are left out, and listings of directories with subdirectories
+    /// mention it. With 1, only the files of the requested directory are included.
+    #[arg(
+        long = "max-depth",
+        value_name = "levels",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub max_depth: Option<u32>,
+
+    /// Maximum number of archives a single client can download per minute
+    ///
+    /// Further archive requests from the same IP address are answered with 429 Too Many Requests.
+    #[arg(long = "archive-rate-limit", value_name = "count")]
+    pub archive_rate_limit: Option<usize>,
+
+    /// Maximum number of files and directories accessed per second when walking directories
+    ///
+    /// This applies to the creation of archives and to recursive walks (feed, URL listings), so
+    /// that walking large trees doesn't saturate the disk at the expense of other requests. The
+    /// limit is shared by all walks in progress.
+    #[arg(
+        long = "walk-throttle",
+        value_name = "operations per second",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub walk_throttle: Option<u32>,
+
+    /// Maximum bandwidth of all responses together, per second (e.g. 10MiB)
+    ///
+    /// Concurrent downloads share this bandwidth fairly, so that the server never exceeds it.
+    #[arg(long = "max-total-rate", value_name = "size", value_parser(parse_rate))]
+    pub max_total_rate: Option<ByteSize>,
+
+    /// Compress responses with gzip, brotli or zstd, as negotiated with the Accept-Encoding header
+    ///
+    /// Images, videos, audio files and archives, which are already compressed, are sent as is.
+    #[arg(long = "compress")]
+    pub compress: bool,
+
+    /// Keep generated archives in this directory and serve them again while the archived
+    /// directory is unchanged
+    ///
+    /// Cached archives are only reused as long as none of the archived files were added,
+    /// removed or modified.
+    #[arg(long = "archive-cache", value_hint = ValueHint::DirPath, value_name = "dir")]
+    pub archive_cache: Option<PathBuf>,
+
+    /// Maximum total size of the archive cache (e.g. 10GiB)
+    ///
+    /// The least recently used archives are removed first.
+    #[arg(
+        long = "archive-cache-max-size",
+        value_name = "size",
+        default_value = "1GiB",
+        requires = "archive_cache"
+    )]
+    pub archive_cache_max_size: ByteSize,
+
+    /// Remove cached archives which have not been used for this many seconds
+    #[arg(
+        long = "archive-cache-max-age",
+        value_name = "seconds",
+        requires = "archive_cache"
+    )]
+    pub archive_cache_max_age: Option<u64>,
+
+    /// Allow generating archives in the background, into the archive cache
+    ///
+    /// POST /archive-jobs?path=<dir>&download=<format> starts a job, whose status and progress
+    /// are then available at /archive-jobs/<id>, and the archive at /archive-jobs/<id>/download
+    /// once done. This avoids timeouts of proxies while very large archives are generated.
+    #[arg(long = "archive-jobs", requires = "archive_cache")]
+    pub archive_jobs: bool,
+
+    /// Create archives in the archive cache before sending them, as files
+    ///
+    /// Their size is then known and range requests are supported, so that interrupted downloads
+    /// can be resumed. The download only starts once the whole archive has been created.
+    #[arg(long = "archive-ranges", requires = "archive_cache")]
+    pub archive_ranges: bool,
+
     /// List directories first
-    #[arg(short = 'D', long = "dirs-first")]
+    ///
+    /// This is a shorthand for --group-dirs first.
+    #[arg(short = 'D', long = "dirs-first", conflicts_with = "group_dirs")]
     pub dirs_first: bool,
 
+    /// Group directories before or after files, or interleave them with files
+    ///
+    /// This is applied after sorting and can be overridden per request with ?group=.
+    #[arg(long = "group-dirs", default_value = "none", ignore_case = true)]
+    pub group_dirs: GroupDirs,
+
     /// Shown instead of host in page title and heading
     #[arg(short = 't', long = "title")]
     pub title: Option<String>,
@@ -150,10 +484,66 @@ pub struct CliArgs {
     #[arg(long = "header", value_parser(parse_header), num_args(1))]
     pub header: Vec<HeaderMap>,
 
+    /// Send a `Content-Security-Policy` header with HTML files, to neutralize their scripts
+    ///
+    /// Without a value, scripts, plugins and forms are blocked while other resources may still be
+    /// loaded from the server. Pages generated by miniserve, like directory listings, are left
+    /// alone. Example: --csp "default-src 'self'; script-src 'self'"
+    #[arg(
+        long = "csp",
+        value_name = "policy",
+        value_parser(csp::parse_policy),
+        num_args(0..=1),
+        default_missing_value = csp::DEFAULT_POLICY
+    )]
+    pub csp: Option<HeaderValue>,
+
+    /// Set a custom value for the Server header of responses
+    #[arg(long = "server-header", conflicts_with = "no_server_header")]
+    pub server_header: Option<String>,
+
+    /// Omit the Server header from responses
+    #[arg(long = "no-server-header")]
+    pub no_server_header: bool,
+
+    /// Send `Cache-Control: max-age=<seconds>` with files
+    ///
+    /// Directory listings are sent with `Cache-Control: no-cache` as soon as a caching policy is
+    /// set, so that clients always revalidate them.
+    #[arg(long = "cache-max-age", value_name = "seconds")]
+    pub cache_max_age: Option<u64>,
+
+    /// Set the `Cache-Control` header of files with a given extension
+    ///
+    /// Example: --cache "jpg=max-age=86400" --cache "html=no-cache"
+    #[arg(
+        long = "cache",
+        value_name = "extension=policy",
+        value_parser(cache_control::parse_cache_policy),
+        num_args(1)
+    )]
+    pub cache: Vec<(String, HeaderValue)>,
+
     /// Visualize symlinks in directory listing
     #[arg(short = 'l', long = "show-symlink-info")]
     pub show_symlink_info: bool,
 
+    /// Show the size of files as bars, relative to the largest file of the directory
+    #[arg(long = "size-bars")]
+    pub size_bars: bool,
+
+    /// Number of threads fetching the metadata of entries when listing a directory
+    ///
+    /// Listing large directories on network filesystems (NFS, SMB, sshfs, ...) is dominated by the
+    /// latency of each `stat`, which can then be overlapped. 1 fetches them one after the other.
+    #[arg(
+        long = "listing-concurrency",
+        value_name = "threads",
+        default_value = "1",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub listing_concurrency: u16,
+
     /// Hide version footer
     #[arg(short = 'F', long = "hide-version-footer")]
     pub hide_version_footer: bool,
@@ -166,6 +556,11 @@ pub struct CliArgs {
     #[arg(short = 'W', long = "show-wget-footer")]
     pub show_wget_footer: bool,
 
+    /// Show the total, used and available space of the filesystem of the served directory in
+    /// the footer of listings
+    #[arg(long = "show-disk-usage")]
+    pub show_disk_usage: bool,
+
     /// Generate completion file for a shell
     #[arg(long = "print-completions", value_name = "shell")]
     pub print_completions: Option<clap_complete::Shell>,
@@ -184,6 +579,181 @@ pub struct CliArgs {
     #[arg(long = "tls-key", requires = "tls_cert", value_hint = ValueHint::FilePath)]
     pub tls_key: Option<PathBuf>,
 
+    /// Also listen for plain HTTP on this port and redirect every request to HTTPS
+    ///
+    /// The path and query of the request are kept in the redirection.
+    #[cfg(feature = "tls")]
+    #[arg(long = "redirect-http", value_name = "port", requires = "tls_cert")]
+    pub redirect_http: Option<u16>,
+
+    /// Serve an Atom feed of the most recently modified files at /feed.xml
+    ///
+    /// Optionally takes the maximum number of entries in the feed (defaults to 20).
+    #[arg(
+        long = "feed",
+        value_name = "entries",
+        num_args(0..=1),
+        default_missing_value = "20"
+    )]
+    pub feed: Option<usize>,
+
+    /// Serve a page listing the most recently modified files of the whole tree at /recent
+    ///
+    /// Optionally takes the maximum number of files listed (defaults to 20).
+    #[arg(
+        long = "recent",
+        value_name = "entries",
+        num_args(0..=1),
+        default_missing_value = "20"
+    )]
+    pub recent: Option<usize>,
+
+    /// Serve a sitemap of the files at /sitemap.xml, for search engines
+    ///
+    /// Optionally takes the maximum number of files in the sitemap (defaults to 50000, the limit
+    /// of the protocol). Hidden files and symlinks are left out unless they are shown.
+    #[arg(
+        long = "sitemap",
+        value_name = "entries",
+        num_args(0..=1),
+        default_missing_value = "50000",
+        value_parser = clap::value_parser!(u64).range(1..=sitemap::MAX_SITEMAP_ENTRIES as u64)
+    )]
+    pub sitemap: Option<u64>,
+
+    /// Serve this file at /robots.txt, at the root of the server whatever the route prefix
+    #[arg(long = "robots-file", value_name = "path", value_hint = ValueHint::FilePath)]
+    pub robots_file: Option<PathBuf>,
+
+    /// Let nginx send files: respond with an X-Accel-Redirect header pointing to the file under
+    /// this internal location instead of the content of the file
+    ///
+    /// Authentication and access checks are still done by miniserve.
+    #[arg(
+        long = "x-accel-redirect",
+        value_name = "location",
+        conflicts_with = "x_sendfile"
+    )]
+    pub x_accel_redirect: Option<String>,
+
+    /// Let Apache or lighttpd send files: respond with an X-Sendfile header holding the absolute
+    /// path of the file instead of its content
+    ///
+    /// Authentication and access checks are still done by miniserve.
+    #[arg(long = "x-sendfile")]
+    pub x_sendfile: bool,
+
+    /// Check the served directory for names which only differ by case at startup
+    ///
+    /// Such files can't coexist on case-insensitive filesystems (as on Windows and macOS), where
+    /// one shadows the other. With "warn", each collision is logged; with "deny", miniserve
+    /// refuses to start.
+    #[arg(long = "case-collisions", value_name = "action", value_enum)]
+    pub case_collisions: Option<CaseCollisions>,
+
+    /// Time in seconds a client has to send the headers of a request once connected, before the
+    /// connection is dropped
+    ///
+    /// This is separate from the keep-alive between requests. 0 disables the timeout.
+    #[arg(long = "client-timeout", value_name = "seconds", default_value = "5")]
+    pub client_timeout: u64,
+
+    /// Time in seconds given to a client to acknowledge the closing of a connection, before it is
+    /// dropped
+    ///
+    /// 0 disables the timeout.
+    #[arg(
+        long = "client-disconnect",
+        value_name = "seconds",
+        default_value = "1"
+    )]
+    pub client_disconnect: u64,
+
+    /// Serve an OpenAPI description of the HTTP API at /openapi.json
+    ///
+    /// It only describes the features enabled by the other options.
+    #[arg(long = "openapi")]
+    pub openapi: bool,
+
+    /// Send all error responses as JSON objects, of the form {"error": "...", "code": 404}
+    ///
+    /// This replaces the HTML error pages, whatever the Accept header of the request, so that
+    /// scripted clients can always parse errors.
+    #[arg(long = "api-errors")]
+    pub api_errors: bool,
+
+    /// Allow clients to request the SHA-256 checksums of files in JSON listings, with
+    /// `?checksums=1`, and the MD5, SHA-1 or SHA-256 checksum of a single file, with
+    /// `?checksum=md5|sha1|sha256`
+    ///
+    /// Checksums in JSON listings are cached until the file changes. Since hashing big files takes
+    /// a while, JSON listings with checksums are streamed as they are computed.
+    #[arg(long = "checksums")]
+    pub checksums: bool,
+
+    /// Use a hash of their content as ETag for files up to this size (e.g. 512KiB)
+    ///
+    /// Bigger files keep the ETag derived from their modification date and size. Hashes are
+    /// cached until the file changes.
+    #[arg(long = "content-etag", value_name = "max-size")]
+    pub content_etag: Option<ByteSize>,
+
+    /// Strength of the ETags of files
+    ///
+    /// With "weak", ETags derived from the modification date and size of files are marked as
+    /// weak (W/"..."), which is enough to revalidate cached files. Content ETags (see
+    /// --content-etag) stay strong. Since If-Range requires a strong match, a range request whose
+    /// If-Range holds a weak ETag is answered with the whole file.
+    #[arg(
+        long = "etag-strength",
+        value_name = "strength",
+        value_enum,
+        default_value = "strong"
+    )]
+    pub etag_strength: EtagStrength,
+
+    /// Separators of thousands and decimals in the sizes shown in listings
+    ///
+    /// The exact size of files in bytes is shown when hovering their size.
+    #[arg(
+        long = "number-format",
+        value_name = "format",
+        value_enum,
+        default_value = "plain"
+    )]
+    pub number_format: NumberFormat,
+
+    /// Show the modification dates in listings in UTC rather than in the time zone of the server
+    #[arg(long = "tz-utc")]
+    pub tz_utc: bool,
+
+    /// Render Markdown files as HTML when viewed in the browser
+    ///
+    /// The original Markdown source can still be downloaded by appending ?raw=true to the URL.
+    #[arg(long = "render-markdown")]
+    pub render_markdown: bool,
+
+    /// Serve an HTML view of CSV, JSON and Markdown files to clients preferring HTML
+    ///
+    /// Browsers get a table for CSV files, pretty-printed JSON and rendered Markdown, while other
+    /// clients get the file as is, based on the Accept header. ?raw=true always gets the file.
+    #[arg(long = "negotiate-content")]
+    pub negotiate_content: bool,
+
+    /// Send a Strict-Transport-Security header on responses
+    ///
+    /// This only has an effect when serving over TLS. It is ignored with a warning otherwise.
+    #[arg(long = "hsts")]
+    pub hsts: bool,
+
+    /// Value of the max-age directive of the Strict-Transport-Security header, in seconds
+    #[arg(long = "hsts-max-age", default_value = "31536000", requires = "hsts")]
+    pub hsts_max_age: u64,
+
+    /// Add the includeSubDomains directive to the Strict-Transport-Security header
+    #[arg(long = "hsts-include-subdomains", requires = "hsts")]
+    pub hsts_include_subdomains: bool,
+
     /// Enable README.md rendering in directories
     #[arg(long)]
     pub readme: bool,
@@ -194,8 +764,45 @@ fn parse_interface(src: &str) -> Result<IpAddr, std::net::AddrParseError> {
     src.parse::<IpAddr>()
 }
 
+/// Parses file permissions given in octal
+#[cfg(unix)]
+fn parse_mode(src: &str) -> Result<u32, String> {
+    match u32::from_str_radix(src, 8) {
+        Ok(mode) if mode <= 0o777 => Ok(mode),
+        _ => Err("expected octal permissions such as 660".to_string()),
+    }
+}
+
+/// Checks whether a regular expression is valid
+fn parse_regex(src: &str) -> Result<Regex, regex::Error> {
+    Regex::new(src)
+}
+
+/// Parses a rate in bytes per second, which can't be zero
+fn parse_rate(src: &str) -> Result<ByteSize, String> {
+    match src.parse::<ByteSize>()? {
+        ByteSize(0) => Err("rate must be greater than zero".to_string()),
+        rate => Ok(rate),
+    }
+}
+
+/// Parses the compression of zip archives, clamping deflate levels to the valid range
+fn parse_zip_compression(src: &str) -> Result<ZipCompression, String> {
+    let invalid = || "expected \"store\" or \"deflate:LEVEL\"".to_string();
+    match src.split_once(':') {
+        None if src == "store" => Ok(ZipCompression::Store),
+        Some(("deflate", level)) => {
+            let level = level.parse::<i32>().map_err(|_| invalid())?;
+            Ok(ZipCompression::Deflate(
+                level.clamp(0, ZipCompression::MAX_DEFLATE_LEVEL),
+            ))
+        }
+        _ => Err(invalid()),
+    }
+}
+
 /// Parse authentication requirement
-fn parse_auth(src: &str) -> Result<auth::RequiredAuth, ContextualError> {
+pub fn parse_auth(src: &str) -> Result<auth::RequiredAuth, ContextualError> {
     let mut split = src.splitn(3, ':');
     let invalid_auth_format = Err(ContextualError::InvalidAuthFormat);
 
@@ -316,4 +923,19 @@ mod tests {
         let err = parse_auth(auth_string).unwrap_err();
         assert_eq!(format!("{err}"), err_msg.to_owned());
     }
+
+    #[rstest(
+        src, expected,
+        case("store", Some(ZipCompression::Store)),
+        case("deflate:6", Some(ZipCompression::Deflate(6))),
+        case("deflate:42", Some(ZipCompression::Deflate(9))),
+        case("deflate:-1", Some(ZipCompression::Deflate(0))),
+        case("deflate", None),
+        case("deflate:best", None),
+        case("store:1", None),
+        case("bzip2:9", None),
+    )]
+    fn parse_zip_compression_values(src: &str, expected: Option<ZipCompression>) {
+        assert_eq!(parse_zip_compression(src).ok(), expected);
+    }
 }
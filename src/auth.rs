@@ -1,8 +1,14 @@
-use actix_web::{dev::ServiceRequest, HttpMessage};
+use std::path::Path;
+
+use actix_web::{dev::ServiceRequest, http::Method, HttpMessage};
 use actix_web_httpauth::extractors::basic::BasicAuth;
+use percent_encoding::percent_decode_str;
 use sha2::{Digest, Sha256, Sha512};
 
+use crate::auth_files;
 use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::MiniserveConfig;
 
 #[derive(Clone, Debug)]
 /// HTTP Basic authentication parameters
@@ -47,7 +53,9 @@ pub fn match_auth(basic_auth: &BasicAuthParams, required_auth: &[RequiredAuth])
 /// Return `true` if `basic_auth_pwd` meets `required_auth_pwd`'s requirement
 pub fn compare_password(basic_auth_pwd: &str, required_auth_pwd: &RequiredAuthPassword) -> bool {
     match &required_auth_pwd {
-        RequiredAuthPassword::Plain(required_password) => *basic_auth_pwd == *required_password,
+        RequiredAuthPassword::Plain(required_password) => {
+            constant_time_eq(basic_auth_pwd.as_bytes(), required_password.as_bytes())
+        }
         RequiredAuthPassword::Sha256(password_hash) => {
             compare_hash::<Sha256>(basic_auth_pwd, password_hash)
         }
@@ -59,7 +67,14 @@ pub fn compare_password(basic_auth_pwd: &str, required_auth_pwd: &RequiredAuthPa
 
 /// Return `true` if hashing of `password` by `T` algorithm equals to `hash`
 pub fn compare_hash<T: Digest>(password: &str, hash: &[u8]) -> bool {
-    get_hash::<T>(password) == hash
+    constant_time_eq(&get_hash::<T>(password), hash)
+}
+
+/// Return `true` if `a` equals `b`, in a time which doesn't depend on where they differ
+///
+/// This keeps the time taken to reject a password from revealing how much of it was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
 }
 
 /// Get hash of a `text`
@@ -73,23 +88,79 @@ pub struct CurrentUser {
     pub name: String,
 }
 
+/// Return `true` if the request is let through without credentials, which is only possible
+/// when `--anonymous-allow` is in use
+pub fn is_anonymous(conf: &MiniserveConfig, current_user: Option<&CurrentUser>) -> bool {
+    !conf.auth.is_empty() && current_user.is_none()
+}
+
+/// Return `true` if anonymous users may see the file at `path`, relative to the served directory
+pub fn anonymous_allowed(conf: &MiniserveConfig, path: &Path) -> bool {
+    let path = path
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    conf.anonymous_allow.iter().any(|re| re.is_match(&path))
+}
+
 pub async fn handle_auth(
     req: ServiceRequest,
-    cred: BasicAuth,
+    cred: Option<BasicAuth>,
 ) -> actix_web::Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
-    let required_auth = &req.app_data::<crate::MiniserveConfig>().unwrap().auth;
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+
+    let cred = match cred {
+        Some(cred) => cred,
+        None if !conf.anonymous_allow.is_empty() => return handle_anonymous(req),
+        None => return Err((ContextualError::InvalidHttpCredentials.into(), req)),
+    };
 
     req.extensions_mut().insert(CurrentUser {
         name: cred.user_id().to_string(),
     });
 
-    if match_auth(&cred.into(), required_auth) {
+    if match_auth(&cred.into(), &conf.auth) {
         Ok(req)
     } else {
         Err((ContextualError::InvalidHttpCredentials.into(), req))
     }
 }
 
+/// Let through read-only requests without credentials, as long as they don't target a file
+/// hidden from anonymous users.
+///
+/// Directory listings are filtered later on, and archives are never available anonymously since
+/// they would contain hidden files.
+fn handle_anonymous(
+    req: ServiceRequest,
+) -> actix_web::Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+
+    let path = req.path();
+    let path = path.strip_prefix(&conf.route_prefix).unwrap_or(path);
+
+    let read_only = req.method() == Method::GET || req.method() == Method::HEAD;
+    // Archives are requested with `?download=`, from `/download` or as background jobs
+    let (_, wants_archive) = auth_files::request_target(&req, conf);
+    if !read_only || wants_archive || path.starts_with("/archive-jobs") {
+        return Err((ContextualError::InvalidHttpCredentials.into(), req));
+    }
+
+    let path = percent_decode_str(path).decode_utf8_lossy();
+    let hidden_file = match sanitize_path(Path::new(&*path), true) {
+        Some(path) => conf.path.join(&path).is_file() && !anonymous_allowed(conf, &path),
+        None => true,
+    };
+
+    if hidden_file {
+        let err = ContextualError::RouteNotFoundError(req.path().to_string());
+        return Err((err.into(), req));
+    }
+
+    Ok(req)
+}
+
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests {
@@ -118,6 +189,18 @@ mod tests {
         assert_eq!(received, expected);
     }
 
+    #[rstest(
+        a, b, expected,
+        case(b"password", b"password", true),
+        case(b"password", b"passwore", false),
+        case(b"password", b"Password", false),
+        case(b"password", b"pass", false),
+        case(b"", b"", true),
+    )]
+    fn test_constant_time_eq(a: &[u8], b: &[u8], expected: bool) {
+        assert_eq!(constant_time_eq(a, b), expected);
+    }
+
     /// Helper function that creates a `RequiredAuth` structure and encrypt `password` if necessary
     fn create_required_auth(username: &str, password: &str, encrypt: &str) -> RequiredAuth {
         use RequiredAuthPassword::*;
@@ -0,0 +1,221 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{header::Header, Method},
+    HttpMessage,
+};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use futures::prelude::*;
+use percent_encoding::percent_decode_str;
+
+use crate::args::parse_auth;
+use crate::auth::{match_auth, BasicAuthParams, RequiredAuth, RequiredAuthPassword};
+use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::listing::{self, ArchiveQuery};
+use crate::MiniserveConfig;
+
+/// Name of the files listing the credentials required to access their directory
+pub const AUTH_FILE: &str = ".miniserve-auth";
+
+/// Reads the credentials required by the auth file of `dir`, if it has one
+///
+/// Each non-empty line which isn't a `#` comment holds a credential in the same format as
+/// `--auth`, except that passwords must be hashed since the file lives among the served files.
+fn read_auth_file(dir: &Path) -> Result<Option<Vec<RequiredAuth>>, ContextualError> {
+    let auth_file = dir.join(AUTH_FILE);
+    let content = match fs::read_to_string(&auth_file) {
+        Ok(content) => content,
+        Err(e)
+            if e.kind() == io::ErrorKind::NotFound || e.kind() == io::ErrorKind::NotADirectory =>
+        {
+            return Ok(None)
+        }
+        Err(e) => {
+            return Err(ContextualError::IoError(
+                format!("Failed to read {}", auth_file.display()),
+                e,
+            ))
+        }
+    };
+
+    let parse_error = |e: String| ContextualError::ParseError(auth_file.display().to_string(), e);
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match parse_auth(line) {
+            Ok(RequiredAuth {
+                password: RequiredAuthPassword::Plain(_),
+                ..
+            }) => Err(parse_error("passwords must be hashed".to_string())),
+            Ok(required_auth) => Ok(required_auth),
+            Err(e) => Err(parse_error(e.to_string())),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Returns the directories from `root` down to `root/relative`
+fn directories_along(root: &Path, relative: &Path) -> Vec<PathBuf> {
+    let mut dir = root.to_path_buf();
+    let mut dirs = vec![dir.clone()];
+    for component in relative.components() {
+        dir.push(component);
+        dirs.push(dir.clone());
+    }
+    dirs
+}
+
+/// Checks that `cred` satisfies the auth files of every directory from the served one down to
+/// `relative`, which is relative to the served directory
+///
+/// When `relative` goes through symlinks, the directories along its target are checked as well.
+/// A missing or unknown auth file makes no difference, while an invalid one denies access.
+pub fn authorize(
+    conf: &MiniserveConfig,
+    relative: &Path,
+    cred: Option<&BasicAuthParams>,
+) -> Result<(), ContextualError> {
+    let mut dirs = directories_along(&conf.path, relative);
+    if let (Ok(root), Ok(target)) = (
+        conf.path.canonicalize(),
+        conf.path.join(relative).canonicalize(),
+    ) {
+        if let Ok(canonical_relative) = target.strip_prefix(&root) {
+            if canonical_relative != relative {
+                dirs.extend(directories_along(&root, canonical_relative));
+            }
+        }
+    }
+
+    for dir in dirs {
+        if let Some(required_auth) = read_auth_file(&dir)? {
+            match cred {
+                Some(cred) if match_auth(cred, &required_auth) => (),
+                _ => return Err(ContextualError::InvalidHttpCredentials),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns `true` if a directory below `dir` has an auth file
+///
+/// Symlinked directories are not followed, like when archives are created.
+pub fn has_protected_descendants(dir: &Path) -> bool {
+    let read_dir = match dir.read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(_) => return false,
+    };
+    read_dir.flatten().any(|entry| {
+        entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+            && (entry.path().join(AUTH_FILE).is_file() || has_protected_descendants(&entry.path()))
+    })
+}
+
+/// Middleware enforcing the auth files of the requested directory and its parents, when
+/// `--auth-files` is in use
+///
+/// The credentials are the same as the ones of `--auth`, since a request only carries one set of
+/// them. Uploads, deletions and background archives apply to the directory given as `path`
+/// query parameter. Archives of directories containing protected subdirectories are refused, and
+/// auth files themselves are never served.
+pub fn auth_files_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let result = if conf.auth_files {
+        check_request(&req, conf)
+    } else {
+        Ok(())
+    };
+
+    let fut = result.map(|()| srv.call(req));
+    async move { fut?.await }
+}
+
+/// Returns the path targeted by a request, relative to the served directory but not sanitized,
+/// and whether an archive of it is requested
+///
+/// Uploads, deletions and background archives apply to the directory given as `path` query
+/// parameter rather than to the path of the request.
+pub fn request_target(req: &ServiceRequest, conf: &MiniserveConfig) -> (PathBuf, bool) {
+    let path = req.path();
+    let path = path.strip_prefix(&conf.route_prefix).unwrap_or(path);
+    let query_params = listing::extract_query_parameters(req.request());
+
+    match (req.method(), path) {
+        (&Method::POST, "/upload" | "/delete") => (query_params.path.unwrap_or_default(), false),
+        (&Method::POST, "/archive-jobs") | (&Method::GET, "/download") => {
+            (query_params.path.unwrap_or_default(), true)
+        }
+        // Archive plans list the files archives would include, so they are checked the same way
+        _ => (
+            PathBuf::from(&*percent_decode_str(path).decode_utf8_lossy()),
+            query_params.download.is_some() || query_params.archive == Some(ArchiveQuery::Plan),
+        ),
+    }
+}
+
+fn check_request(req: &ServiceRequest, conf: &MiniserveConfig) -> Result<(), ContextualError> {
+    let (target, wants_archive) = request_target(req, conf);
+    // Invalid paths are rejected later on
+    let relative = match sanitize_path(&target, true) {
+        Some(relative) => relative,
+        None => return Ok(()),
+    };
+
+    if relative.file_name().is_some_and(|name| name == AUTH_FILE) {
+        return Err(ContextualError::RouteNotFoundError(req.path().to_string()));
+    }
+
+    let cred = Authorization::<Basic>::parse(req)
+        .ok()
+        .map(|auth| BasicAuthParams {
+            username: auth.as_ref().user_id().to_string(),
+            password: auth.as_ref().password().unwrap_or_default().to_string(),
+        });
+    authorize(conf, &relative, cred.as_ref())?;
+    if let Some(cred) = cred {
+        // Checked again when deleting files, which may be in subdirectories
+        req.extensions_mut().insert(cred);
+    }
+
+    if wants_archive && has_protected_descendants(&conf.path.join(&relative)) {
+        return Err(ContextualError::ProtectedDirectoryError(format!(
+            "/{}",
+            relative.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", &["/srv"])]
+    #[case("a", &["/srv", "/srv/a"])]
+    #[case("a/b/c.txt", &["/srv", "/srv/a", "/srv/a/b", "/srv/a/b/c.txt"])]
+    fn directories_along_path(#[case] relative: &str, #[case] expected: &[&str]) {
+        let expected = expected.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            directories_along(Path::new("/srv"), Path::new(relative)),
+            expected
+        );
+    }
+}
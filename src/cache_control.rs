@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+};
+use futures::prelude::*;
+
+use crate::etag::requested_file;
+use crate::MiniserveConfig;
+
+/// `Cache-Control` policies applied to responses, set with `--cache-max-age` and `--cache`
+#[derive(Debug, Clone, Default)]
+pub struct CacheControl {
+    /// Policy of files without a more specific one
+    pub default: Option<HeaderValue>,
+
+    /// Policies of files, by lowercase extension
+    pub by_extension: HashMap<String, HeaderValue>,
+}
+
+impl CacheControl {
+    /// Returns the policy for the file named `file_name`, if any
+    fn for_file(&self, file_name: &str) -> Option<&HeaderValue> {
+        file_name
+            .rsplit_once('.')
+            .and_then(|(_, extension)| self.by_extension.get(&extension.to_lowercase()))
+            .or(self.default.as_ref())
+    }
+}
+
+/// Parses a per-extension policy of the form `extension=policy`, e.g. `jpg=max-age=86400`
+pub fn parse_cache_policy(src: &str) -> Result<(String, HeaderValue), String> {
+    let (extension, policy) = src
+        .split_once('=')
+        .ok_or_else(|| "expected <extension>=<policy>".to_string())?;
+    let extension = extension.trim().trim_start_matches('.').to_lowercase();
+    if extension.is_empty() {
+        return Err("the extension must not be empty".to_string());
+    }
+    let policy = HeaderValue::from_str(policy.trim()).map_err(|e| e.to_string())?;
+    Ok((extension, policy))
+}
+
+/// Middleware adding a `Cache-Control` header to successful responses when caching policies are
+/// configured
+///
+/// Files get the policy of their extension or the default one, while directory listings always
+/// get `no-cache` so that clients revalidate them. A `Cache-Control` header already set by
+/// another part of miniserve is kept.
+pub fn cache_control_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let policy = req.app_data::<MiniserveConfig>().and_then(|conf| {
+        let cache_control = conf.cache_control.as_ref()?;
+        let path = requested_file(req.path(), conf)?;
+        if path.is_dir() {
+            Some(HeaderValue::from_static("no-cache"))
+        } else {
+            let file_name = path.file_name()?.to_string_lossy();
+            cache_control.for_file(&file_name).cloned()
+        }
+    });
+
+    let fut = srv.call(req);
+
+    async move {
+        let mut res = fut.await?;
+
+        let cacheable = res.status().is_success() || res.status().as_u16() == 304;
+        if let (Some(policy), true) = (policy, cacheable) {
+            if !res.headers().contains_key(header::CACHE_CONTROL) {
+                res.headers_mut().insert(header::CACHE_CONTROL, policy);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("jpg=max-age=86400", "jpg", "max-age=86400")]
+    #[case(".JPG=no-store", "jpg", "no-store")]
+    #[case("css = public, max-age=60", "css", "public, max-age=60")]
+    fn test_parse_cache_policy(#[case] src: &str, #[case] extension: &str, #[case] policy: &str) {
+        assert_eq!(
+            parse_cache_policy(src).unwrap(),
+            (
+                extension.to_string(),
+                HeaderValue::from_str(policy).unwrap()
+            )
+        );
+    }
+
+    #[rstest]
+    #[case("jpg")]
+    #[case("=max-age=60")]
+    fn test_parse_invalid_cache_policy(#[case] src: &str) {
+        assert!(parse_cache_policy(src).is_err());
+    }
+
+    #[test]
+    fn test_policy_for_file() {
+        let cache_control = CacheControl {
+            default: Some(HeaderValue::from_static("max-age=60")),
+            by_extension: HashMap::from([(
+                "jpg".to_string(),
+                HeaderValue::from_static("max-age=86400"),
+            )]),
+        };
+        assert_eq!(
+            cache_control.for_file("photo.JPG").unwrap(),
+            "max-age=86400"
+        );
+        assert_eq!(cache_control.for_file("notes.txt").unwrap(), "max-age=60");
+        assert_eq!(cache_control.for_file("README").unwrap(), "max-age=60");
+    }
+}
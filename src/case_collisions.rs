@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+/// What to do when the served directory contains names which only differ by case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CaseCollisions {
+    /// Log a warning for each collision
+    Warn,
+
+    /// Refuse to start
+    Deny,
+}
+
+/// Returns the pairs of entries under `root` whose names only differ by case, relative to `root`
+///
+/// Such entries can't coexist on a case-insensitive filesystem, where one would shadow the other.
+/// Hidden entries are skipped unless `show_hidden` is set, and symlinked directories are not
+/// followed.
+pub fn find_case_collisions(root: &Path, show_hidden: bool) -> Vec<(PathBuf, PathBuf)> {
+    let mut collisions = Vec::new();
+    walk(root, Path::new(""), show_hidden, &mut collisions);
+    collisions
+}
+
+fn walk(root: &Path, relative: &Path, show_hidden: bool, collisions: &mut Vec<(PathBuf, PathBuf)>) {
+    let read_dir = match root.join(relative).read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+
+    let mut entries = read_dir
+        .flatten()
+        .filter(|entry| show_hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut seen: HashMap<String, PathBuf> = HashMap::new();
+    for entry in entries {
+        let path = relative.join(entry.file_name());
+        let folded_name = entry.file_name().to_string_lossy().to_lowercase();
+        match seen.get(&folded_name) {
+            Some(other) => collisions.push((other.clone(), path.clone())),
+            None => {
+                seen.insert(folded_name, path.clone());
+            }
+        }
+
+        if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+            walk(root, &path, show_hidden, collisions);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use actix_web::{
+    dev::ServiceRequest, guard::GuardContext, http::Method, web, HttpRequest, HttpResponse,
+};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use strum::EnumString;
+
+use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::listing::{self, QueryParameters};
+use crate::MiniserveConfig;
+
+/// Cache of the SHA-256 checksums of files, hex-encoded
+#[derive(Default)]
+pub struct Checksums {
+    /// Known checksums, along with the modification date and size of the file they were computed
+    /// for
+    cache: Mutex<HashMap<PathBuf, (SystemTime, u64, String)>>,
+}
+
+impl Checksums {
+    /// Returns the checksum of the file at `path`, or `None` if it isn't a file or can't be read.
+    ///
+    /// The hash is only computed again if the modification date or size of the file changed.
+    pub fn get(&self, path: &Path) -> Option<String> {
+        let metadata = path.metadata().ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified = metadata.modified().ok()?;
+
+        if let Some((cached_modified, cached_len, checksum)) = self.cache.lock().unwrap().get(path)
+        {
+            if *cached_modified == modified && *cached_len == metadata.len() {
+                return Some(checksum.clone());
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut File::open(path).ok()?, &mut hasher).ok()?;
+        let checksum = hex::encode(hasher.finalize());
+
+        self.cache.lock().unwrap().insert(
+            path.to_path_buf(),
+            (modified, metadata.len(), checksum.clone()),
+        );
+        Some(checksum)
+    }
+}
+
+/// Hash algorithms of the checksums of single files, requested with `?checksum=`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    /// Returns the hex-encoded checksum of everything read from `reader`
+    fn checksum(self, reader: &mut impl io::Read) -> io::Result<String> {
+        match self {
+            Self::Md5 => hex_digest::<Md5>(reader),
+            Self::Sha1 => hex_digest::<Sha1>(reader),
+            Self::Sha256 => hex_digest::<Sha256>(reader),
+        }
+    }
+}
+
+/// Hashes `reader` as it is read, so that memory use doesn't depend on its size
+fn hex_digest<D: Digest + io::Write>(reader: &mut impl io::Read) -> io::Result<String> {
+    let mut hasher = D::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Guard of the route answering `GET` requests with `?checksum=`, when `--checksums` is enabled
+pub fn is_requested(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .uri
+        .query()
+        .and_then(|query| web::Query::<QueryParameters>::from_query(query).ok())
+        .is_some_and(|query| query.checksum.is_some())
+}
+
+/// Returns `true` if `req` is answered with the checksum of a file rather than its content
+///
+/// Middlewares transforming the content of files must leave such responses alone.
+pub fn should_answer(req: &ServiceRequest) -> bool {
+    req.app_data::<MiniserveConfig>()
+        .map(|conf| conf.checksums.is_some())
+        .unwrap_or(false)
+        && req.method() == Method::GET
+        && listing::extract_query_parameters(req.request())
+            .checksum
+            .is_some()
+}
+
+/// Serves the hex-encoded checksum of the requested file, in the algorithm given by
+/// `?checksum=md5|sha1|sha256`
+///
+/// The file is subject to the same restrictions (hidden files, symlinks) as when it is
+/// downloaded, and is hashed as it is read.
+pub async fn file_checksum_handler(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let query_params = listing::extract_query_parameters(&req);
+    let name = query_params.checksum.clone().unwrap_or_default();
+    let algorithm: ChecksumAlgorithm = name.to_lowercase().parse().map_err(|_| {
+        ContextualError::InvalidHttpRequestError(format!(
+            "Unsupported checksum algorithm '{name}', expected md5, sha1 or sha256"
+        ))
+    })?;
+
+    let path = resolve_file(req.path(), conf, query_params.shows_hidden(conf))
+        .ok_or_else(|| ContextualError::RouteNotFoundError(req.path().to_string()))?;
+    let checksum = web::block(move || algorithm.checksum(&mut File::open(path)?))
+        .await
+        .map_err(|e| {
+            ContextualError::IoError("Failed to hash file".to_string(), io::Error::other(e))
+        })?
+        .map_err(|e| ContextualError::IoError("Failed to hash file".to_string(), e))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(mime::TEXT_PLAIN_UTF_8)
+        .body(checksum))
+}
+
+/// Resolves the request path to the file it targets, or `None` if it isn't a file that may be
+/// downloaded, hidden files being only downloaded with `show_hidden`
+fn resolve_file(request_path: &str, conf: &MiniserveConfig, show_hidden: bool) -> Option<PathBuf> {
+    let path = request_path
+        .strip_prefix(&conf.route_prefix)
+        .unwrap_or(request_path);
+    let path = percent_encoding::percent_decode_str(path).decode_utf8_lossy();
+    let relative_path = sanitize_path(Path::new(&*path), show_hidden)?;
+    let app_root_dir = conf.path.canonicalize().ok()?;
+    let file = app_root_dir.join(relative_path);
+
+    // Disallow going through symlinks when they are not shown
+    let canonical_file = file.canonicalize().ok()?;
+    if !canonical_file.is_file() || (conf.no_symlinks && canonical_file != file) {
+        return None;
+    }
+    Some(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn test_checksums_follow_changes() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let file = dir.child("file.txt");
+        file.write_str("hello").unwrap();
+        let checksums = Checksums::default();
+
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(checksums.get(file.path()).as_deref(), Some(hello));
+        assert_eq!(checksums.get(file.path()).as_deref(), Some(hello));
+
+        file.write_str("hello world").unwrap();
+        assert_eq!(
+            checksums.get(file.path()).as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+
+        assert_eq!(checksums.get(dir.path()), None);
+    }
+
+    #[rstest]
+    #[case("md5", "5d41402abc4b2a76b9719d911017c592")]
+    #[case("sha1", "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d")]
+    #[case(
+        "sha256",
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    )]
+    fn test_checksum_algorithms(#[case] name: &str, #[case] expected: &str) {
+        let algorithm: ChecksumAlgorithm = name.parse().unwrap();
+        let checksum = algorithm.checksum(&mut &b"hello"[..]).unwrap();
+        assert_eq!(checksum, expected);
+    }
+}
@@ -0,0 +1,91 @@
+use std::ffi::OsStr;
+
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+
+use crate::archive;
+
+/// Returns `true` if the response with `headers` to a request for `path` is already compressed
+///
+/// Images, videos, audio files and archives gain next to nothing from compression. They are
+/// recognized by the extension of the requested path or by their content type.
+pub fn is_precompressed(path: &str, headers: &HeaderMap) -> bool {
+    is_compressed_path(path) || is_compressed_type(headers.get(header::CONTENT_TYPE))
+}
+
+/// Keeps the response with `headers` from being compressed with `--compress`
+///
+/// The response gets `Content-Encoding: identity`, which the `Compress` middleware leaves as is,
+/// unless it already has an encoding.
+pub fn skip_compression(headers: &mut HeaderMap) {
+    if !headers.contains_key(header::CONTENT_ENCODING) {
+        headers.insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static("identity"),
+        );
+    }
+}
+
+/// Returns `true` if the file requested at `path` is already compressed, judging by its extension
+fn is_compressed_path(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .is_some_and(|name| archive::is_compressed(OsStr::new(name), &[]))
+}
+
+/// Returns `true` if `content_type` is an image, video or audio format, which are compressed
+/// already, SVG images aside
+fn is_compressed_type(content_type: Option<&HeaderValue>) -> bool {
+    let content_type = match content_type.and_then(|value| value.to_str().ok()) {
+        Some(content_type) => content_type.to_lowercase(),
+        None => return false,
+    };
+    ["image/", "video/", "audio/"]
+        .iter()
+        .any(|prefix| content_type.starts_with(prefix))
+        && !content_type.starts_with("image/svg+xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("/photos/holidays.JPG", true)]
+    #[case("/videos/clip.mp4", true)]
+    #[case("/backup.tar.gz", true)]
+    #[case("/index.html", false)]
+    #[case("/", false)]
+    fn test_is_compressed_path(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(is_compressed_path(path), expected);
+    }
+
+    #[rstest]
+    #[case("/photos/holidays.JPG", None, true)]
+    #[case("/photos/holidays", Some("image/jpeg"), true)]
+    #[case("/notes.txt", Some("text/plain; charset=utf-8"), false)]
+    fn test_is_precompressed(
+        #[case] path: &str,
+        #[case] content_type: Option<&'static str>,
+        #[case] expected: bool,
+    ) {
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+        assert_eq!(is_precompressed(path, &headers), expected);
+    }
+
+    #[rstest]
+    #[case(Some("image/png"), true)]
+    #[case(Some("video/mp4"), true)]
+    #[case(Some("audio/ogg"), true)]
+    #[case(Some("image/svg+xml"), false)]
+    #[case(Some("text/html; charset=utf-8"), false)]
+    #[case(Some("application/javascript"), false)]
+    #[case(None, false)]
+    fn test_is_compressed_type(#[case] content_type: Option<&str>, #[case] expected: bool) {
+        let content_type = content_type.map(|value| HeaderValue::from_str(value).unwrap());
+        assert_eq!(is_compressed_type(content_type.as_ref()), expected);
+    }
+}
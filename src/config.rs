@@ -2,22 +2,49 @@
 use std::{fs::File, io::BufReader};
 use std::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
-    path::PathBuf,
+    path::{Component, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 
 #[cfg(feature = "tls")]
 use anyhow::anyhow;
-use anyhow::{Context, Result};
-use http::HeaderMap;
+use anyhow::{bail, Context, Result};
+use bytesize::ByteSize;
+use http::{HeaderMap, HeaderValue};
+use regex::Regex;
 
 #[cfg(feature = "tls")]
 use rustls_pemfile as pemfile;
 
+#[cfg(feature = "git")]
+use crate::git_snapshot::GitSnapshot;
+
 use crate::{
+    allowed_hosts::AllowedHosts,
+    archive::ArchiveSettings,
+    archive_cache::ArchiveCache,
+    archive_jobs::ArchiveJobs,
     args::{CliArgs, MediaType},
     auth::RequiredAuth,
-    file_upload::sanitize_path,
+    auth_files::AUTH_FILE,
+    cache_control::CacheControl,
+    case_collisions::CaseCollisions,
+    checksums::Checksums,
+    dir_config::DIR_CONFIG_FILE,
+    disk_usage::DiskUsageMonitor,
+    etag::{ContentEtags, EtagStrength},
+    exclude::Exclusions,
+    file_upload::{sanitize_path, OnDuplicate},
+    listing::GroupDirs,
+    number_format::NumberFormat,
+    offload::Offload,
+    rate_limit::{
+        BandwidthLimiter, ConcurrencyLimiter, RateLimiter, TotalBandwidthLimiter, WalkThrottle,
+    },
     renderer::ThemeSlug,
+    trash::Trash,
+    upload_quota::{self, UploadQuota},
 };
 
 /// Possible characters for random routes
@@ -40,9 +67,18 @@ pub struct MiniserveConfig {
     /// IP address(es) on which miniserve will be available
     pub interfaces: Vec<IpAddr>,
 
+    /// If set, only requests addressed to these hosts are answered
+    pub allowed_hosts: Option<AllowedHosts>,
+
     /// Enable HTTP basic authentication
     pub auth: Vec<RequiredAuth>,
 
+    /// Regular expressions of files visible to users without credentials
+    pub anonymous_allow: Vec<Regex>,
+
+    /// Enforce the credentials of `.miniserve-auth` files
+    pub auth_files: bool,
+
     /// If false, miniserve will serve the current working directory
     pub path_explicitly_chosen: bool,
 
@@ -52,6 +88,18 @@ pub struct MiniserveConfig {
     /// Show hidden files
     pub show_hidden: bool,
 
+    /// Hide files and directories which usually hold secrets
+    pub hide_sensitive: bool,
+
+    /// Files and directories hidden with --exclude
+    pub exclusions: Option<Arc<Exclusions>>,
+
+    /// Enable overriding the settings of listings with a .miniserve.toml file in directories
+    pub dir_config: bool,
+
+    /// If enabled, listings are always rendered without CSS, JavaScript or icons
+    pub minimal: bool,
+
     /// Route prefix; Either empty or prefixed with slash
     pub route_prefix: String,
 
@@ -95,8 +143,32 @@ pub struct MiniserveConfig {
     /// HTML accept attribute value
     pub uploadable_media_type: Option<String>,
 
-    /// Enable upload to override existing files
-    pub overwrite_files: bool,
+    /// If set, limits the total size of uploaded files
+    pub upload_quota: Option<Arc<UploadQuota>>,
+
+    /// If set, limits how many uploads each client can have in progress
+    pub upload_concurrency_limiter: Option<Arc<ConcurrencyLimiter>>,
+
+    /// If set, limits the size of the body of each upload request
+    pub max_upload_size: Option<ByteSize>,
+
+    /// If set, limits the upload bandwidth of each client
+    pub upload_rate_limiter: Option<Arc<BandwidthLimiter>>,
+
+    /// If set, limits the total bandwidth of all responses
+    pub total_rate_limiter: Option<Arc<TotalBandwidthLimiter>>,
+
+    /// Enable compressing responses
+    pub compress: bool,
+
+    /// Enable deleting files and directories
+    pub delete_enabled: bool,
+
+    /// If set, deleted files are moved to this trash instead of being removed
+    pub trash: Option<Arc<Trash>>,
+
+    /// What uploads do with files whose name is already taken
+    pub on_duplicate: OnDuplicate,
 
     /// If false, creation of uncompressed tar archives is disabled
     pub tar_enabled: bool,
@@ -104,11 +176,35 @@ pub struct MiniserveConfig {
     /// If false, creation of gz-compressed tar archives is disabled
     pub tar_gz_enabled: bool,
 
+    /// If false, creation of zstd-compressed tar archives is disabled
+    pub tar_zst_enabled: bool,
+
+    /// Compression settings of archives
+    pub archive_settings: ArchiveSettings,
+
+    /// Number of directory levels walked by archives and recursive listings, if limited
+    pub max_depth: Option<usize>,
+
     /// If false, creation of zip archives is disabled
     pub zip_enabled: bool,
 
-    /// If enabled, directories are listed first
-    pub dirs_first: bool,
+    /// If set, limits how many archives each client can download per minute
+    pub archive_rate_limiter: Option<Arc<RateLimiter>>,
+
+    /// If set, limits the rate of filesystem accesses of archive creation and recursive walks
+    pub walk_throttle: Option<Arc<WalkThrottle>>,
+
+    /// If set, generated archives are kept on disk and reused
+    pub archive_cache: Option<ArchiveCache>,
+
+    /// If set, registry of the archives generated in the background
+    pub archive_jobs: Option<Arc<ArchiveJobs>>,
+
+    /// If enabled, archives are created in the archive cache before being sent, as files
+    pub archive_ranges: bool,
+
+    /// How directories are grouped relative to files in listings
+    pub group_dirs: GroupDirs,
 
     /// Shown instead of host in page title and heading
     pub title: Option<String>,
@@ -116,9 +212,27 @@ pub struct MiniserveConfig {
     /// If specified, header will be added
     pub header: Vec<HeaderMap>,
 
+    /// If specified, `Content-Security-Policy` of HTML files
+    pub csp: Option<HeaderValue>,
+
+    /// If specified, value of the Server header
+    pub server_header: Option<HeaderValue>,
+
+    /// If enabled, the Server header is omitted from responses
+    pub no_server_header: bool,
+
+    /// If set, `Cache-Control` policies of files and directory listings
+    pub cache_control: Option<CacheControl>,
+
     /// If specified, symlink destination will be shown
     pub show_symlink_info: bool,
 
+    /// If enabled, the size of files is also shown as bars in listings
+    pub size_bars: bool,
+
+    /// Number of threads fetching the metadata of entries in directory listings
+    pub listing_concurrency: usize,
+
     /// If enabled, version footer is hidden
     pub hide_version_footer: bool,
 
@@ -128,22 +242,102 @@ pub struct MiniserveConfig {
     /// If enabled, display a wget command to recursively download the current directory
     pub show_wget_footer: bool,
 
+    /// If set, the disk usage shown in the footer of listings
+    pub disk_usage: Option<Arc<DiskUsageMonitor>>,
+
     /// If enabled, render the readme from the current directory
     pub readme: bool,
 
+    /// If set, serve an Atom feed with at most this many of the most recently modified files
+    pub feed: Option<usize>,
+
+    /// If set, serve a page listing at most this many of the most recently modified files
+    pub recent: Option<usize>,
+
+    /// If set, serve a sitemap with at most this many files
+    pub sitemap: Option<usize>,
+
+    /// If set, content of /robots.txt
+    pub robots_txt: Option<String>,
+
+    /// If set, the transfer of files is delegated to a reverse proxy
+    pub offload: Option<Offload>,
+
+    /// If set, what to do when the served directory contains names which only differ by case
+    pub case_collisions: Option<CaseCollisions>,
+
+    /// Time a client has to send the headers of a request
+    pub client_timeout: Duration,
+
+    /// Time given to a client to acknowledge the closing of a connection
+    pub client_disconnect: Duration,
+
+    /// If enabled, serve an OpenAPI description of the HTTP API
+    pub openapi: bool,
+
+    /// If enabled, error responses are sent as JSON instead of HTML pages
+    pub api_errors: bool,
+
+    /// If set, small files get an ETag computed from their content
+    pub content_etags: Option<Arc<ContentEtags>>,
+
+    /// If set, cache of the checksums which can be included in JSON listings
+    pub checksums: Option<Arc<Checksums>>,
+
+    /// Strength of the ETags which are not computed from the content of files
+    pub etag_strength: EtagStrength,
+
+    /// Separators of thousands and decimals in the sizes shown in listings
+    pub number_format: NumberFormat,
+
+    /// If enabled, modification dates in listings are shown in UTC
+    pub tz_utc: bool,
+
+    /// If enabled, Markdown files are rendered as HTML unless requested raw
+    pub render_markdown: bool,
+
+    /// If enabled, browsers get an HTML view of CSV, JSON and Markdown files
+    pub negotiate_content: bool,
+
+    /// If set, the value of the Strict-Transport-Security header sent over TLS
+    pub hsts: Option<String>,
+
     /// If set, use provided rustls config for TLS
     #[cfg(feature = "tls")]
     pub tls_rustls_config: Option<rustls::ServerConfig>,
 
     #[cfg(not(feature = "tls"))]
     pub tls_rustls_config: Option<()>,
+
+    /// If set, snapshot of a git repository being served, only kept here so that it isn't removed
+    #[cfg(feature = "git")]
+    #[allow(dead_code)]
+    pub git_snapshot: Option<Arc<GitSnapshot>>,
+
+    /// If set, plain HTTP requests on this port are redirected to HTTPS
+    pub redirect_http_port: Option<u16>,
+
+    /// If set, Unix domain socket to listen on
+    pub unix_socket: Option<PathBuf>,
+
+    /// Permissions of the Unix domain socket
+    pub unix_socket_mode: u32,
 }
 
 impl MiniserveConfig {
     /// Parses the command line arguments
     pub fn try_from_args(args: CliArgs) -> Result<Self> {
+        #[cfg(unix)]
+        let (unix_socket, unix_socket_mode) = (args.unix_socket, args.unix_socket_mode);
+
+        #[cfg(not(unix))]
+        let (unix_socket, unix_socket_mode) = (None, 0);
+
         let interfaces = if !args.interfaces.is_empty() {
             args.interfaces
+        } else if unix_socket.is_some() {
+            // Only the Unix domain socket is listened on
+            vec![]
         } else {
             vec![
                 IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)),
@@ -195,6 +389,9 @@ impl MiniserveConfig {
                     File::open(&tls_key).context(format!("Couldn't access TLS key {tls_key:?}"))?,
                 );
                 let cert_chain = pemfile::certs(cert_file).context("Reading cert file")?;
+                if cert_chain.is_empty() {
+                    bail!("No PEM certificate in TLS certificate {tls_cert:?}");
+                }
                 let key = pemfile::read_all(key_file)
                     .context("Reading private key file")?
                     .into_iter()
@@ -202,7 +399,7 @@ impl MiniserveConfig {
                         pemfile::Item::RSAKey(key) | pemfile::Item::PKCS8Key(key) => Some(key),
                         _ => None,
                     })
-                    .ok_or_else(|| anyhow!("No supported private key in file"))?;
+                    .ok_or_else(|| anyhow!("No supported private key in TLS key {tls_key:?}"))?;
                 let server_config = rustls::ServerConfig::builder()
                     .with_safe_defaults()
                     .with_no_client_auth()
@@ -218,6 +415,33 @@ impl MiniserveConfig {
         #[cfg(not(feature = "tls"))]
         let tls_rustls_server_config = None;
 
+        #[cfg(feature = "tls")]
+        let redirect_http_port = args.redirect_http;
+
+        #[cfg(not(feature = "tls"))]
+        let redirect_http_port = None;
+
+        // Upload directories are relative to the served directory, which they can't leave
+        let allowed_upload_dir = args
+            .allowed_upload_dir
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|dir| {
+                let is_inside = dir
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+                match sanitize_path(dir, args.hidden) {
+                    Some(sanitized) if is_inside => {
+                        Ok(sanitized.to_string_lossy().replace('\\', "/"))
+                    }
+                    _ => bail!(
+                        "Invalid upload directory {dir:?}, it must be a path inside the served directory"
+                    ),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
         let uploadable_media_type = args.media_type_raw.or_else(|| {
             args.media_type.map(|types| {
                 types
@@ -232,15 +456,125 @@ impl MiniserveConfig {
             })
         });
 
+        let server_header = args
+            .server_header
+            .map(HeaderValue::try_from)
+            .transpose()
+            .context("Invalid value for --server-header")?;
+
+        let archive_cache = match args.archive_cache {
+            Some(dir) => {
+                std::fs::create_dir_all(&dir)
+                    .context(format!("Couldn't create archive cache directory {dir:?}"))?;
+                Some(ArchiveCache::new(
+                    dir,
+                    args.archive_cache_max_size.as_u64(),
+                    args.archive_cache_max_age.map(Duration::from_secs),
+                ))
+            }
+            None => None,
+        };
+
+        let path = args.path.unwrap_or_else(|| PathBuf::from("."));
+
+        #[cfg(feature = "git")]
+        let git_snapshot = args
+            .git_ref
+            .map(|git_ref| GitSnapshot::extract(&path, &git_ref))
+            .transpose()?
+            .map(Arc::new);
+
+        #[cfg(feature = "git")]
+        let path = git_snapshot
+            .as_ref()
+            .map(|snapshot| snapshot.path())
+            .unwrap_or(path);
+
+        // The usage of the upload quota is kept out of reach of clients, like excluded files
+        let mut exclude = args.exclude.clone();
+        if args.upload_quota.is_some() {
+            exclude.push(format!("/{}", upload_quota::SIDECAR_FILE_NAME));
+        }
+        let exclusions = match exclude.is_empty() {
+            true => None,
+            false => {
+                let root = path.canonicalize().unwrap_or_else(|_| path.clone());
+                let exclusions = Exclusions::new(root, &exclude)
+                    .context("Invalid pattern given with --exclude")?;
+                Some(Arc::new(exclusions))
+            }
+        };
+
+        let robots_txt = args
+            .robots_file
+            .map(|robots_file| {
+                std::fs::read_to_string(&robots_file)
+                    .with_context(|| format!("Couldn't read {}", robots_file.display()))
+            })
+            .transpose()?;
+
+        let trash = match args.trash_dir {
+            Some(dir) => {
+                std::fs::create_dir_all(&dir)
+                    .context(format!("Couldn't create trash directory {dir:?}"))?;
+                let dir = dir
+                    .canonicalize()
+                    .context(format!("Couldn't resolve trash directory {dir:?}"))?;
+                let served_dir = path.canonicalize().unwrap_or_else(|_| path.clone());
+                if dir.starts_with(served_dir) {
+                    bail!("The trash directory {dir:?} can't be inside the served directory");
+                }
+                Some(Arc::new(Trash::new(
+                    dir,
+                    args.trash_max_age.map(Duration::from_secs),
+                )))
+            }
+            None => None,
+        };
+
+        let disk_usage = args
+            .show_disk_usage
+            .then(|| DiskUsageMonitor::new(path.clone()))
+            .flatten()
+            .map(Arc::new);
+
+        let upload_quota = args
+            .upload_quota
+            .map(|limit| {
+                let sidecar = path.join(upload_quota::SIDECAR_FILE_NAME);
+                UploadQuota::new(limit.as_u64(), sidecar)
+                    .context("Couldn't read the usage of the upload quota")
+            })
+            .transpose()?
+            .map(Arc::new);
+
+        let hsts = args.hsts.then(|| {
+            let mut value = format!("max-age={}", args.hsts_max_age);
+            if args.hsts_include_subdomains {
+                value.push_str("; includeSubDomains");
+            }
+            value
+        });
+
+        let allowed_hosts = (!args.allowed_hosts.is_empty())
+            .then(|| AllowedHosts::new(&args.allowed_hosts, &interfaces));
+
         Ok(MiniserveConfig {
             verbose: args.verbose,
-            path: args.path.unwrap_or_else(|| PathBuf::from(".")),
+            path,
             port,
             interfaces,
+            allowed_hosts,
             auth: args.auth,
+            anonymous_allow: args.anonymous_allow,
+            auth_files: args.auth_files,
             path_explicitly_chosen,
             no_symlinks: args.no_symlinks,
             show_hidden: args.hidden,
+            hide_sensitive: args.hide_sensitive,
+            exclusions: exclusions.clone(),
+            dir_config: args.dir_config,
+            minimal: args.minimal,
             route_prefix,
             favicon_route,
             css_route,
@@ -248,35 +582,115 @@ impl MiniserveConfig {
             default_color_scheme_dark,
             index: args.index,
             spa: args.spa,
-            overwrite_files: args.overwrite_files,
+            upload_quota,
+            upload_concurrency_limiter: args
+                .upload_concurrency
+                .map(|max| Arc::new(ConcurrencyLimiter::new(max))),
+            max_upload_size: args.max_upload_size,
+            upload_rate_limiter: args
+                .upload_rate_per_ip
+                .map(|rate| Arc::new(BandwidthLimiter::new(rate.as_u64()))),
+            total_rate_limiter: args
+                .max_total_rate
+                .map(|rate| Arc::new(TotalBandwidthLimiter::new(rate.as_u64()))),
+            compress: args.compress,
+            delete_enabled: args.delete_enabled,
+            trash,
+            on_duplicate: args.on_duplicate.unwrap_or(if args.overwrite_files {
+                OnDuplicate::Overwrite
+            } else {
+                OnDuplicate::Error
+            }),
             show_qrcode: args.qrcode,
             mkdir_enabled: args.mkdir_enabled,
             file_upload: args.allowed_upload_dir.is_some(),
-            allowed_upload_dir: args
-                .allowed_upload_dir
-                .unwrap_or_default()
-                .iter()
-                .map(|x| {
-                    sanitize_path(x, false)
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .replace('\\', "/")
-                })
-                .collect(),
+            allowed_upload_dir,
             uploadable_media_type,
             tar_enabled: args.enable_tar,
             tar_gz_enabled: args.enable_tar_gz,
+            tar_zst_enabled: args.enable_tar_zst,
+            archive_settings: ArchiveSettings {
+                zstd_level: args.tar_zst_level,
+                zip: args.zip_compression,
+                zip_deflate_compressed: args.zip_deflate_compressed,
+                normalize_names: !args.no_archive_nfc,
+                include_hidden: args.hidden,
+                exclusions,
+                max_depth: args.max_depth.map(|max_depth| max_depth as usize),
+                reserved_names: [
+                    (args.auth_files, AUTH_FILE),
+                    (args.dir_config, DIR_CONFIG_FILE),
+                ]
+                .into_iter()
+                .filter_map(|(enabled, name)| enabled.then_some(name))
+                .collect(),
+            },
+            max_depth: args.max_depth.map(|max_depth| max_depth as usize),
             zip_enabled: args.enable_zip,
-            dirs_first: args.dirs_first,
+            archive_rate_limiter: args
+                .archive_rate_limit
+                .map(|max| Arc::new(RateLimiter::new(max, Duration::from_secs(60)))),
+            walk_throttle: args
+                .walk_throttle
+                .map(|operations| Arc::new(WalkThrottle::new(operations))),
+            archive_cache,
+            archive_jobs: args.archive_jobs.then(Default::default),
+            archive_ranges: args.archive_ranges,
+            group_dirs: if args.dirs_first {
+                GroupDirs::First
+            } else {
+                args.group_dirs
+            },
             title: args.title,
             header: args.header,
+            csp: args.csp,
+            server_header,
+            no_server_header: args.no_server_header,
+            cache_control: (args.cache_max_age.is_some() || !args.cache.is_empty()).then(|| {
+                CacheControl {
+                    default: args.cache_max_age.map(|max_age| {
+                        HeaderValue::try_from(format!("max-age={max_age}")).unwrap()
+                    }),
+                    by_extension: args.cache.into_iter().collect(),
+                }
+            }),
             show_symlink_info: args.show_symlink_info,
+            size_bars: args.size_bars,
+            listing_concurrency: args.listing_concurrency.into(),
             hide_version_footer: args.hide_version_footer,
             hide_theme_selector: args.hide_theme_selector,
             show_wget_footer: args.show_wget_footer,
+            disk_usage,
             readme: args.readme,
+            feed: args.feed,
+            recent: args.recent,
+            sitemap: args.sitemap.map(|max_entries| max_entries as usize),
+            robots_txt,
+            offload: match args.x_accel_redirect {
+                Some(location) => Some(Offload::XAccelRedirect(location)),
+                None => args.x_sendfile.then_some(Offload::XSendfile),
+            },
+            case_collisions: args.case_collisions,
+            client_timeout: Duration::from_secs(args.client_timeout),
+            client_disconnect: Duration::from_secs(args.client_disconnect),
+            openapi: args.openapi,
+            api_errors: args.api_errors,
+            content_etags: args
+                .content_etag
+                .map(|max_size| Arc::new(ContentEtags::new(max_size.as_u64()))),
+            checksums: args.checksums.then(Default::default),
+            etag_strength: args.etag_strength,
+            number_format: args.number_format,
+            tz_utc: args.tz_utc,
+            render_markdown: args.render_markdown,
+            negotiate_content: args.negotiate_content,
+            hsts,
             tls_rustls_config: tls_rustls_server_config,
+            #[cfg(feature = "git")]
+            git_snapshot,
+            redirect_http_port,
+            unix_socket,
+            unix_socket_mode,
         })
     }
 }
@@ -0,0 +1,97 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{parser::ValueSource, Arg, ArgMatches, CommandFactory, Parser};
+use toml::Value;
+
+use crate::args::CliArgs;
+
+/// Parses the command line, along with the options of the file given with `--config`, if any
+///
+/// The keys of the file are the long names of the options (e.g. `port = 8080`,
+/// `upload-files = true`), and `path` for the path to serve. Options given on the command line
+/// override those of the file.
+pub fn parse_args() -> Result<CliArgs> {
+    let cli_args = std::env::args_os().collect::<Vec<_>>();
+    let args = CliArgs::parse_from(&cli_args);
+    let config_file = match &args.config {
+        Some(config_file) => config_file,
+        None => return Ok(args),
+    };
+
+    let matches = CliArgs::command().get_matches_from(&cli_args);
+    let file_args = read_config_file(config_file, &matches)?;
+
+    // The options of the file come right after the name of the program
+    let mut cli_args = cli_args.into_iter();
+    let all_args = cli_args
+        .next()
+        .into_iter()
+        .chain(file_args)
+        .chain(cli_args)
+        .collect::<Vec<_>>();
+    Ok(CliArgs::parse_from(all_args))
+}
+
+/// Converts the options of a config file to command line arguments, leaving out those given on
+/// the command line
+fn read_config_file(config_file: &Path, matches: &ArgMatches) -> Result<Vec<OsString>> {
+    let content = std::fs::read_to_string(config_file)
+        .with_context(|| format!("Failed to read config file {config_file:?}"))?;
+    let table = content
+        .parse::<toml::Table>()
+        .with_context(|| format!("Failed to parse config file {config_file:?}"))?;
+
+    let command = CliArgs::command();
+    let mut file_args = Vec::new();
+    for (key, value) in table {
+        let arg = command
+            .get_arguments()
+            .filter(|arg| arg.get_id() != "config")
+            .find(|arg| match arg.get_long() {
+                Some(long) => long == key,
+                None => arg.is_positional() && arg.get_id() == key.as_str(),
+            })
+            .ok_or_else(|| anyhow!("Unknown option '{key}' in config file {config_file:?}"))?;
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::String(value) => Some(value),
+                Value::Integer(value) => Some(value.to_string()),
+                Value::Float(value) => Some(value.to_string()),
+                Value::Boolean(value) if takes_value(arg) => Some(value.to_string()),
+                // Flags are off unless given
+                Value::Boolean(value) => {
+                    if value {
+                        file_args.push(format!("--{key}").into());
+                    }
+                    None
+                }
+                _ => bail!("Unsupported value for '{key}' in config file {config_file:?}"),
+            };
+            match value {
+                Some(value) if arg.is_positional() => file_args.push(value.into()),
+                Some(value) => file_args.push(format!("--{key}={value}").into()),
+                None => (),
+            }
+        }
+    }
+
+    Ok(file_args)
+}
+
+/// Returns `true` if `arg` requires a value, so that booleans must be passed as such
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+        && arg
+            .get_num_args()
+            .is_none_or(|num_args| num_args.min_values() > 0)
+}
@@ -0,0 +1,91 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+};
+use futures::prelude::*;
+
+use crate::etag::requested_file;
+use crate::MiniserveConfig;
+
+/// Policy of `--csp` without a value
+///
+/// Scripts, plugins and forms can't run, while the page may still load its stylesheets, images
+/// and other resources from the server.
+pub const DEFAULT_POLICY: &str =
+    "default-src 'self'; script-src 'none'; object-src 'none'; base-uri 'none'; form-action 'none'";
+
+/// Parses the value of a `Content-Security-Policy` header
+pub fn parse_policy(src: &str) -> Result<HeaderValue, String> {
+    let policy = src.trim();
+    if policy.is_empty() {
+        return Err("the policy must not be empty".to_string());
+    }
+    HeaderValue::from_str(policy).map_err(|e| e.to_string())
+}
+
+/// Middleware adding the `--csp` policy to HTML files served from the served directory
+///
+/// This covers files, index files and their rendered views, but not the pages generated by
+/// miniserve like directory listings, which need their own scripts. A `Content-Security-Policy`
+/// header already set, e.g. with `--header`, is kept.
+pub fn csp_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let policy = req.app_data::<MiniserveConfig>().and_then(|conf| {
+        let policy = conf.csp.as_ref()?;
+        let path = requested_file(req.path(), conf)?;
+        let generated_listing = path.is_dir()
+            && conf
+                .index
+                .as_ref()
+                .is_none_or(|index| !path.join(index).is_file());
+        (!generated_listing).then(|| policy.clone())
+    });
+
+    let fut = srv.call(req);
+
+    async move {
+        let mut res = fut.await?;
+
+        let is_html = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/html"));
+        if let (Some(policy), true) = (policy, is_html) {
+            if !res.headers().contains_key(header::CONTENT_SECURITY_POLICY) {
+                res.headers_mut()
+                    .insert(header::CONTENT_SECURITY_POLICY, policy);
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(DEFAULT_POLICY)]
+    #[case(" default-src 'self' ")]
+    fn test_parse_policy(#[case] src: &str) {
+        assert_eq!(parse_policy(src).unwrap(), src.trim());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("default-src\n'self'")]
+    fn test_parse_invalid_policy(#[case] src: &str) {
+        assert!(parse_policy(src).is_err());
+    }
+}
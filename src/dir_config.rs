@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::renderer::ThemeSlug;
+
+/// Name of the files overriding the settings of the listing of their directory
+pub const DIR_CONFIG_FILE: &str = ".miniserve.toml";
+
+/// Keys which can be set in a directory config file
+const KNOWN_KEYS: &[&str] = &["title", "theme", "columns"];
+
+/// Settings of the listing of a directory, overriding the global ones
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct DirConfig {
+    /// Title of the page
+    pub title: Option<String>,
+
+    /// Default color scheme, in both light and dark mode
+    pub theme: Option<ThemeSlug>,
+
+    /// Columns shown besides the name of entries, all of them if unset
+    pub columns: Option<Vec<Column>>,
+}
+
+/// Optional columns of listings
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Size,
+    Date,
+}
+
+impl DirConfig {
+    /// Returns `true` if `column` is shown in the listing
+    pub fn shows(&self, column: Column) -> bool {
+        self.columns
+            .as_ref()
+            .is_none_or(|columns| columns.contains(&column))
+    }
+}
+
+/// Reads the config file of `dir`, if it has one
+///
+/// Unknown keys are only warned about, while an invalid file is ignored altogether.
+pub fn read_dir_config(dir: &Path) -> DirConfig {
+    let config_file = dir.join(DIR_CONFIG_FILE);
+    let content = match fs::read_to_string(&config_file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return DirConfig::default(),
+        Err(e) => {
+            log::warn!("Failed to read {}: {}", config_file.display(), e);
+            return DirConfig::default();
+        }
+    };
+
+    parse_dir_config(&content, &config_file).unwrap_or_else(|e| {
+        log::warn!("Ignoring invalid {}: {}", config_file.display(), e);
+        DirConfig::default()
+    })
+}
+
+fn parse_dir_config(content: &str, config_file: &Path) -> Result<DirConfig, toml::de::Error> {
+    let table = content.parse::<toml::Table>()?;
+    for key in table
+        .keys()
+        .filter(|key| !KNOWN_KEYS.contains(&key.as_str()))
+    {
+        log::warn!("Unknown key '{key}' in {}", config_file.display());
+    }
+    DirConfig::deserialize(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_dir_config() {
+        let config = parse_dir_config(
+            r#"
+            title = "Photos"
+            theme = "monokai"
+            columns = ["date"]
+            unknown = true
+            "#,
+            Path::new(DIR_CONFIG_FILE),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            DirConfig {
+                title: Some("Photos".to_string()),
+                theme: Some(ThemeSlug::Monokai),
+                columns: Some(vec![Column::Date]),
+            }
+        );
+        assert!(config.shows(Column::Date));
+        assert!(!config.shows(Column::Size));
+    }
+
+    #[test]
+    fn test_parse_invalid_dir_config() {
+        let config_file = Path::new(DIR_CONFIG_FILE);
+        assert!(parse_dir_config(r#"theme = "unknown""#, config_file).is_err());
+        assert!(parse_dir_config("title = ", config_file).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bytesize::ByteSize;
+
+/// Figures are refreshed at most this often, as listings may be requested a lot
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Space of the filesystem backing the served directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub total: ByteSize,
+    pub used: ByteSize,
+
+    /// Space available to miniserve, which may be less than the free space
+    pub available: ByteSize,
+}
+
+/// Queries the disk usage of the served directory, caching it briefly
+pub struct DiskUsageMonitor {
+    path: PathBuf,
+    cached: Mutex<Option<(Instant, DiskUsage)>>,
+}
+
+impl DiskUsageMonitor {
+    /// Returns `None` with a warning if the disk usage of `path` can't be queried on this
+    /// platform or filesystem
+    pub fn new(path: PathBuf) -> Option<Self> {
+        match query(&path) {
+            Ok(usage) => Some(Self {
+                path,
+                cached: Mutex::new(Some((Instant::now(), usage))),
+            }),
+            Err(e) => {
+                log::warn!(
+                    "Disk usage of {} can't be queried, it won't be shown: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Returns the current disk usage, unless it can't be queried anymore
+    pub fn get(&self) -> Option<DiskUsage> {
+        let mut cached = self.cached.lock().unwrap();
+        match *cached {
+            Some((queried, usage)) if queried.elapsed() < REFRESH_INTERVAL => Some(usage),
+            _ => {
+                *cached = query(&self.path).ok().map(|usage| (Instant::now(), usage));
+                cached.map(|(_, usage)| usage)
+            }
+        }
+    }
+}
+
+fn query(path: &Path) -> std::io::Result<DiskUsage> {
+    let stats = fs2::statvfs(path)?;
+    Ok(DiskUsage {
+        total: ByteSize::b(stats.total_space()),
+        used: ByteSize::b(stats.total_space().saturating_sub(stats.free_space())),
+        available: ByteSize::b(stats.available_space()),
+    })
+}
@@ -20,13 +20,38 @@ pub enum ContextualError {
     MultipartError(actix_multipart::MultipartError),
 
     /// Might occur during file upload
-    #[error("File already exists, and the overwrite_files option has not been set")]
+    #[error("File already exists, and --on-duplicate is set to error")]
     DuplicateFileError,
 
+    /// Might occur during file upload, when the upload quota is exhausted
+    #[error("Upload quota exceeded")]
+    InsufficientStorageError,
+
+    /// Might occur during file upload, when the request is larger than `--max-upload-size`
+    #[error("Upload too large, the maximum size of an upload is {0}")]
+    UploadTooLargeError(bytesize::ByteSize),
+
+    /// Might occur during file upload, when the client has too many uploads in progress
+    #[error("Too many uploads in progress, please try again later")]
+    TooManyUploadsError,
+
+    /// Might occur with `--auth-files`, when archiving or deleting a directory containing
+    /// directories protected by an auth file
+    #[error("{0} contains directories protected by an auth file")]
+    ProtectedDirectoryError(String),
+
+    /// Might occur with `--hide-sensitive`, when archiving a directory containing sensitive files
+    #[error("{0} contains sensitive files")]
+    SensitiveDirectoryError(String),
+
     /// Upload not allowed
     #[error("Upload not allowed to this directory")]
     UploadForbiddenError,
 
+    /// Might occur when uploading or creating directories without -u
+    #[error("Uploading files and creating directories is not enabled")]
+    UploadDisabledError,
+
     /// Any error related to an invalid path (failed to retrieve entry name, unexpected entry type, etc)
     #[error("Invalid path\ncaused by: {0}")]
     InvalidPathError(String),
@@ -80,6 +105,11 @@ pub enum ContextualError {
 Please set an explicit serve path like: `miniserve /my/path`")]
     NoExplicitPathAndNoTerminal,
 
+    /// In case miniserve was invoked with --case-collisions deny and the served directory
+    /// contains names which only differ by case
+    #[error("The served directory contains names which only differ by case: {0}")]
+    CaseCollisionError(String),
+
     /// In case miniserve was invoked with --no-symlinks but the serve path is a symlink
     #[error("The -P|--no-symlinks option was provided but the serve path '{0}' is a symlink")]
     NoSymlinksOptionWithSymlinkServePath(String),
@@ -93,8 +123,14 @@ impl ResponseError for ContextualError {
             Self::InsufficientPermissionsError(_) => StatusCode::FORBIDDEN,
             Self::InvalidHttpCredentials => StatusCode::UNAUTHORIZED,
             Self::InvalidHttpRequestError(_) => StatusCode::BAD_REQUEST,
-            Self::DuplicateFileError => StatusCode::FORBIDDEN,
+            Self::DuplicateFileError => StatusCode::CONFLICT,
             Self::UploadForbiddenError => StatusCode::FORBIDDEN,
+            Self::UploadDisabledError => StatusCode::FORBIDDEN,
+            Self::ProtectedDirectoryError(_) => StatusCode::FORBIDDEN,
+            Self::SensitiveDirectoryError(_) => StatusCode::FORBIDDEN,
+            Self::InsufficientStorageError => StatusCode::INSUFFICIENT_STORAGE,
+            Self::TooManyUploadsError => StatusCode::TOO_MANY_REQUESTS,
+            Self::UploadTooLargeError(_) => StatusCode::PAYLOAD_TOO_LARGE,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -109,13 +145,18 @@ impl ResponseError for ContextualError {
                 header::HeaderValue::from_static("Basic realm=\"miniserve\""),
             ));
         }
+        // The rest of the upload is not received, so the connection can't be reused
+        if let Self::UploadTooLargeError(_) = self {
+            resp.force_close();
+        }
 
         resp.content_type(mime::TEXT_PLAIN_UTF_8)
             .body(self.to_string())
     }
 }
 
-/// Middleware to convert plain-text error responses to user-friendly web pages
+/// Middleware to convert plain-text error responses to user-friendly web pages, or all error
+/// responses to JSON objects with `--api-errors`
 pub fn error_page_middleware<S, B>(
     req: ServiceRequest,
     srv: &S,
@@ -130,6 +171,14 @@ where
     async {
         let res = fut.await?.map_into_boxed_body();
 
+        let api_errors = res
+            .request()
+            .app_data::<MiniserveConfig>()
+            .is_some_and(|conf| conf.api_errors);
+        if api_errors && (res.status().is_client_error() || res.status().is_server_error()) {
+            return Ok(res.map_body(map_api_error));
+        }
+
         if (res.status().is_client_error() || res.status().is_server_error())
             && res.headers().get(header::CONTENT_TYPE).map(AsRef::as_ref)
                 == Some(mime::TEXT_PLAIN_UTF_8.essence_str().as_bytes())
@@ -168,6 +217,41 @@ fn map_error_page(req: &HttpRequest, head: &mut ResponseHead, body: BoxBody) ->
     BoxBody::new(render_error(error_msg, head.status, conf, return_address).into_string())
 }
 
+/// Replaces an error response by a JSON object with its message and status code
+///
+/// The message is the plain-text body of the response if there is one, and the reason of the
+/// status code otherwise.
+fn map_api_error(head: &mut ResponseHead, body: BoxBody) -> BoxBody {
+    let is_plain_text = head
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with(mime::TEXT_PLAIN.essence_str()));
+    let message = body
+        .try_into_bytes()
+        .ok()
+        .filter(|_| is_plain_text)
+        .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| {
+            head.status
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string()
+        });
+
+    head.headers.insert(
+        header::CONTENT_TYPE,
+        mime::APPLICATION_JSON.essence_str().try_into().unwrap(),
+    );
+
+    let error = serde_json::json!({
+        "error": message,
+        "code": head.status.as_u16(),
+    });
+    BoxBody::new(error.to_string())
+}
+
 pub fn log_error_chain(description: String) {
     for cause in description.lines() {
         log::error!("{}", cause);
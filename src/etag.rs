@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    HttpResponse,
+};
+use clap::ValueEnum;
+use futures::prelude::*;
+use percent_encoding::percent_decode_str;
+
+use crate::checksums::Checksums;
+use crate::file_upload::sanitize_path;
+use crate::MiniserveConfig;
+
+/// Cache of ETags computed from the content of files
+pub struct ContentEtags {
+    /// Files bigger than this keep the ETag computed by `actix_files` from their metadata
+    max_size: u64,
+
+    checksums: Checksums,
+}
+
+impl ContentEtags {
+    pub fn new(max_size: u64) -> Self {
+        ContentEtags {
+            max_size,
+            checksums: Checksums::default(),
+        }
+    }
+
+    /// Returns the content ETag of the file at `path`, or `None` if it is too big or can't be
+    /// read.
+    ///
+    /// The hash is only computed again if the modification date or size of the file changed.
+    pub fn get(&self, path: &Path) -> Option<String> {
+        let metadata = path.metadata().ok()?;
+        if metadata.len() > self.max_size {
+            return None;
+        }
+        let checksum = self.checksums.get(path)?;
+        Some(format!("\"{checksum}\""))
+    }
+}
+
+/// Strength of the ETags sent for files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EtagStrength {
+    /// ETags are strong validators, usable for range requests
+    Strong,
+
+    /// ETags derived from the modification date and size of files are marked as weak
+    Weak,
+}
+
+/// Middleware applying `--content-etag` and `--etag-strength` to the ETags of files
+///
+/// Unlike the default ETag, which is derived from the modification date of the file, a content
+/// ETag stays the same when a file is touched or deployed again with the same content. Content
+/// ETags are always strong, while the default ones are marked as weak with `--etag-strength weak`.
+pub fn etag_middleware<S, B>(
+    mut req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let (content_etag, weak) = match req.app_data::<MiniserveConfig>() {
+        Some(conf) => (
+            conf.content_etags
+                .as_ref()
+                .and_then(|content_etags| content_etags.get(&requested_file(req.path(), conf)?)),
+            conf.etag_strength == EtagStrength::Weak,
+        ),
+        None => (None, false),
+    };
+    let content_etag = content_etag.and_then(|etag| HeaderValue::try_from(etag).ok());
+    let enabled = content_etag.is_some() || weak;
+
+    // `actix_files` can't know about our ETags, so we evaluate the preconditions ourselves
+    let if_none_match = if enabled {
+        let headers = req.headers_mut();
+        let if_none_match = headers.remove(header::IF_NONE_MATCH).next();
+        if if_none_match.is_some() {
+            // If-Modified-Since must be ignored when If-None-Match is present
+            headers.remove(header::IF_MODIFIED_SINCE);
+        }
+
+        // A range is only sent if the entity tag in If-Range strongly matches the current one,
+        // which a weak ETag never does. Otherwise the whole file is sent.
+        let if_range_etag = headers.get(header::IF_RANGE).filter(|if_range| {
+            if_range.as_bytes().starts_with(b"\"") || if_range.as_bytes().starts_with(b"W/")
+        });
+        if let Some(if_range) = if_range_etag {
+            if content_etag.as_ref() != Some(if_range) {
+                headers.remove(header::RANGE);
+                headers.remove(header::IF_RANGE);
+            }
+        }
+
+        if_none_match
+    } else {
+        None
+    };
+
+    let fut = srv.call(req);
+
+    async move {
+        let mut res = fut.await?.map_into_boxed_body();
+
+        let is_file_content = matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT);
+        if !enabled || !is_file_content || !res.headers().contains_key(header::ETAG) {
+            return Ok(res);
+        }
+
+        let etag = match content_etag {
+            Some(etag) => Some(etag),
+            None => res
+                .headers()
+                .get(header::ETAG)
+                .and_then(|etag| match etag.as_bytes() {
+                    [b'W', b'/', ..] => Some(etag.clone()),
+                    etag => HeaderValue::from_bytes(&[b"W/", etag].concat()).ok(),
+                }),
+        };
+        if let Some(etag) = etag {
+            res.headers_mut().insert(header::ETAG, etag);
+        }
+
+        let etag_matched = match (&if_none_match, res.headers().get(header::ETAG)) {
+            (Some(if_none_match), Some(etag)) => etag_matches(if_none_match, etag),
+            _ => false,
+        };
+        if etag_matched {
+            let mut not_modified = HttpResponse::NotModified();
+            for name in [header::ETAG, header::LAST_MODIFIED, header::CACHE_CONTROL] {
+                if let Some(value) = res.headers().get(&name) {
+                    not_modified.insert_header((name, value.clone()));
+                }
+            }
+            return Ok(res.into_response(not_modified.body(BoxBody::new(()))));
+        }
+
+        Ok(res)
+    }
+}
+
+/// Resolves the request path to the file it targets in the served directory
+pub fn requested_file(request_path: &str, conf: &MiniserveConfig) -> Option<PathBuf> {
+    let path = request_path
+        .strip_prefix(&conf.route_prefix)
+        .unwrap_or(request_path);
+    let path = percent_decode_str(path).decode_utf8_lossy();
+    let path = sanitize_path(Path::new(&*path), true)?;
+    Some(conf.path.join(path))
+}
+
+/// Return `true` if `etag` is one of the entity tags listed in an If-None-Match header,
+/// using the weak comparison as required by RFC 7232
+fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let (if_none_match, etag) = match (if_none_match.to_str(), etag.to_str()) {
+        (Ok(if_none_match), Ok(etag)) => (if_none_match, etag.strip_prefix("W/").unwrap_or(etag)),
+        _ => return false,
+    };
+
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("\"abc\"", true)]
+    #[case("W/\"abc\"", true)]
+    #[case("\"xyz\", \"abc\"", true)]
+    #[case("*", true)]
+    #[case("\"xyz\"", false)]
+    #[case("abc", false)]
+    fn test_etag_matches(#[case] if_none_match: &str, #[case] expected: bool) {
+        assert_eq!(
+            etag_matches(
+                &HeaderValue::from_str(if_none_match).unwrap(),
+                &HeaderValue::from_static("\"abc\"")
+            ),
+            expected
+        );
+    }
+}
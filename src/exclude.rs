@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Files and directories left out of listings, archives and downloads with `--exclude`
+#[derive(Debug)]
+pub struct Exclusions {
+    /// Served directory, which the patterns are relative to
+    root: PathBuf,
+
+    globs: GlobSet,
+}
+
+impl Exclusions {
+    /// Compiles `patterns`, matched against paths relative to `root`
+    ///
+    /// As in .gitignore files, patterns without a slash match names at any depth, while the other
+    /// ones match from `root`. `*` doesn't match slashes, unlike `**`.
+    pub fn new(root: PathBuf, patterns: &[String]) -> Result<Self, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(glob(pattern)?);
+        }
+        Ok(Exclusions {
+            root,
+            globs: builder.build()?,
+        })
+    }
+
+    /// Returns `true` if `path`, relative to the served directory, or one of its parents is
+    /// excluded
+    pub fn excludes(&self, path: &Path) -> bool {
+        path.ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| self.globs.is_match(ancestor))
+    }
+
+    /// Same as `excludes`, for a path under the served directory
+    pub fn excludes_absolute(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .is_ok_and(|relative| self.excludes(relative))
+    }
+}
+
+/// Compiles `pattern`, matching from the root if it has a slash other than a trailing one
+fn glob(pattern: &str) -> Result<Glob, globset::Error> {
+    let pattern = pattern.trim_end_matches('/');
+    let pattern = match pattern.contains('/') {
+        true => pattern.trim_start_matches('/').to_string(),
+        false => format!("**/{pattern}"),
+    };
+    GlobBuilder::new(&pattern).literal_separator(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("*.log", "debug.log", true)]
+    #[case("*.log", "logs/debug.log", true)]
+    #[case("*.log", "debug.log.txt", false)]
+    #[case("target", "target", true)]
+    #[case("target/", "target/debug/miniserve", true)]
+    #[case("target", "src/target.rs", false)]
+    #[case("node_modules", "web/node_modules/a/index.js", true)]
+    #[case("docs/*.md", "docs/index.md", true)]
+    #[case("docs/*.md", "docs/api/index.md", false)]
+    #[case("docs/*.md", "src/docs/index.md", false)]
+    #[case("docs/**/*.md", "docs/api/index.md", true)]
+    #[case("/build", "build/out.txt", true)]
+    #[case("/build", "src/build", false)]
+    fn test_excludes(#[case] pattern: &str, #[case] path: &str, #[case] expected: bool) {
+        let exclusions = Exclusions::new(PathBuf::from("/srv"), &[pattern.to_string()]).unwrap();
+        assert_eq!(exclusions.excludes(Path::new(path)), expected);
+        assert_eq!(
+            exclusions.excludes_absolute(&Path::new("/srv").join(path)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_paths_outside_root_are_not_excluded() {
+        let exclusions = Exclusions::new(PathBuf::from("/srv"), &["*.log".to_string()]).unwrap();
+        assert!(!exclusions.excludes_absolute(Path::new("/var/debug.log")));
+    }
+}
@@ -0,0 +1,236 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use percent_encoding::utf8_percent_encode;
+
+use crate::auth::{self, CurrentUser};
+use crate::auth_files::AUTH_FILE;
+use crate::errors::ContextualError;
+use crate::listing::percent_encode_sets::PATH_SEGMENT;
+use crate::sensitive;
+use crate::MiniserveConfig;
+
+/// A file that may appear in the feed
+pub struct FeedEntry {
+    /// Path of the file, relative to the served directory
+    pub path: PathBuf,
+
+    /// Last modification date
+    pub modified: SystemTime,
+}
+
+/// Serves an Atom feed of the most recently modified files under the served directory
+pub async fn feed_handler(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let max_entries = conf.feed.unwrap_or_default();
+
+    let anonymous = auth::is_anonymous(conf, req.extensions().get::<CurrentUser>());
+
+    // The walk may be throttled, so it mustn't block the worker
+    let walk_conf = conf.clone();
+    let mut entries = web::block(move || {
+        let mut entries = Vec::new();
+        collect_files(
+            &walk_conf.path,
+            Path::new(""),
+            walk_conf.max_depth,
+            walk_conf.show_hidden,
+            &walk_conf,
+            &mut entries,
+        );
+        entries
+    })
+    .await
+    .map_err(|e| {
+        ContextualError::IoError("Failed to list files".to_string(), io::Error::other(e))
+    })?;
+    if anonymous {
+        entries.retain(|entry| auth::anonymous_allowed(conf, &entry.path));
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified));
+    entries.truncate(max_entries);
+
+    let base_url = format!(
+        "{}://{}{}",
+        req.connection_info().scheme(),
+        req.connection_info().host(),
+        conf.route_prefix
+    );
+    let title = conf
+        .title
+        .clone()
+        .unwrap_or_else(|| req.connection_info().host().into());
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/atom+xml; charset=utf-8")
+        .body(render_feed(&title, &base_url, &entries)))
+}
+
+/// Collects the files in `base.join(relative)` and in its subdirectories, down to `max_depth`
+/// levels if given, skipping hidden entries unless `show_hidden` is set, and symlinks when the
+/// configuration says so.
+///
+/// With a `max_depth` of 1, only the files of `base.join(relative)` are collected.
+///
+/// Files are handed to `entries` as they are found, so it may keep only some of them.
+///
+/// Entries which cannot be read are silently skipped.
+pub fn collect_files(
+    base: &Path,
+    relative: &Path,
+    max_depth: Option<usize>,
+    show_hidden: bool,
+    conf: &MiniserveConfig,
+    entries: &mut impl Extend<FeedEntry>,
+) {
+    let read_dir = match base.join(relative).read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+
+    for entry in read_dir.flatten() {
+        if let Some(throttle) = &conf.walk_throttle {
+            throttle.wait();
+        }
+        let file_name = entry.file_name();
+        if (!show_hidden && file_name.to_string_lossy().starts_with('.'))
+            || (conf.auth_files && file_name == AUTH_FILE)
+            || (conf.hide_sensitive && sensitive::is_sensitive(&file_name.to_string_lossy()))
+            || conf
+                .exclusions
+                .as_ref()
+                .is_some_and(|exclusions| exclusions.excludes(&relative.join(&file_name)))
+        {
+            continue;
+        }
+        let is_symlink = entry
+            .file_type()
+            .map(|file_type| file_type.is_symlink())
+            .unwrap_or(false);
+        if conf.no_symlinks && is_symlink {
+            continue;
+        }
+
+        let path = relative.join(&file_name);
+        match std::fs::metadata(entry.path()) {
+            // Don't follow symlinked directories to avoid walking in circles
+            // Protected directories are left out, as their credentials were not checked
+            Ok(metadata)
+                if metadata.is_dir() && !is_symlink && max_depth.is_none_or(|depth| depth > 1) =>
+            {
+                let protected = conf.auth_files && entry.path().join(AUTH_FILE).is_file();
+                if !protected {
+                    let max_depth = max_depth.map(|depth| depth - 1);
+                    collect_files(base, &path, max_depth, show_hidden, conf, entries)
+                }
+            }
+            Ok(metadata) if metadata.is_file() => entries.extend(Some(FeedEntry {
+                path,
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })),
+            _ => (),
+        }
+    }
+}
+
+/// Renders the Atom document for the given entries
+fn render_feed(title: &str, base_url: &str, entries: &[FeedEntry]) -> String {
+    let updated = entries
+        .first()
+        .map(|entry| entry.modified)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut feed = format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="utf-8"?>"#,
+            "\n",
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">"#,
+            "<title>{title}</title>",
+            r#"<link href="{base_url}/"/>"#,
+            "<id>{base_url}/</id>",
+            "<updated>{updated}</updated>",
+        ),
+        title = escape_xml(title),
+        base_url = escape_xml(base_url),
+        updated = to_rfc3339(updated),
+    );
+
+    for entry in entries {
+        let components = entry
+            .path
+            .iter()
+            .map(|component| component.to_string_lossy())
+            .collect::<Vec<_>>();
+        let name = components.join("/");
+        let link = entry_url(base_url, &entry.path);
+        feed.push_str(&format!(
+            concat!(
+                "<entry>",
+                "<title>{name}</title>",
+                r#"<link href="{link}"/>"#,
+                "<id>{link}</id>",
+                "<updated>{updated}</updated>",
+                "</entry>",
+            ),
+            name = escape_xml(&name),
+            link = escape_xml(&link),
+            updated = to_rfc3339(entry.modified),
+        ));
+    }
+
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// Returns the absolute URL of the file at `path`, relative to the served directory at `base_url`
+pub fn entry_url(base_url: &str, path: &Path) -> String {
+    let encoded_path = path
+        .iter()
+        .map(|component| {
+            utf8_percent_encode(&component.to_string_lossy(), PATH_SEGMENT).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{base_url}/{encoded_path}")
+}
+
+/// Formats a SystemTime as required by Atom
+pub fn to_rfc3339(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339()
+}
+
+/// Escapes the characters which have a special meaning in XML
+pub fn escape_xml(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                _ => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("plain", "plain")]
+    #[case("a & b", "a &amp; b")]
+    #[case("<\"'>", "&lt;&quot;&apos;&gt;")]
+    #[case("bell\u{7}", "bell")]
+    fn test_escape_xml(#[case] input: &str, #[case] output: &str) {
+        assert_eq!(escape_xml(input), output);
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::auth::BasicAuthParams;
+use crate::auth_files::{self, AUTH_FILE};
+use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::listing;
+
+/// Outcome of the deletion of one of the requested paths
+#[derive(Serialize)]
+struct DeleteResult {
+    path: String,
+    deleted: bool,
+
+    /// Reason of the failure, if the path couldn't be deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Handles a request to delete several files or directories at once.
+///
+/// The directory they are in is expected as `path` query parameter, and their names as `file`
+/// fields of the url-encoded form in the body. Each of them is deleted independently, and the
+/// outcome of every deletion is returned as a JSON array.
+///
+/// With `--trash-dir`, they are moved to the trash instead, and old deletions are purged from it.
+pub async fn delete_files(
+    req: HttpRequest,
+    form: web::Form<Vec<(String, String)>>,
+) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+
+    let query_params = listing::extract_query_parameters(&req);
+    let dir = query_params.path.as_ref().ok_or_else(|| {
+        ContextualError::InvalidHttpRequestError("Missing query parameter 'path'".to_string())
+    })?;
+
+    let app_root_dir = conf.path.canonicalize().map_err(|e| {
+        ContextualError::IoError("Failed to resolve path served by miniserve".to_string(), e)
+    })?;
+
+    let results = form
+        .into_inner()
+        .into_iter()
+        .filter(|(field, _)| field == "file")
+        .map(|(_, name)| {
+            let path = dir.join(&name);
+            let cred = req.extensions().get::<BasicAuthParams>().cloned();
+            let error = delete_path(&path, &app_root_dir, conf, cred.as_ref()).err();
+            if let Some(error) = &error {
+                log::warn!("Failed to delete {}: {}", path.display(), error);
+            }
+            DeleteResult {
+                path: path.to_string_lossy().to_string(),
+                deleted: error.is_none(),
+                error: error.map(|e| e.to_string()),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(trash) = &conf.trash {
+        trash.purge(Utc::now());
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(mime::APPLICATION_JSON)
+        .body(serde_json::to_string(&results).unwrap()))
+}
+
+/// Deletes the file or directory at `path`, relative to the served directory
+///
+/// With `--auth-files`, `cred` must satisfy the auth files above `path`, and directories
+/// protected by one can't be deleted.
+fn delete_path(
+    path: &Path,
+    app_root_dir: &Path,
+    conf: &crate::MiniserveConfig,
+    cred: Option<&BasicAuthParams>,
+) -> Result<(), ContextualError> {
+    let relative_path = sanitize_path(path, conf.show_hidden)
+        .filter(|path| path.components().next().is_some())
+        .ok_or_else(|| ContextualError::InvalidPathError("Invalid path to delete".to_string()))?;
+    if conf
+        .exclusions
+        .as_ref()
+        .is_some_and(|exclusions| exclusions.excludes(&relative_path))
+    {
+        return Err(ContextualError::InvalidPathError(
+            "Invalid path to delete".to_string(),
+        ));
+    }
+    let target = app_root_dir.join(&relative_path);
+
+    let metadata = target
+        .symlink_metadata()
+        .map_err(|e| ContextualError::IoError(format!("Failed to access {}", path.display()), e))?;
+
+    // Disallow going through symlinks out of the served directory, and deleting symlinks which
+    // are not shown anyway
+    let through_symlink = target
+        .parent()
+        .and_then(|parent| parent.canonicalize().ok())
+        .is_none_or(|parent| !parent.starts_with(app_root_dir));
+    if conf.no_symlinks && (through_symlink || metadata.file_type().is_symlink()) {
+        return Err(ContextualError::InvalidPathError(format!(
+            "{} is or goes through a symlink",
+            path.display()
+        )));
+    }
+
+    if conf.auth_files {
+        auth_files::authorize(conf, relative_path.parent().unwrap_or(Path::new("")), cred)?;
+        let is_auth_file = relative_path
+            .file_name()
+            .is_some_and(|name| name == AUTH_FILE);
+        if is_auth_file
+            || metadata.is_dir()
+                && (target.join(AUTH_FILE).is_file()
+                    || auth_files::has_protected_descendants(&target))
+        {
+            return Err(ContextualError::ProtectedDirectoryError(
+                path.display().to_string(),
+            ));
+        }
+    }
+
+    let size = conf.upload_quota.as_ref().map(|_| total_size(&target));
+
+    if let Some(trash) = &conf.trash {
+        trash.put(&target, &relative_path, Utc::now())
+    } else if metadata.is_dir() {
+        std::fs::remove_dir_all(&target)
+    } else {
+        std::fs::remove_file(&target)
+    }
+    .map_err(|e| ContextualError::IoError(format!("Failed to delete {}", path.display()), e))?;
+
+    // Deleted files, whether removed or moved to the trash, no longer count in the upload quota
+    if let (Some(quota), Some(size)) = (&conf.upload_quota, size) {
+        if let Err(e) = quota.release(size) {
+            log::error!("Failed to update the upload quota: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the total size of the files at `path`, without following symlinks
+fn total_size(path: &Path) -> u64 {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => path
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| total_size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => 0,
+    }
+}
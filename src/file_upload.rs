@@ -1,51 +1,135 @@
 use std::{
-    io::Write,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    net::IpAddr,
     path::{Component, Path, PathBuf},
+    time::Instant,
 };
 
-use actix_web::{http::header, HttpRequest, HttpResponse};
-use futures::TryStreamExt;
+use actix_multipart::MultipartError;
+use actix_web::{error::PayloadError, http::header, HttpRequest, HttpResponse};
+use clap::ValueEnum;
+use futures::{StreamExt, TryStreamExt};
 
+use crate::auth_files::AUTH_FILE;
+use crate::dir_config::DIR_CONFIG_FILE;
 use crate::errors::ContextualError;
 use crate::listing;
+use crate::rate_limit::BandwidthLimiter;
+use crate::upload_quota::SIDECAR_FILE_NAME;
 
-/// Saves file data from a multipart form field (`field`) to `file_path`, optionally overwriting
-/// existing file.
+/// Bandwidth limiter of uploads, along with the client it applies to
+type UploadRateLimit<'a> = Option<(&'a BandwidthLimiter, IpAddr)>;
+
+/// What to do when uploading a file whose name is already taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnDuplicate {
+    /// Replace the existing file
+    Overwrite,
+
+    /// Save the upload under a free name, made by appending a number to its name
+    Rename,
+
+    /// Refuse the upload
+    Error,
+}
+
+/// Creates the file to upload to at `file_path`, or at a free name with `OnDuplicate::Rename`
+///
+/// Unless overwriting, files are created only if they don't exist yet, so that concurrent uploads
+/// of the same name never write to the same file.
+///
+/// Returns the file and its path.
+fn create_file(
+    file_path: PathBuf,
+    on_duplicate: OnDuplicate,
+) -> Result<(File, PathBuf), ContextualError> {
+    let create_error = |path: &Path, e| {
+        ContextualError::IoError(format!("Failed to create {}", path.display()), e)
+    };
+    if on_duplicate == OnDuplicate::Overwrite {
+        let file = File::create(&file_path).map_err(|e| create_error(&file_path, e))?;
+        return Ok((file, file_path));
+    }
+
+    let mut candidate = file_path.clone();
+    for n in 1.. {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(file) => return Ok((file, candidate)),
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => {
+                return Err(create_error(&candidate, e))
+            }
+            Err(_) if on_duplicate == OnDuplicate::Rename => {
+                candidate = numbered_path(&file_path, n);
+            }
+            Err(_) => return Err(ContextualError::DuplicateFileError),
+        }
+    }
+    unreachable!()
+}
+
+/// Returns `path` with `n` appended to the stem of its file name, as in "file (1).txt"
+fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!(" ({n})"));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
+
+/// Saves file data from a multipart form field (`field`) to `file_path`, or elsewhere if it is
+/// taken, depending on `on_duplicate`.
 ///
 /// Returns total bytes written to file.
 async fn save_file(
     field: actix_multipart::Field,
     file_path: PathBuf,
-    overwrite_files: bool,
+    on_duplicate: OnDuplicate,
+    rate_limit: UploadRateLimit<'_>,
 ) -> Result<u64, ContextualError> {
-    if !overwrite_files && file_path.exists() {
-        return Err(ContextualError::DuplicateFileError);
-    }
-
-    let file = std::fs::File::create(&file_path).map_err(|e| {
-        ContextualError::IoError(format!("Failed to create {}", file_path.display()), e)
-    })?;
+    let (file, file_path) = create_file(file_path, on_duplicate)?;
 
-    let (_, written_len) = field
+    let written = field
         .map_err(ContextualError::MultipartError)
         .try_fold((file, 0u64), |(mut file, written_len), bytes| async move {
             file.write_all(bytes.as_ref())
                 .map_err(|e| ContextualError::IoError("Failed to write to file".to_string(), e))?;
+            // Not reading the rest of the body in the meantime slows down the client
+            if let Some((limiter, ip)) = rate_limit {
+                let delay = limiter.take(ip, bytes.len(), Instant::now());
+                if !delay.is_zero() {
+                    actix_web::rt::time::sleep(delay).await;
+                }
+            }
             Ok((file, written_len + bytes.len() as u64))
         })
-        .await?;
-
-    Ok(written_len)
+        .await;
+
+    // Partly uploaded files are not kept
+    match written {
+        Ok((_, written_len)) => Ok(written_len),
+        Err(e) => {
+            let _ = std::fs::remove_file(&file_path);
+            Err(e)
+        }
+    }
 }
 
 /// Handles a single field in a multipart form
 async fn handle_multipart(
     mut field: actix_multipart::Field,
     path: PathBuf,
-    overwrite_files: bool,
+    on_duplicate: OnDuplicate,
     allow_mkdir: bool,
     allow_hidden_paths: bool,
     allow_symlinks: bool,
+    rate_limit: UploadRateLimit<'_>,
 ) -> Result<u64, ContextualError> {
     let field_name = field.name().to_string();
 
@@ -83,11 +167,21 @@ async fn handle_multipart(
                         e.to_string(),
                     )
                 })?;
+                if !is_valid_dir_name(mkdir_path) {
+                    return Err(ContextualError::InvalidHttpRequestError(format!(
+                        "Invalid directory name {mkdir_path:?}"
+                    )));
+                }
                 let mkdir_path = mkdir_path.replace('\\', "/");
                 absolute_path.push(&mkdir_path);
                 user_given_path.push(&mkdir_path);
             }
-            _ => {
+            Ok(None) => {
+                return Err(ContextualError::InvalidHttpRequestError(
+                    "Invalid directory name \"\"".to_string(),
+                ))
+            }
+            Err(_) => {
                 return Err(ContextualError::ParseError(
                     "Failed to parse 'mkdir' path".to_string(),
                     "".to_string(),
@@ -130,6 +224,13 @@ async fn handle_multipart(
         )
     })?;
 
+    // Uploading them would change the credentials, the settings of directories or the quota
+    if is_reserved_name(Path::new(&filename)) {
+        return Err(ContextualError::InvalidHttpRequestError(format!(
+            "{filename} is reserved by miniserve"
+        )));
+    }
+
     let filename_path = sanitize_path(Path::new(&filename), false).ok_or_else(|| {
         ContextualError::InvalidPathError("Invalid file name to upload".to_string())
     })?;
@@ -141,7 +242,12 @@ async fn handle_multipart(
         ));
     }
 
-    save_file(field, path.join(filename_path), overwrite_files).await
+    save_file(field, path.join(filename_path), on_duplicate, rate_limit).await
+}
+
+/// Answers upload and mkdir requests when uploading is not enabled
+pub async fn upload_disabled() -> Result<HttpResponse, ContextualError> {
+    Err(ContextualError::UploadDisabledError)
 }
 
 /// Handle incoming request to upload a file or create a directory.
@@ -195,26 +301,109 @@ pub async fn upload_file(
         )),
     }?;
 
-    actix_multipart::Multipart::new(req.headers(), payload)
+    // Uploads announcing a larger body are rejected before receiving it, the others as soon as
+    // they exceed the limit
+    let content_length = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok()?.parse::<u64>().ok());
+    if let Some(max_size) = conf.max_upload_size {
+        if content_length.is_some_and(|len| len > max_size.as_u64()) {
+            return Err(ContextualError::UploadTooLargeError(max_size));
+        }
+    }
+
+    // Held until the upload is over
+    let _upload_permit = match (&conf.upload_concurrency_limiter, req.peer_addr()) {
+        (Some(limiter), Some(peer_addr)) => Some(
+            limiter
+                .acquire(peer_addr.ip())
+                .ok_or(ContextualError::TooManyUploadsError)?,
+        ),
+        _ => None,
+    };
+
+    // The size of the files isn't known before they are received, so the whole request body is
+    // accounted for against the quota until the upload is over
+    let reserved = match &conf.upload_quota {
+        Some(quota) => {
+            let content_length = content_length.ok_or_else(|| {
+                ContextualError::InvalidHttpRequestError(
+                    "Missing Content-Length header, required by the upload quota".to_string(),
+                )
+            })?;
+            quota.reserve(content_length)?;
+            content_length
+        }
+        None => 0,
+    };
+
+    let rate_limit = conf
+        .upload_rate_limiter
+        .as_deref()
+        .zip(req.peer_addr().map(|addr| addr.ip()));
+    let max_size = conf.max_upload_size;
+    let mut received_len = 0;
+    let payload = payload.map(move |bytes| {
+        let bytes = bytes?;
+        received_len += bytes.len() as u64;
+        match max_size {
+            Some(max_size) if received_len > max_size.as_u64() => Err(PayloadError::Overflow),
+            _ => Ok(bytes),
+        }
+    });
+    let fields = actix_multipart::Multipart::new(req.headers(), payload)
         .map_err(ContextualError::MultipartError)
         .and_then(|field| {
             handle_multipart(
                 field,
                 non_canonicalized_target_dir.clone(),
-                conf.overwrite_files,
+                conf.on_duplicate,
                 conf.mkdir_enabled,
                 conf.show_hidden,
                 !conf.no_symlinks,
+                rate_limit,
             )
-        })
-        .try_collect::<Vec<u64>>()
-        .await?;
+        });
+    futures::pin_mut!(fields);
+
+    // The files saved before a field fails are kept, so they count against the quota
+    let mut written_len = 0;
+    let mut written = Ok(());
+    while let Some(field) = fields.next().await {
+        match field {
+            Ok(len) => written_len += len,
+            Err(e) => {
+                written = Err(e);
+                break;
+            }
+        }
+    }
+
+    if let Some(quota) = &conf.upload_quota {
+        quota.commit(reserved, written_len)?;
+    }
+    // The payload overflows when exceeding --max-upload-size
+    if let (
+        Some(max_size),
+        Err(ContextualError::MultipartError(MultipartError::Payload(PayloadError::Overflow))),
+    ) = (max_size, &written)
+    {
+        return Err(ContextualError::UploadTooLargeError(max_size));
+    }
+    written?;
 
     Ok(HttpResponse::SeeOther()
         .append_header((header::LOCATION, return_path))
         .finish())
 }
 
+/// Whether `name` may be used to create a directory, which excludes blank names and names with
+/// control characters
+fn is_valid_dir_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.chars().any(char::is_control)
+}
+
 /// Guarantee that the path is relative and cannot traverse back to parent directories
 /// and optionally prevent traversing hidden directories.
 ///
@@ -244,6 +433,16 @@ pub fn sanitize_path(path: &Path, traverse_hidden: bool) -> Option<PathBuf> {
     Some(buf)
 }
 
+/// Names of the files holding the settings or the state of miniserve, which can't be uploaded
+pub const RESERVED_NAMES: &[&str] = &[AUTH_FILE, DIR_CONFIG_FILE, SIDECAR_FILE_NAME];
+
+/// Returns `true` if the file at `path` has the name of a file holding the settings or the state
+/// of miniserve
+pub fn is_reserved_name(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| RESERVED_NAMES.iter().any(|reserved| name == *reserved))
+}
+
 /// Returns if a path goes through a symolic link
 fn contains_symlink(path: &PathBuf) -> bool {
     let mut joined_path = PathBuf::new();
@@ -296,4 +495,24 @@ mod tests {
     fn test_sanitize_path_no_hidden_files(#[case] input: &str) {
         assert_eq!(sanitize_path(Path::new(input), false), None);
     }
+
+    #[rstest]
+    #[case("file.txt", 1, "file (1).txt")]
+    #[case("dir/archive.tar.gz", 2, "dir/archive.tar (2).gz")]
+    #[case("README", 3, "README (3)")]
+    #[case(".hidden", 1, ".hidden (1)")]
+    fn test_numbered_path(#[case] input: &str, #[case] n: usize, #[case] output: &str) {
+        assert_eq!(numbered_path(Path::new(input), n), Path::new(output));
+    }
+
+    #[rstest]
+    #[case("hello", true)]
+    #[case("hello world/nested", true)]
+    #[case("", false)]
+    #[case("   ", false)]
+    #[case("new\nline", false)]
+    #[case("nul\0byte", false)]
+    fn test_is_valid_dir_name(#[case] name: &str, #[case] valid: bool) {
+        assert_eq!(is_valid_dir_name(name), valid);
+    }
 }
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use git2::{ObjectType, Repository, Tree};
+use tempfile::TempDir;
+
+/// Files of a git repository at a given ref, extracted to a temporary directory
+///
+/// The directory is removed when this is dropped.
+pub struct GitSnapshot {
+    dir: TempDir,
+
+    /// Name of the served directory, same as the one of the repository
+    name: PathBuf,
+}
+
+impl GitSnapshot {
+    /// Extracts the tree of `git_ref` (a branch, tag, commit id, ...) of the repository at
+    /// `repo_path`
+    ///
+    /// Submodules are left out, as their content isn't part of the repository.
+    pub fn extract(repo_path: &Path, git_ref: &str) -> Result<Self> {
+        let repo = Repository::open(repo_path)
+            .context(format!("{repo_path:?} is not a git repository"))?;
+        let commit = repo
+            .revparse_single(git_ref)
+            .and_then(|object| object.peel_to_commit())
+            .context(format!("Couldn't resolve git ref {git_ref:?}"))?;
+
+        let name = repo_path
+            .canonicalize()?
+            .file_name()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("repository"));
+        let snapshot = GitSnapshot {
+            dir: tempfile::Builder::new()
+                .prefix("miniserve-git-")
+                .tempdir()?,
+            name,
+        };
+        fs::create_dir(snapshot.path())?;
+        extract_tree(&repo, &commit.tree()?, &snapshot.path())
+            .context(format!("Couldn't extract git ref {git_ref:?}"))?;
+
+        log::info!("Serving git ref {} (commit {})", git_ref, commit.id());
+        Ok(snapshot)
+    }
+
+    /// Path of the extracted files
+    pub fn path(&self) -> PathBuf {
+        self.dir.path().join(&self.name)
+    }
+}
+
+/// Writes the blobs of `tree` to `dir`, recursively
+fn extract_tree(repo: &Repository, tree: &Tree, dir: &Path) -> Result<()> {
+    for entry in tree.iter() {
+        let name = match entry.name() {
+            Some(name) => name,
+            None => continue,
+        };
+        let path = dir.join(name);
+
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                fs::create_dir(&path)?;
+                extract_tree(repo, &entry.to_object(repo)?.peel_to_tree()?, &path)?;
+            }
+            Some(ObjectType::Blob) => {
+                let blob = entry.to_object(repo)?.peel_to_blob()?;
+                // Symbolic links are stored as blobs containing their target
+                #[cfg(unix)]
+                if entry.filemode() == 0o120000 {
+                    use std::os::unix::ffi::OsStrExt;
+                    let target = std::ffi::OsStr::from_bytes(blob.content());
+                    std::os::unix::fs::symlink(target, &path)?;
+                    continue;
+                }
+                fs::write(&path, blob.content())?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
@@ -1,26 +1,49 @@
 #![allow(clippy::format_push_string)]
-use std::io;
+use std::cmp::Ordering;
+use std::fs::{DirEntry, Metadata};
+use std::io::{self, Write};
 use std::path::{Component, Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
-use actix_web::{dev::ServiceResponse, web::Query, HttpMessage, HttpRequest, HttpResponse};
+use actix_web::{
+    dev::ServiceResponse, http::header, web::Query, HttpMessage, HttpRequest, HttpResponse,
+    ResponseError,
+};
 use bytesize::ByteSize;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use comrak::{markdown_to_html, ComrakOptions};
 use percent_encoding::{percent_decode_str, utf8_percent_encode};
 use regex::Regex;
-use serde::Deserialize;
-use strum::{Display, EnumString};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
 
-use crate::archive::ArchiveMethod;
-use crate::auth::CurrentUser;
+use crate::archive::{self, ArchiveMethod, ArchiveSettings};
+use crate::archive_jobs;
+use crate::auth::{self, CurrentUser};
+use crate::auth_files::AUTH_FILE;
+use crate::checksums::Checksums;
+use crate::dir_config::{read_dir_config, DirConfig};
 use crate::errors::{self, ContextualError};
+use crate::feed;
 use crate::renderer;
+use crate::sensitive;
 
 use self::percent_encode_sets::PATH_SEGMENT;
 
+/// Media type of the streamed JSON Lines listing
+const NDJSON: &str = "application/x-ndjson";
+
+/// Number of entries on a page of HTML listings, unless `?per_page=` is given
+const DEFAULT_PER_PAGE: usize = 1000;
+
+/// Maximum number of entries on a page of HTML listings
+const MAX_PER_PAGE: usize = 10_000;
+
 /// "percent-encode sets" as defined by WHATWG specs:
 /// https://url.spec.whatwg.org/#percent-encoded-bytes
-mod percent_encode_sets {
+pub mod percent_encode_sets {
     use percent_encoding::{AsciiSet, CONTROLS};
     const BASE: &AsciiSet = &CONTROLS.add(b'%');
     pub const QUERY: &AsciiSet = &BASE.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
@@ -34,9 +57,228 @@ pub struct QueryParameters {
     pub path: Option<PathBuf>,
     pub sort: Option<SortingMethod>,
     pub order: Option<SortingOrder>,
+    pub group: Option<GroupDirs>,
+    #[serde(default, deserialize_with = "deserialize_optional_flag")]
     pub raw: Option<bool>,
-    pub mkdir_name: Option<String>,
-    download: Option<ArchiveMethod>,
+    pub download: Option<ArchiveMethod>,
+    pub archive: Option<ArchiveQuery>,
+    pub json: Option<String>,
+    pub ndjson: Option<String>,
+    pub format: Option<ListingFormat>,
+    #[serde(default, deserialize_with = "deserialize_optional_flag")]
+    pub recursive: Option<bool>,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    #[serde(
+        rename = "type",
+        default,
+        deserialize_with = "deserialize_optional_file_type"
+    )]
+    pub file_type: Option<FileType>,
+    pub q: Option<String>,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_optional_flag")]
+    pub checksums: Option<bool>,
+    pub checksum: Option<String>,
+    pub theme: Option<renderer::ThemeSlug>,
+    #[serde(default, deserialize_with = "deserialize_optional_flag")]
+    pub hidden: Option<bool>,
+}
+
+impl QueryParameters {
+    /// Returns the text searched for with `?q=`, if not empty
+    pub fn search(&self) -> Option<&str> {
+        self.q.as_deref().filter(|search| !search.is_empty())
+    }
+
+    /// Whether hidden files are shown, as requested with `?hidden=` or otherwise with --hidden
+    pub fn shows_hidden(&self, conf: &crate::MiniserveConfig) -> bool {
+        self.hidden.unwrap_or(conf.show_hidden)
+    }
+
+    /// Returns the settings of the archives created for the request, which include hidden files
+    /// if they are shown
+    pub fn archive_settings(&self, conf: &crate::MiniserveConfig) -> ArchiveSettings {
+        ArchiveSettings {
+            include_hidden: self.shows_hidden(conf),
+            ..conf.archive_settings.clone()
+        }
+    }
+}
+
+/// Position of a page of an HTML listing, from `?page=` and `?per_page=`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    /// Number of the page, from 1
+    pub page: usize,
+
+    /// Maximum number of entries on a page
+    pub per_page: usize,
+
+    /// Number of entries on all pages
+    pub total: usize,
+}
+
+impl Pagination {
+    /// Returns the number of pages holding entries
+    pub fn page_count(&self) -> usize {
+        self.total.div_ceil(self.per_page)
+    }
+
+    /// Returns the number of the previous page holding entries, if any
+    pub fn previous(&self) -> Option<usize> {
+        (self.page > 1 && self.page_count() > 0).then(|| (self.page - 1).min(self.page_count()))
+    }
+
+    /// Returns the number of the next page holding entries, if any
+    pub fn next(&self) -> Option<usize> {
+        (self.page < self.page_count()).then(|| self.page + 1)
+    }
+}
+
+/// Number and total size of the files of an HTML listing, on all of its pages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirSummary {
+    /// Number of files, not counting directories
+    pub file_count: u64,
+
+    /// Number of directories
+    pub dir_count: u64,
+
+    /// Sum of the sizes of the files
+    pub total_size: ByteSize,
+}
+
+impl DirSummary {
+    /// Sums up the files among `entries`, without descending into directories
+    fn of(entries: &[Entry]) -> Self {
+        let files = entries.iter().filter(|entry| entry.is_file());
+        DirSummary {
+            file_count: files.clone().count() as u64,
+            dir_count: entries.iter().filter(|entry| entry.is_dir()).count() as u64,
+            total_size: ByteSize::b(
+                files
+                    .filter_map(|entry| entry.size)
+                    .map(|s| s.as_u64())
+                    .sum(),
+            ),
+        }
+    }
+}
+
+/// Returns the entries on the page requested with `?page=` and `?per_page=`
+///
+/// Pages are numbered from 1, so that page 0 holds no entries, as pages after the last one.
+fn paginate(entries: Vec<Entry>, query_params: &QueryParameters) -> (Vec<Entry>, Pagination) {
+    let pagination = Pagination {
+        page: query_params.page.unwrap_or(1),
+        per_page: query_params
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE),
+        total: entries.len(),
+    };
+    let entries = match pagination.page.checked_sub(1) {
+        Some(skipped_pages) => entries
+            .into_iter()
+            .skip(skipped_pages.saturating_mul(pagination.per_page))
+            .take(pagination.per_page)
+            .collect(),
+        None => Vec::new(),
+    };
+    (entries, pagination)
+}
+
+/// Alternative formats of directory listings
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingFormat {
+    /// Plain list of the absolute URLs of the files
+    Urls,
+
+    /// Spreadsheet of the entries, with their size, modification date and type
+    Csv,
+}
+
+/// Reports about the archives of a directory, with `?archive=`
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveQuery {
+    /// Files an archive would include and their total size, as JSON
+    Plan,
+}
+
+/// Categories of files which listings can be filtered by, with `?type=`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumString, Display, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum FileType {
+    Image,
+    Video,
+    Audio,
+    Text,
+    Archive,
+}
+
+impl FileType {
+    /// Returns the category of the file named `file_name`, from the MIME type it is served with
+    pub fn of(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        let mime = actix_files::file_extension_to_mime(&extension.to_lowercase());
+        match (mime.type_(), mime.subtype().as_str()) {
+            (mime::IMAGE, _) => Some(FileType::Image),
+            (mime::VIDEO, _) => Some(FileType::Video),
+            (mime::AUDIO, _) => Some(FileType::Audio),
+            (mime::TEXT, _) => Some(FileType::Text),
+            (
+                mime::APPLICATION,
+                "zip" | "gzip" | "x-tar" | "x-bzip" | "x-bzip2" | "x-xz" | "zstd"
+                | "x-7z-compressed" | "vnd.rar" | "x-rar-compressed",
+            ) => Some(FileType::Archive),
+            _ => None,
+        }
+    }
+
+    /// Label of the category in the listing
+    pub fn label(self) -> &'static str {
+        match self {
+            FileType::Image => "Images",
+            FileType::Video => "Videos",
+            FileType::Audio => "Audio",
+            FileType::Text => "Text",
+            FileType::Archive => "Archives",
+        }
+    }
+}
+
+/// Deserializes the `type` query parameter, which is empty to show all entries
+fn deserialize_optional_file_type<'de, D>(deserializer: D) -> Result<Option<FileType>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None | Some("") => Ok(None),
+        Some(file_type) => file_type
+            .parse()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserializes an optional boolean query parameter, accepting `1` and `0` as well
+fn deserialize_optional_flag<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None => Ok(None),
+        Some("true" | "1") => Ok(Some(true)),
+        Some("false" | "0") => Ok(Some(false)),
+        Some(other) => Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Str(other),
+            &"true, false, 1 or 0",
+        )),
+    }
 }
 
 /// Available sorting methods
@@ -54,6 +296,17 @@ pub enum SortingMethod {
     Date,
 }
 
+impl SortingMethod {
+    /// Order used when none is requested: names go from A to Z, while the largest and most
+    /// recently modified entries come first, as they always have without an explicit `order`
+    fn default_order(self) -> SortingOrder {
+        match self {
+            SortingMethod::Name => SortingOrder::Ascending,
+            SortingMethod::Size | SortingMethod::Date => SortingOrder::Descending,
+        }
+    }
+}
+
 /// Available sorting orders
 #[derive(Deserialize, Clone, EnumString, Display, Copy)]
 pub enum SortingOrder {
@@ -68,6 +321,87 @@ pub enum SortingOrder {
     Descending,
 }
 
+/// Available ways of grouping directories relative to files
+#[derive(Deserialize, Clone, Copy, ValueEnum, EnumString, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum GroupDirs {
+    /// List directories before files
+    First,
+
+    /// List directories after files
+    Last,
+
+    /// Interleave directories with files
+    None,
+}
+
+/// Entry of the JSON listing
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    name: &'a str,
+    is_dir: bool,
+
+    /// Size in bytes, `null` for directories
+    size: Option<u64>,
+
+    /// Last modification date as an RFC 3339 timestamp
+    last_modified: Option<String>,
+
+    /// SHA-256 checksum of files, only present when requested with `?checksums=1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+}
+
+/// Position in a paginated JSON listing, pointing at the last entry of the previous page
+///
+/// Since it stores the sorting key of that entry rather than an offset, entries added or removed
+/// while paginating don't cause other entries to be skipped or repeated.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    name: String,
+    is_dir: bool,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+}
+
+impl Cursor {
+    fn from_entry(entry: &Entry) -> Self {
+        Cursor {
+            name: entry.name.clone(),
+            is_dir: entry.is_dir(),
+            size: entry.size.map(|size| size.as_u64()),
+            modified: entry.last_modification_date,
+        }
+    }
+
+    /// Encodes the cursor as an opaque string
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).unwrap())
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        serde_json::from_slice(&hex::decode(cursor).ok()?).ok()
+    }
+
+    /// Builds an entry which compares to other entries like the one the cursor points at
+    fn to_entry(&self) -> Entry {
+        let entry_type = if self.is_dir {
+            EntryType::Directory
+        } else {
+            EntryType::File
+        };
+        Entry::new(
+            self.name.clone(),
+            entry_type,
+            String::new(),
+            self.size.map(ByteSize::b),
+            self.modified,
+            None,
+        )
+    }
+}
+
 #[derive(PartialEq, Eq)]
 /// Possible entry types
 pub enum EntryType {
@@ -220,164 +554,202 @@ pub fn directory_listing(
     };
 
     let query_params = extract_query_parameters(req);
+    let anonymous = auth::is_anonymous(conf, current_user);
+    let checksums = conf
+        .checksums
+        .clone()
+        .filter(|_| query_params.checksums == Some(true));
+    let relative_dir = dir.path.strip_prefix(&dir.base).unwrap_or(&dir.path);
+    let show_hidden = query_params.shows_hidden(conf);
+
+    if query_params.format == Some(ListingFormat::Urls) {
+        return Ok(ServiceResponse::new(
+            req.clone(),
+            urls_listing(
+                req,
+                &dir.base,
+                relative_dir,
+                query_params.recursive.unwrap_or(false),
+                show_hidden,
+                anonymous,
+            ),
+        ));
+    }
+
+    if query_params.ndjson.is_some() || accepts(req, NDJSON) {
+        return Ok(ServiceResponse::new(
+            req.clone(),
+            ndjson_listing(
+                dir.path.clone(),
+                relative_dir.to_path_buf(),
+                conf.clone(),
+                show_hidden,
+                anonymous,
+                checksums,
+            ),
+        ));
+    }
 
     let mut entries: Vec<Entry> = Vec::new();
     let mut readme: Option<(String, String)> = None;
     let readme_rx: Regex = Regex::new("^readme([.](md|txt))?$").unwrap();
 
-    for entry in dir.path.read_dir()? {
-        if dir.is_visible(&entry) || conf.show_hidden {
-            let entry = entry?;
-            // show file url as relative to static path
-            let file_name = entry.file_name().to_string_lossy().to_string();
-            let (is_symlink, metadata) = match entry.metadata() {
-                Ok(metadata) if metadata.file_type().is_symlink() => {
-                    // for symlinks, get the metadata of the original file
-                    (true, std::fs::metadata(entry.path()))
-                }
-                res => (false, res),
-            };
-            let symlink_dest = (is_symlink && conf.show_symlink_info)
-                .then(|| entry.path())
-                .and_then(|path| std::fs::read_link(path).ok())
-                .map(|path| path.to_string_lossy().into_owned());
-            let file_url = base
-                .join(utf8_percent_encode(&file_name, PATH_SEGMENT).to_string())
-                .to_string_lossy()
-                .to_string();
-
-            // if file is a directory, add '/' to the end of the name
-            if let Ok(metadata) = metadata {
-                if conf.no_symlinks && is_symlink {
-                    continue;
-                }
-                let last_modification_date = match metadata.modified() {
-                    Ok(date) => Some(date),
-                    Err(_) => None,
-                };
+    // Hidden entries are filtered by name only, so that no metadata is fetched before
+    // `stat_entries`, which may do it concurrently
+    let dir_entries = dir
+        .path
+        .read_dir()?
+        .filter(|entry| {
+            entry.as_ref().map_or(show_hidden, |entry| {
+                let file_name = entry.file_name();
+                let file_name = file_name.to_string_lossy();
+                !((!show_hidden && file_name.starts_with('.'))
+                    || (conf.auth_files && file_name == AUTH_FILE)
+                    || (conf.hide_sensitive && sensitive::is_sensitive(&file_name))
+                    || conf
+                        .exclusions
+                        .as_ref()
+                        .is_some_and(|exclusions| exclusions.excludes_absolute(&entry.path())))
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let metadatas = stat_entries(&dir_entries, conf.listing_concurrency);
 
-                if metadata.is_dir() {
-                    entries.push(Entry::new(
-                        file_name,
-                        EntryType::Directory,
-                        file_url,
-                        None,
-                        last_modification_date,
-                        symlink_dest,
-                    ));
-                } else if metadata.is_file() {
-                    entries.push(Entry::new(
-                        file_name.clone(),
-                        EntryType::File,
-                        file_url,
-                        Some(ByteSize::b(metadata.len())),
-                        last_modification_date,
-                        symlink_dest,
+    for (entry, (is_symlink, metadata)) in dir_entries.iter().zip(metadatas) {
+        // show file url as relative to static path
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let symlink_dest = (is_symlink && conf.show_symlink_info)
+            .then(|| entry.path())
+            .and_then(|path| std::fs::read_link(path).ok())
+            .map(|path| path.to_string_lossy().into_owned());
+        let file_url = base
+            .join(utf8_percent_encode(&file_name, PATH_SEGMENT).to_string())
+            .to_string_lossy()
+            .to_string();
+
+        // if file is a directory, add '/' to the end of the name
+        if let Ok(metadata) = metadata {
+            if conf.no_symlinks && is_symlink {
+                continue;
+            }
+            if anonymous
+                && !metadata.is_dir()
+                && !auth::anonymous_allowed(conf, &relative_dir.join(&file_name))
+            {
+                continue;
+            }
+            let last_modification_date = metadata.modified().ok();
+
+            if metadata.is_dir() {
+                entries.push(Entry::new(
+                    file_name,
+                    EntryType::Directory,
+                    file_url,
+                    None,
+                    last_modification_date,
+                    symlink_dest,
+                ));
+            } else if metadata.is_file() {
+                entries.push(Entry::new(
+                    file_name.clone(),
+                    EntryType::File,
+                    file_url,
+                    Some(ByteSize::b(metadata.len())),
+                    last_modification_date,
+                    symlink_dest,
+                ));
+                if conf.readme && readme_rx.is_match(&file_name.to_lowercase()) {
+                    let ext = file_name.split('.').next_back().unwrap().to_lowercase();
+                    readme = Some((
+                        file_name.to_string(),
+                        if ext == "md" {
+                            markdown_to_html(
+                                &std::fs::read_to_string(entry.path())?,
+                                &ComrakOptions::default(),
+                            )
+                        } else {
+                            // Uploaded readmes must not inject markup into the listing
+                            let content = std::fs::read_to_string(entry.path())?;
+                            maud::html! { pre { (content) } }.into_string()
+                        },
                     ));
-                    if conf.readme && readme_rx.is_match(&file_name.to_lowercase()) {
-                        let ext = file_name.split('.').last().unwrap().to_lowercase();
-                        readme = Some((
-                            file_name.to_string(),
-                            if ext == "md" {
-                                markdown_to_html(
-                                    &std::fs::read_to_string(entry.path())?,
-                                    &ComrakOptions::default(),
-                                )
-                            } else {
-                                format!("<pre>{}</pre>", &std::fs::read_to_string(entry.path())?)
-                            },
-                        ));
-                    }
                 }
-            } else {
-                continue;
             }
+        } else {
+            continue;
         }
     }
 
-    match query_params.sort.unwrap_or(SortingMethod::Name) {
-        SortingMethod::Name => entries.sort_by(|e1, e2| {
-            alphanumeric_sort::compare_str(e1.name.to_lowercase(), e2.name.to_lowercase())
-        }),
-        SortingMethod::Size => entries.sort_by(|e1, e2| {
-            // If we can't get the size of the entry (directory for instance)
-            // let's consider it's 0b
-            e2.size
-                .unwrap_or_else(|| ByteSize::b(0))
-                .cmp(&e1.size.unwrap_or_else(|| ByteSize::b(0)))
-        }),
-        SortingMethod::Date => entries.sort_by(|e1, e2| {
-            // If, for some reason, we can't get the last modification date of an entry
-            // let's consider it was modified on UNIX_EPOCH (01/01/19270 00:00:00)
-            e2.last_modification_date
-                .unwrap_or(SystemTime::UNIX_EPOCH)
-                .cmp(&e1.last_modification_date.unwrap_or(SystemTime::UNIX_EPOCH))
-        }),
-    };
+    if let Some(file_type) = query_params.file_type {
+        entries.retain(|entry| entry.is_file() && FileType::of(&entry.name) == Some(file_type));
+    }
+    if let Some(search) = query_params.search() {
+        entries.retain(|entry| name_matches(&entry.name, search));
+    }
 
-    if let Some(SortingOrder::Descending) = query_params.order {
-        entries.reverse()
+    let sort_method = query_params.sort.unwrap_or(SortingMethod::Name);
+    let sort = (
+        sort_method,
+        query_params
+            .order
+            .unwrap_or_else(|| sort_method.default_order()),
+        query_params.group.unwrap_or(conf.group_dirs),
+    );
+    entries.sort_by(|e1, e2| compare_entries(e1, e2, sort));
+
+    if query_params.format == Some(ListingFormat::Csv) {
+        if query_params.recursive == Some(true) {
+            entries = recursive_file_entries(&dir.base, relative_dir, conf, show_hidden, anonymous);
+            if let Some(file_type) = query_params.file_type {
+                entries.retain(|entry| FileType::of(&entry.name) == Some(file_type));
+            }
+            if let Some(search) = query_params.search() {
+                entries.retain(|entry| name_matches(&entry.name, search));
+            }
+            entries.sort_by(|e1, e2| compare_entries(e1, e2, sort));
+        }
+        return Ok(ServiceResponse::new(req.clone(), csv_listing(&entries)));
     }
 
-    // List directories first
-    if conf.dirs_first {
-        entries.sort_by_key(|e| !e.is_dir());
+    if query_params.archive == Some(ArchiveQuery::Plan) {
+        return Ok(ServiceResponse::new(
+            req.clone(),
+            archive_plan(req, conf, &dir.path, query_params.archive_settings(conf)),
+        ));
     }
 
     if let Some(archive_method) = query_params.download {
-        if !archive_method.is_enabled(conf.tar_enabled, conf.tar_gz_enabled, conf.zip_enabled) {
-            return Ok(ServiceResponse::new(
-                req.clone(),
-                HttpResponse::Forbidden()
-                    .content_type(mime::TEXT_PLAIN_UTF_8)
-                    .body("Archive creation is disabled."),
-            ));
+        if let Some(refusal) = refuse_archive(req, conf, archive_method) {
+            return Ok(ServiceResponse::new(req.clone(), refusal));
         }
-        log::info!(
-            "Creating an archive ({extension}) of {path}...",
-            extension = archive_method.extension(),
-            path = &dir.path.display().to_string()
-        );
-
-        let file_name = format!(
-            "{}.{}",
-            dir.path.file_name().unwrap().to_str().unwrap(),
-            archive_method.extension()
-        );
-
-        // We will create the archive in a separate thread, and stream the content using a pipe.
-        // The pipe is made of a futures channel, and an adapter to implement the `Write` trait.
-        // Include 10 messages of buffer for erratic connection speeds.
-        let (tx, rx) = futures::channel::mpsc::channel::<io::Result<actix_web::web::Bytes>>(10);
-        let pipe = crate::pipe::Pipe::new(tx);
-
-        // Start the actual archive creation in a separate thread.
-        let dir = dir.path.to_path_buf();
-        let skip_symlinks = conf.no_symlinks;
-        std::thread::spawn(move || {
-            if let Err(err) = archive_method.create_archive(dir, skip_symlinks, pipe) {
-                log::error!("Error during archive creation: {:?}", err);
-            }
-        });
-
         Ok(ServiceResponse::new(
             req.clone(),
-            HttpResponse::Ok()
-                .content_type(archive_method.content_type())
-                .append_header(archive_method.content_encoding())
-                .append_header(("Content-Transfer-Encoding", "binary"))
-                .append_header((
-                    "Content-Disposition",
-                    format!("attachment; filename={file_name:?}"),
-                ))
-                .body(actix_web::body::BodyStream::new(rx)),
+            stream_archive(
+                conf,
+                &dir.path,
+                archive_method,
+                query_params.archive_settings(conf),
+            ),
+        ))
+    } else if query_params.json.is_some() || accepts(req, mime::APPLICATION_JSON.essence_str()) {
+        Ok(ServiceResponse::new(
+            req.clone(),
+            json_listing(entries, &query_params, sort, &dir.path, checksums),
         ))
     } else {
+        let dir_config = match conf.dir_config {
+            true => read_dir_config(&dir.path),
+            false => DirConfig::default(),
+        };
+        let summary = DirSummary::of(&entries);
+        let (entries, pagination) = paginate(entries, &query_params);
         Ok(ServiceResponse::new(
             req.clone(),
             HttpResponse::Ok().content_type(mime::TEXT_HTML_UTF_8).body(
                 renderer::page(
                     entries,
+                    pagination,
+                    summary,
                     readme,
                     &abs_uri,
                     is_root,
@@ -386,6 +758,7 @@ pub fn directory_listing(
                     &encoded_dir,
                     conf,
                     current_user,
+                    &dir_config,
                 )
                 .into_string(),
             ),
@@ -393,6 +766,682 @@ pub fn directory_listing(
     }
 }
 
+/// Streams an archive of `dir` in the format `archive_method` made with `settings`, from the
+/// archive cache if it holds an up to date one
+fn stream_archive(
+    conf: &crate::MiniserveConfig,
+    dir: &Path,
+    archive_method: ArchiveMethod,
+    settings: ArchiveSettings,
+) -> HttpResponse {
+    let file_name = format!(
+        "{}.{}",
+        dir.file_name().unwrap_or_default().to_string_lossy(),
+        archive_method.extension()
+    );
+
+    let dir = dir.to_path_buf();
+    let skip_symlinks = conf.no_symlinks;
+    let throttle = conf.walk_throttle.clone();
+    let cache_entry = conf.archive_cache.clone().and_then(|cache| {
+        let entry_path = cache
+            .entry_path(&dir, archive_method, skip_symlinks, settings.include_hidden)
+            .ok()?;
+        Some((cache, entry_path))
+    });
+    let cached_archive = cache_entry
+        .as_ref()
+        .and_then(|(cache, entry_path)| cache.lookup(entry_path));
+
+    if cached_archive.is_some() {
+        log::info!(
+            "Serving a cached archive ({extension}) of {path}",
+            extension = archive_method.extension(),
+            path = &dir.display().to_string()
+        );
+    } else {
+        log::info!(
+            "Creating an archive ({extension}) of {path}...",
+            extension = archive_method.extension(),
+            path = &dir.display().to_string()
+        );
+    }
+
+    // We will create the archive in a separate thread, and stream the content using a pipe.
+    // The pipe is made of a futures channel, and an adapter to implement the `Write` trait.
+    // Include 10 messages of buffer for erratic connection speeds.
+    let (tx, rx) = futures::channel::mpsc::channel::<io::Result<actix_web::web::Bytes>>(10);
+    let mut pipe = crate::pipe::Pipe::new(tx);
+
+    // Start the actual archive creation in a separate thread.
+    let cache_status = cache_entry.as_ref().map(|_| {
+        if cached_archive.is_some() {
+            "hit"
+        } else {
+            "miss"
+        }
+    });
+    std::thread::spawn(move || {
+        let result = match (cached_archive, cache_entry) {
+            (Some(mut file), _) => io::copy(&mut file, &mut pipe).map(|_| ()).map_err(|e| {
+                ContextualError::IoError("Failed to read cached archive".to_string(), e)
+            }),
+            (None, Some((cache, entry_path))) => cache.create_archive(
+                &entry_path,
+                archive_method,
+                &dir,
+                skip_symlinks,
+                throttle.as_deref(),
+                &settings,
+                pipe,
+            ),
+            (None, None) => archive_method.create_archive(
+                dir,
+                skip_symlinks,
+                throttle.as_deref(),
+                &settings,
+                pipe,
+            ),
+        };
+        if let Err(err) = result {
+            log::error!("Error during archive creation: {:?}", err);
+        }
+    });
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(archive_method.content_type())
+        .append_header(archive_method.content_encoding())
+        .append_header(("Content-Transfer-Encoding", "binary"))
+        .insert_header(attachment_disposition(&file_name));
+    if let Some(cache_status) = cache_status {
+        response.append_header(("X-Archive-Cache", cache_status));
+    }
+
+    response.body(actix_web::body::BodyStream::new(rx))
+}
+
+/// Lists the files an archive of `dir` made with `settings` would include, as JSON, unless
+/// archives are refused
+fn archive_plan(
+    req: &HttpRequest,
+    conf: &crate::MiniserveConfig,
+    dir: &Path,
+    settings: ArchiveSettings,
+) -> HttpResponse {
+    // The plan is the same whatever the format, so it is refused like an archive in any enabled
+    // format, or like a tarball when none is
+    let archive_method = ArchiveMethod::iter()
+        .find(|method| {
+            method.is_enabled(
+                conf.tar_enabled,
+                conf.tar_gz_enabled,
+                conf.tar_zst_enabled,
+                conf.zip_enabled,
+            )
+        })
+        .unwrap_or(ArchiveMethod::Tar);
+    if let Some(refusal) = refuse_archive(req, conf, archive_method) {
+        return refusal;
+    }
+
+    match archive::plan_archive(
+        dir,
+        conf.no_symlinks,
+        conf.walk_throttle.as_deref(),
+        &settings,
+    ) {
+        Ok(plan) => HttpResponse::Ok().json(plan),
+        Err(err) => err.error_response(),
+    }
+}
+
+/// Serves an archive of the directory given as `path` query parameter, in the format requested by
+/// the `Accept` header
+///
+/// The first enabled format named in the header is used. Clients accepting any format get the
+/// first enabled one, in the order of the download buttons of listings.
+pub async fn download_handler(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+    let query_params = extract_query_parameters(&req);
+
+    let archive_method = match negotiate_archive_method(&req, conf) {
+        Some(archive_method) => archive_method,
+        None => {
+            return Ok(HttpResponse::NotAcceptable()
+                .content_type(mime::TEXT_PLAIN_UTF_8)
+                .insert_header((header::VARY, "accept"))
+                .body("None of the accepted archive formats is enabled."))
+        }
+    };
+    if let Some(refusal) = refuse_archive(&req, conf, archive_method) {
+        return Ok(refusal);
+    }
+
+    let dir = archive_jobs::resolve_dir(
+        query_params.path.as_deref().unwrap_or(Path::new("")),
+        conf,
+        query_params.shows_hidden(conf),
+    )?;
+    let mut response = stream_archive(
+        conf,
+        &dir,
+        archive_method,
+        query_params.archive_settings(conf),
+    );
+    response
+        .headers_mut()
+        .insert(header::VARY, header::HeaderValue::from_static("accept"));
+    Ok(response)
+}
+
+/// Returns the enabled archive method requested by the `Accept` header of `req`, if any
+fn negotiate_archive_method(
+    req: &HttpRequest,
+    conf: &crate::MiniserveConfig,
+) -> Option<ArchiveMethod> {
+    let is_enabled = |archive_method: &ArchiveMethod| {
+        archive_method.is_enabled(
+            conf.tar_enabled,
+            conf.tar_gz_enabled,
+            conf.tar_zst_enabled,
+            conf.zip_enabled,
+        )
+    };
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or("*/*");
+
+    let mut accepts_any = false;
+    for accepted in accept.split(',') {
+        let media_type = accepted.split(';').next().unwrap_or_default().trim();
+        match media_type {
+            "*/*" | "application/*" => accepts_any = true,
+            _ => {
+                let archive_method =
+                    ArchiveMethod::from_content_type(media_type).filter(is_enabled);
+                if archive_method.is_some() {
+                    return archive_method;
+                }
+            }
+        }
+    }
+    accepts_any
+        .then(|| ArchiveMethod::iter().find(is_enabled))
+        .flatten()
+}
+
+/// Returns the response refusing an archive in the format `archive_method`, if it is disabled or
+/// the client has requested too many archives lately
+pub fn refuse_archive(
+    req: &HttpRequest,
+    conf: &crate::MiniserveConfig,
+    archive_method: ArchiveMethod,
+) -> Option<HttpResponse> {
+    if !archive_method.is_enabled(
+        conf.tar_enabled,
+        conf.tar_gz_enabled,
+        conf.tar_zst_enabled,
+        conf.zip_enabled,
+    ) {
+        return Some(
+            HttpResponse::Forbidden()
+                .content_type(mime::TEXT_PLAIN_UTF_8)
+                .body("Archive creation is disabled."),
+        );
+    }
+    if let (Some(limiter), Some(peer_addr)) = (&conf.archive_rate_limiter, req.peer_addr()) {
+        if let Err(retry_after) = limiter.check(peer_addr.ip(), Instant::now()) {
+            return Some(
+                HttpResponse::TooManyRequests()
+                    .content_type(mime::TEXT_PLAIN_UTF_8)
+                    .append_header((
+                        header::RETRY_AFTER,
+                        // Round up so that retrying after this delay always succeeds
+                        (retry_after.as_secs() + 1).to_string(),
+                    ))
+                    .body("Too many archive requests, please try again later."),
+            );
+        }
+    }
+    None
+}
+
+/// Returns `true` if `name` contains `search`, ignoring case
+fn name_matches(name: &str, search: &str) -> bool {
+    name.to_lowercase().contains(&search.to_lowercase())
+}
+
+/// Compares two entries according to the requested sorting method, order and grouping of
+/// directories.
+///
+/// Entries which are equal for the sorting method are ordered by name, so that this is a total
+/// order which can be used for pagination.
+fn compare_entries(
+    e1: &Entry,
+    e2: &Entry,
+    (method, order, group): (SortingMethod, SortingOrder, GroupDirs),
+) -> Ordering {
+    let by_method = match method {
+        SortingMethod::Name => {
+            alphanumeric_sort::compare_str(e1.name.to_lowercase(), e2.name.to_lowercase())
+        }
+        // If we can't get the size of the entry (directory for instance)
+        // let's consider it's 0b
+        SortingMethod::Size => e1
+            .size
+            .unwrap_or_else(|| ByteSize::b(0))
+            .cmp(&e2.size.unwrap_or_else(|| ByteSize::b(0))),
+        // If, for some reason, we can't get the last modification date of an entry
+        // let's consider it was modified on UNIX_EPOCH (01/01/1970 00:00:00)
+        SortingMethod::Date => e1
+            .last_modification_date
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .cmp(&e2.last_modification_date.unwrap_or(SystemTime::UNIX_EPOCH)),
+    }
+    .then_with(|| e1.name.cmp(&e2.name));
+
+    let by_method = match order {
+        SortingOrder::Ascending => by_method,
+        SortingOrder::Descending => by_method.reverse(),
+    };
+
+    let by_group = match group {
+        GroupDirs::First => e2.is_dir().cmp(&e1.is_dir()),
+        GroupDirs::Last => e1.is_dir().cmp(&e2.is_dir()),
+        GroupDirs::None => Ordering::Equal,
+    };
+
+    by_group.then(by_method)
+}
+
+/// Fetches the metadata of `entries`, following symlinks, using up to `threads` threads
+///
+/// Each result tells whether the entry is a symlink, along with the metadata of the entry it
+/// points to. Results are in the same order as `entries` whatever the number of threads, which
+/// mostly helps on network filesystems where every `stat` is a round trip.
+fn stat_entries(entries: &[DirEntry], threads: usize) -> Vec<(bool, io::Result<Metadata>)> {
+    fn stat_entry(entry: &DirEntry) -> (bool, io::Result<Metadata>) {
+        match entry.metadata() {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                // for symlinks, get the metadata of the original file
+                (true, std::fs::metadata(entry.path()))
+            }
+            res => (false, res),
+        }
+    }
+
+    if threads <= 1 || entries.len() <= 1 {
+        return entries.iter().map(stat_entry).collect();
+    }
+
+    let chunk_size = entries.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        entries
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| chunk.iter().map(stat_entry).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Builds the `Content-Disposition` header of a downloaded archive named `file_name`
+///
+/// The name is sent encoded as per RFC 5987 in `filename*`, along with an ASCII-only fallback in
+/// `filename` for clients which don't support it.
+pub fn attachment_disposition(file_name: &str) -> header::ContentDisposition {
+    let ascii_file_name = file_name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+
+    header::ContentDisposition {
+        disposition: header::DispositionType::Attachment,
+        parameters: vec![
+            header::DispositionParam::Filename(ascii_file_name),
+            header::DispositionParam::FilenameExt(header::ExtendedValue {
+                charset: header::Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: file_name.as_bytes().to_vec(),
+            }),
+        ],
+    }
+}
+
+/// Return `true` if `media_type` is the preferred media type of the client
+pub fn accepts(req: &HttpRequest, media_type: &str) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .and_then(|accept| accept.split(',').next())
+        .map(|accepted| accepted.split(';').next().unwrap_or_default().trim())
+        == Some(media_type)
+}
+
+/// Renders the sorted entries as a JSON array
+///
+/// With `?limit=`, only that many entries are returned, starting after the one pointed at by
+/// `?cursor=`. The cursor of the next page is then sent in the `X-Next-Cursor` header, which is
+/// absent on the last page.
+///
+/// With `checksums`, the checksums of the files in `dir` are included, and entries are streamed
+/// as their checksum is computed.
+fn json_listing(
+    entries: Vec<Entry>,
+    query_params: &QueryParameters,
+    sort: (SortingMethod, SortingOrder, GroupDirs),
+    dir: &Path,
+    checksums: Option<Arc<Checksums>>,
+) -> HttpResponse {
+    let mut entries = match query_params.cursor.as_deref().map(Cursor::decode) {
+        Some(Some(cursor)) => {
+            let cursor = cursor.to_entry();
+            entries
+                .into_iter()
+                .filter(|entry| compare_entries(entry, &cursor, sort) == Ordering::Greater)
+                .collect()
+        }
+        Some(None) => {
+            return HttpResponse::BadRequest()
+                .content_type(mime::TEXT_PLAIN_UTF_8)
+                .body("Invalid value for 'cursor' parameter");
+        }
+        None => entries,
+    };
+
+    let mut next_cursor = None;
+    if let Some(limit) = query_params.limit {
+        let limit = limit.max(1);
+        if entries.len() > limit {
+            entries.truncate(limit);
+            next_cursor = entries
+                .last()
+                .map(|entry| Cursor::from_entry(entry).encode());
+        }
+    }
+
+    let mut res = HttpResponse::Ok();
+    res.content_type(mime::APPLICATION_JSON);
+    if let Some(next_cursor) = next_cursor {
+        res.insert_header(("X-Next-Cursor", next_cursor));
+    }
+
+    let checksums = match checksums {
+        Some(checksums) => checksums,
+        None => {
+            let json_entries = entries
+                .iter()
+                .map(|entry| json_entry(entry, None))
+                .collect::<Vec<_>>();
+            return res.body(serde_json::to_string(&json_entries).unwrap());
+        }
+    };
+
+    // Hashing files may take a while, so entries are sent as soon as their checksum is known
+    let (tx, rx) = futures::channel::mpsc::channel::<io::Result<actix_web::web::Bytes>>(10);
+    let mut pipe = crate::pipe::Pipe::new(tx);
+    let dir = dir.to_path_buf();
+    std::thread::spawn(move || {
+        let result = pipe.write_all(b"[").and_then(|()| {
+            for (i, entry) in entries.iter().enumerate() {
+                let checksum = match entry.is_dir() {
+                    true => None,
+                    false => checksums.get(&dir.join(&entry.name)),
+                };
+                let mut json = if i == 0 { vec![] } else { vec![b','] };
+                serde_json::to_writer(&mut json, &json_entry(entry, checksum))?;
+                pipe.write_all(&json)?;
+            }
+            pipe.write_all(b"]")
+        });
+        if let Err(err) = result {
+            log::error!(
+                "Error while streaming the listing of {}: {}",
+                dir.display(),
+                err
+            );
+        }
+    });
+    res.body(actix_web::body::BodyStream::new(rx))
+}
+
+/// Entry of the JSON listing describing `entry`
+fn json_entry(entry: &Entry, checksum: Option<String>) -> JsonEntry<'_> {
+    JsonEntry {
+        name: &entry.name,
+        is_dir: entry.is_dir(),
+        size: entry.size.map(|size| size.as_u64()),
+        last_modified: entry
+            .last_modification_date
+            .map(|date| DateTime::<Utc>::from(date).to_rfc3339()),
+        checksum,
+    }
+}
+
+/// Lists the absolute URLs of the files in `relative_dir`, one per line, as expected by
+/// `wget -i`
+///
+/// With `recursive`, the files in subdirectories are listed as well, down to --max-depth. Hidden
+/// files are only listed with `show_hidden`.
+fn urls_listing(
+    req: &HttpRequest,
+    base: &Path,
+    relative_dir: &Path,
+    recursive: bool,
+    show_hidden: bool,
+    anonymous: bool,
+) -> HttpResponse {
+    let conf = req.app_data::<crate::MiniserveConfig>().unwrap();
+
+    let max_depth = match recursive {
+        true => conf.max_depth,
+        false => Some(1),
+    };
+    let mut files = Vec::new();
+    feed::collect_files(base, relative_dir, max_depth, show_hidden, conf, &mut files);
+    if anonymous {
+        files.retain(|file| auth::anonymous_allowed(conf, &file.path));
+    }
+    files.sort_by(|f1, f2| f1.path.cmp(&f2.path));
+
+    let base_url = format!(
+        "{}://{}{}",
+        req.connection_info().scheme(),
+        req.connection_info().host(),
+        conf.route_prefix
+    );
+    let urls = files
+        .iter()
+        .map(|file| {
+            let encoded_path = file
+                .path
+                .iter()
+                .map(|segment| {
+                    utf8_percent_encode(&segment.to_string_lossy(), PATH_SEGMENT).to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            format!("{base_url}/{encoded_path}\n")
+        })
+        .collect::<String>();
+
+    HttpResponse::Ok()
+        .content_type(mime::TEXT_PLAIN_UTF_8)
+        .body(urls)
+}
+
+/// Returns the files in `relative_dir` and its subdirectories down to --max-depth, named by their
+/// path relative to it, including hidden files with `show_hidden`
+fn recursive_file_entries(
+    base: &Path,
+    relative_dir: &Path,
+    conf: &crate::MiniserveConfig,
+    show_hidden: bool,
+    anonymous: bool,
+) -> Vec<Entry> {
+    let mut files = Vec::new();
+    feed::collect_files(
+        base,
+        relative_dir,
+        conf.max_depth,
+        show_hidden,
+        conf,
+        &mut files,
+    );
+    if anonymous {
+        files.retain(|file| auth::anonymous_allowed(conf, &file.path));
+    }
+
+    files
+        .into_iter()
+        .map(|file| {
+            let size = std::fs::metadata(base.join(&file.path))
+                .ok()
+                .map(|metadata| ByteSize::b(metadata.len()));
+            let name = file
+                .path
+                .strip_prefix(relative_dir)
+                .unwrap_or(&file.path)
+                .iter()
+                .map(|segment| segment.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Entry::new(
+                name,
+                EntryType::File,
+                String::new(),
+                size,
+                Some(file.modified),
+                None,
+            )
+        })
+        .collect()
+}
+
+/// Renders the entries as CSV, with the columns `name`, `size`, `mtime` and `is_dir`
+///
+/// Sizes are in bytes and empty for directories, and modification dates are RFC 3339 timestamps.
+fn csv_listing(entries: &[Entry]) -> HttpResponse {
+    let mut csv = String::from("name,size,mtime,is_dir\r\n");
+    for entry in entries {
+        let size = entry
+            .size
+            .map(|size| size.as_u64().to_string())
+            .unwrap_or_default();
+        let mtime = entry
+            .last_modification_date
+            .map(|date| DateTime::<Utc>::from(date).to_rfc3339())
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{}\r\n",
+            csv_field(&entry.name),
+            size,
+            mtime,
+            entry.is_dir()
+        ));
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .body(csv)
+}
+
+/// Quotes `field` if it contains separators, quotes or line breaks
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Streams the entries of `dir` as JSON Lines, one object per entry as in the JSON listing
+///
+/// Entries are sent in the order they are read from the disk while the directory is walked, so
+/// they are neither sorted nor paginated. Hidden entries are only sent with `show_hidden`.
+fn ndjson_listing(
+    dir: PathBuf,
+    relative_dir: PathBuf,
+    conf: crate::MiniserveConfig,
+    show_hidden: bool,
+    anonymous: bool,
+    checksums: Option<Arc<Checksums>>,
+) -> HttpResponse {
+    let (tx, rx) = futures::channel::mpsc::channel::<io::Result<actix_web::web::Bytes>>(10);
+    let mut pipe = crate::pipe::Pipe::new(tx);
+
+    std::thread::spawn(move || {
+        let result = dir.read_dir().and_then(|read_dir| {
+            for entry in read_dir {
+                let entry = entry?;
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if (file_name.starts_with('.') && !show_hidden)
+                    || (conf.auth_files && file_name == AUTH_FILE)
+                    || (conf.hide_sensitive && sensitive::is_sensitive(&file_name))
+                    || conf
+                        .exclusions
+                        .as_ref()
+                        .is_some_and(|exclusions| exclusions.excludes_absolute(&entry.path()))
+                {
+                    continue;
+                }
+                let (is_symlink, metadata) = match entry.metadata() {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        (true, std::fs::metadata(entry.path()))
+                    }
+                    res => (false, res),
+                };
+                let metadata = match metadata {
+                    Ok(metadata) if metadata.is_dir() || metadata.is_file() => metadata,
+                    _ => continue,
+                };
+                if conf.no_symlinks && is_symlink {
+                    continue;
+                }
+                if anonymous
+                    && !metadata.is_dir()
+                    && !auth::anonymous_allowed(&conf, &relative_dir.join(&file_name))
+                {
+                    continue;
+                }
+
+                let json_entry = JsonEntry {
+                    name: &file_name,
+                    is_dir: metadata.is_dir(),
+                    size: metadata.is_file().then_some(metadata.len()),
+                    last_modified: metadata
+                        .modified()
+                        .ok()
+                        .map(|date| DateTime::<Utc>::from(date).to_rfc3339()),
+                    checksum: checksums
+                        .as_ref()
+                        .filter(|_| metadata.is_file())
+                        .and_then(|checksums| checksums.get(&entry.path())),
+                };
+                let mut line = serde_json::to_vec(&json_entry)?;
+                line.push(b'\n');
+                pipe.write_all(&line)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            log::error!(
+                "Error while streaming the listing of {}: {}",
+                dir.display(),
+                err
+            );
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type(NDJSON)
+        .body(actix_web::body::BodyStream::new(rx))
+}
+
 pub fn extract_query_parameters(req: &HttpRequest) -> QueryParameters {
     match Query::<QueryParameters>::from_query(req.query_string()) {
         Ok(Query(query_params)) => query_params,
@@ -403,3 +1452,73 @@ pub fn extract_query_parameters(req: &HttpRequest) -> QueryParameters {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn summarize(stats: Vec<(bool, io::Result<Metadata>)>) -> Vec<(bool, Option<(bool, u64)>)> {
+        stats
+            .into_iter()
+            .map(|(is_symlink, metadata)| {
+                let metadata = metadata.ok().map(|m| (m.is_dir(), m.len()));
+                (
+                    is_symlink,
+                    metadata.map(|(is_dir, len)| (is_dir, if is_dir { 0 } else { len })),
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case("photo.JPG", Some(FileType::Image))]
+    #[case("movie.mkv", Some(FileType::Video))]
+    #[case("song.mp3", Some(FileType::Audio))]
+    #[case("notes.txt", Some(FileType::Text))]
+    #[case("backup.tar.gz", Some(FileType::Archive))]
+    #[case("program.exe", None)]
+    #[case("Makefile", None)]
+    fn test_file_type(#[case] file_name: &str, #[case] expected: Option<FileType>) {
+        assert_eq!(FileType::of(file_name), expected);
+    }
+
+    #[rstest]
+    #[case("plain.txt", "plain.txt")]
+    #[case("a,b.txt", "\"a,b.txt\"")]
+    #[case("say \"hi\".txt", "\"say \"\"hi\"\".txt\"")]
+    #[case("two\nlines", "\"two\nlines\"")]
+    fn test_csv_field(#[case] field: &str, #[case] expected: &str) {
+        assert_eq!(csv_field(field), expected);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(4)]
+    #[case(64)]
+    fn test_stat_entries_concurrently(#[case] threads: usize) {
+        let dir = assert_fs::TempDir::new().unwrap();
+        for i in 0..10 {
+            dir.child(format!("file{i}"))
+                .write_str(&"x".repeat(i))
+                .unwrap();
+            dir.child(format!("dir{i}")).create_dir_all().unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("file3"), dir.path().join("link")).unwrap();
+
+        let entries = dir
+            .path()
+            .read_dir()
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(
+            summarize(stat_entries(&entries, threads)),
+            summarize(stat_entries(&entries, 1))
+        );
+    }
+}
@@ -5,31 +5,66 @@ use std::time::Duration;
 
 use actix_files::NamedFile;
 use actix_web::{
-    http::header::ContentType, middleware, web, App, HttpRequest, HttpResponse, Responder,
+    dev::{fn_service, Service, ServiceRequest, ServiceResponse},
+    guard,
+    http::header::{self, ContentType},
+    middleware, web, App, HttpRequest, HttpResponse, Responder,
 };
 use actix_web_httpauth::middleware::HttpAuthentication;
 use anyhow::Result;
-use clap::{crate_version, CommandFactory, Parser};
+use clap::{crate_version, CommandFactory};
 use fast_qr::QRBuilder;
+use futures::prelude::*;
 use log::{error, warn};
 use yansi::{Color, Paint};
 
+mod allowed_hosts;
 mod archive;
+mod archive_cache;
+mod archive_jobs;
+mod archive_ranges;
 mod args;
 mod auth;
+mod auth_files;
+mod cache_control;
+mod case_collisions;
+mod checksums;
+mod compress;
 mod config;
+mod config_file;
 mod consts;
+mod csp;
+mod dir_config;
+mod disk_usage;
 mod errors;
+mod etag;
+mod exclude;
+mod feed;
+mod file_delete;
 mod file_upload;
+#[cfg(feature = "git")]
+mod git_snapshot;
 mod listing;
+mod markdown;
+mod negotiation;
+mod number_format;
+mod offload;
+mod openapi;
 mod pipe;
+mod rate_limit;
+mod recent;
 mod renderer;
+mod sensitive;
+mod sitemap;
+mod total_rate;
+mod trash;
+mod upload_quota;
 
 use crate::config::MiniserveConfig;
 use crate::errors::ContextualError;
 
 fn main() -> Result<()> {
-    let args = args::CliArgs::parse();
+    let args = config_file::parse_args()?;
 
     if let Some(shell) = args.print_completions {
         let mut clap_app = args::CliArgs::command();
@@ -47,9 +82,8 @@ fn main() -> Result<()> {
 
     let miniserve_config = MiniserveConfig::try_from_args(args)?;
 
-    run(miniserve_config).map_err(|e| {
+    run(miniserve_config).inspect_err(|e| {
         errors::log_error_chain(e.to_string());
-        e
     })?;
 
     Ok(())
@@ -84,8 +118,25 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
 
     let inside_config = miniserve_config.clone();
 
+    let served_path = miniserve_config.path.to_string_lossy();
     let canon_path = miniserve_config.path.canonicalize().map_err(|e| {
-        ContextualError::IoError("Failed to resolve path to be served".to_string(), e)
+        ContextualError::IoError(
+            format!("Failed to resolve path to be served '{served_path}'"),
+            e,
+        )
+    })?;
+
+    // Fail right away rather than on every request if the path can't be read
+    let readable = if canon_path.is_dir() {
+        canon_path.read_dir().map(drop)
+    } else {
+        std::fs::File::open(&canon_path).map(drop)
+    };
+    readable.map_err(|e| {
+        ContextualError::IoError(
+            format!("Failed to read path to be served '{served_path}'"),
+            e,
+        )
     })?;
 
     // warn if --index is specified but not found
@@ -98,6 +149,45 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         }
     }
 
+    if let Some(action) = miniserve_config.case_collisions {
+        let collisions =
+            case_collisions::find_case_collisions(&canon_path, miniserve_config.show_hidden);
+        for (first, second) in &collisions {
+            warn!(
+                "'{}' and '{}' only differ by case, one of them will be shadowed by the other on case-insensitive filesystems.",
+                first.display(),
+                second.display()
+            );
+        }
+        if action == case_collisions::CaseCollisions::Deny && !collisions.is_empty() {
+            return Err(ContextualError::CaseCollisionError(
+                collisions
+                    .iter()
+                    .map(|(first, second)| {
+                        format!("'{}' and '{}'", first.display(), second.display())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
+    }
+
+    if !miniserve_config.hide_sensitive {
+        // Hidden files are served with ?hidden=true even without --hidden
+        let sensitive_files =
+            sensitive::find_sensitive_files(&canon_path, true, sensitive::MAX_SCAN_DEPTH);
+        for path in &sensitive_files {
+            warn!(
+                "'{}' usually holds secrets and is being served, use --hide-sensitive to hide it.",
+                path.display()
+            );
+        }
+    }
+
+    if miniserve_config.hsts.is_some() && miniserve_config.tls_rustls_config.is_none() {
+        warn!("The --hsts option has no effect when not serving over TLS, ignoring it.");
+    }
+
     let path_string = canon_path.to_string_lossy();
 
     println!(
@@ -176,29 +266,60 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
 
     let display_sockets = socket_addresses
         .iter()
-        .map(|sock| Color::Green.paint(sock.to_string()).bold().to_string())
+        .map(|sock| sock.to_string())
+        .chain(
+            miniserve_config
+                .unix_socket
+                .iter()
+                .map(|path| format!("unix:{}", path.display())),
+        )
+        .map(|sock| Color::Green.paint(sock).bold().to_string())
         .collect::<Vec<_>>();
 
     let srv = actix_web::HttpServer::new(move || {
         App::new()
             .wrap(configure_header(&inside_config.clone()))
+            .wrap_fn(response_headers_middleware)
             .app_data(inside_config.clone())
+            .wrap_fn(allowed_hosts::allowed_hosts_middleware)
             .wrap_fn(errors::error_page_middleware)
+            .wrap(middleware::Condition::new(
+                inside_config.compress,
+                middleware::Compress::default(),
+            ))
+            .wrap_fn(total_rate::total_rate_middleware)
             .wrap(middleware::Logger::default())
             .route(&inside_config.favicon_route, web::get().to(favicon))
             .route(&inside_config.css_route, web::get().to(css))
+            .configure(|c| {
+                if inside_config.robots_txt.is_some() {
+                    // Crawlers only look for it at the root of the server
+                    c.route("/robots.txt", web::get().to(sitemap::robots_txt));
+                }
+            })
             .service(
                 web::scope(&inside_config.route_prefix)
+                    .wrap_fn(etag::etag_middleware)
+                    .wrap_fn(offload::offload_middleware)
+                    .wrap_fn(archive_ranges::archive_ranges_middleware)
+                    .wrap_fn(sensitive::hide_sensitive_middleware)
+                    .wrap_fn(auth_files::auth_files_middleware)
                     .wrap(middleware::Condition::new(
                         !inside_config.auth.is_empty(),
-                        actix_web::middleware::Compat::new(HttpAuthentication::basic(
+                        actix_web::middleware::Compat::new(HttpAuthentication::with_fn(
                             auth::handle_auth,
                         )),
                     ))
+                    .wrap_fn(negotiation::content_negotiation_middleware)
+                    .wrap_fn(markdown::render_markdown_middleware)
+                    .wrap_fn(cache_control::cache_control_middleware)
+                    .wrap_fn(csp::csp_middleware)
                     .configure(|c| configure_app(c, &inside_config)),
             )
             .default_service(web::get().to(error_404))
-    });
+    })
+    .client_request_timeout(miniserve_config.client_timeout)
+    .client_disconnect_timeout(miniserve_config.client_disconnect);
 
     let srv = socket_addresses.iter().try_fold(srv, |srv, addr| {
         let listener = create_tcp_listener(*addr)
@@ -216,8 +337,43 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         srv.map_err(|e| ContextualError::IoError(format!("Failed to bind server to {addr}"), e))
     })?;
 
+    #[cfg(unix)]
+    let srv = match &miniserve_config.unix_socket {
+        Some(path) => {
+            let bind_error = |e| {
+                ContextualError::IoError(format!("Failed to bind server to {}", path.display()), e)
+            };
+            let listener = create_unix_listener(path, miniserve_config.unix_socket_mode)
+                .map_err(bind_error)?;
+            srv.listen_uds(listener).map_err(bind_error)?
+        }
+        None => srv,
+    };
+
     let srv = srv.shutdown_timeout(0).run();
 
+    let redirect_srv = match miniserve_config.redirect_http_port {
+        Some(http_port) => {
+            let https_port = miniserve_config.port;
+            let redirect_srv = actix_web::HttpServer::new(move || {
+                App::new().default_service(web::to(move |req| redirect_to_https(req, https_port)))
+            });
+            let redirect_srv = miniserve_config.interfaces.iter().try_fold(
+                redirect_srv,
+                |redirect_srv, &interface| {
+                    let addr = SocketAddr::new(interface, http_port);
+                    create_tcp_listener(addr)
+                        .and_then(|listener| redirect_srv.listen(listener))
+                        .map_err(|e| {
+                            ContextualError::IoError(format!("Failed to bind server to {addr}"), e)
+                        })
+                },
+            )?;
+            Some(redirect_srv.shutdown_timeout(0).run())
+        }
+        None => None,
+    };
+
     println!("Bound to {}", display_sockets.join(", "));
 
     println!("Serving path {}", Color::Yellow.paint(path_string).bold());
@@ -253,8 +409,36 @@ async fn run(miniserve_config: MiniserveConfig) -> Result<(), ContextualError> {
         println!("Quit by pressing CTRL-C");
     }
 
-    srv.await
-        .map_err(|e| ContextualError::IoError("".to_owned(), e))
+    let result = match redirect_srv {
+        Some(redirect_srv) => future::try_join(srv, redirect_srv).await.map(|_| ()),
+        None => srv.await,
+    };
+
+    if let Some(path) = &miniserve_config.unix_socket {
+        if let Err(e) = std::fs::remove_file(path) {
+            warn!("Failed to remove the socket {}: {}", path.display(), e);
+        }
+    }
+
+    result.map_err(|e| ContextualError::IoError("".to_owned(), e))
+}
+
+/// Binds a Unix domain socket at `path`, with the permissions given by `mode`
+///
+/// A socket left over by a previous run is replaced, but no other kind of file.
+#[cfg(unix)]
+fn create_unix_listener(
+    path: &std::path::Path,
+    mode: u32,
+) -> io::Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = std::os::unix::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(listener)
 }
 
 /// Allows us to set low-level socket options
@@ -274,11 +458,99 @@ fn create_tcp_listener(addr: SocketAddr) -> io::Result<TcpListener> {
     Ok(TcpListener::from(socket))
 }
 
+/// Redirects a plain HTTP request to the same URL over HTTPS
+async fn redirect_to_https(req: HttpRequest, https_port: u16) -> HttpResponse {
+    let conn = req.connection_info();
+
+    // Drop the port of the HTTP listener from the host, if any
+    let host = match conn.host().rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => conn.host(),
+    };
+    let port = match https_port {
+        443 => String::new(),
+        port => format!(":{port}"),
+    };
+    let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+
+    HttpResponse::MovedPermanently()
+        .append_header((
+            header::LOCATION,
+            format!("https://{host}{port}{path_and_query}"),
+        ))
+        .finish()
+}
+
 fn configure_header(conf: &MiniserveConfig) -> middleware::DefaultHeaders {
-    conf.header.iter().flatten().fold(
+    let headers = conf.header.iter().flatten().fold(
         middleware::DefaultHeaders::new(),
         |headers, (header_name, header_value)| headers.add((header_name, header_value)),
-    )
+    );
+
+    // HSTS is only meaningful (and only honored by browsers) over a secure connection
+    match (&conf.hsts, &conf.tls_rustls_config) {
+        (Some(hsts), Some(_)) => headers.add((header::STRICT_TRANSPORT_SECURITY, hsts.as_str())),
+        _ => headers,
+    }
+}
+
+/// Middleware adjusting the headers of responses
+///
+/// It overrides or removes the Server header. With `--compress`, it also marks the responses
+/// which are already compressed, so that they aren't compressed again.
+fn response_headers_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+{
+    let server_header = req
+        .app_data::<MiniserveConfig>()
+        .map(|conf| (conf.server_header.clone(), conf.no_server_header));
+    // With --compress, already compressed files are recognized by their path or content type
+    let request_path = req
+        .app_data::<MiniserveConfig>()
+        .filter(|conf| conf.compress)
+        .map(|_| req.path().to_string());
+    let fut = srv.call(req);
+
+    async move {
+        let mut res = fut.await?;
+        match server_header {
+            Some((Some(value), _)) => {
+                res.headers_mut().insert(header::SERVER, value);
+            }
+            Some((None, true)) => {
+                res.headers_mut().remove(header::SERVER);
+            }
+            _ => (),
+        }
+        if request_path.is_some_and(|path| compress::is_precompressed(&path, res.headers())) {
+            compress::skip_compression(res.headers_mut());
+        }
+        Ok(res)
+    }
+}
+
+/// Serves the index file of the served directory in place of the paths which don't exist, with
+/// `--spa`
+///
+/// The file is opened for each request, so that it can be changed while running, and a 404 is
+/// still sent if it doesn't exist.
+async fn spa_index(req: ServiceRequest) -> actix_web::Result<ServiceResponse> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    // --spa requires --index in clap
+    let index_path = conf.path.join(conf.index.as_ref().unwrap());
+    let (req, _) = req.into_parts();
+    match NamedFile::open_async(&index_path).await {
+        Ok(file) if index_path.is_file() => {
+            let res = file.into_response(&req);
+            Ok(ServiceResponse::new(req, res))
+        }
+        _ => Err(ContextualError::RouteNotFoundError(req.path().to_string()).into()),
+    }
 }
 
 /// Configures the Actix application
@@ -295,27 +567,40 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
             //
             // Note: --spa requires --index in clap.
             if conf.spa {
-                files = files.default_handler(
-                    NamedFile::open(conf.path.join(index_file))
-                        .expect("Can't open SPA index file."),
-                );
+                files = files.default_handler(fn_service(spa_index));
             }
         }
 
-        if conf.show_hidden {
-            files = files.use_hidden_files();
-        }
-
         let base_path = conf.path.clone();
         let no_symlinks = conf.no_symlinks;
+        let show_hidden = conf.show_hidden;
+        let exclusions = conf.exclusions.clone();
         files
+            .use_hidden_files()
             .show_files_listing()
             .files_listing_renderer(listing::directory_listing)
             .prefer_utf8(true)
             .redirect_to_slash_directory()
-            .path_filter(move |path, _| {
-                // deny symlinks if conf.no_symlinks
-                !(no_symlinks && base_path.join(path).is_symlink())
+            .path_filter(move |path, head| {
+                // deny symlinks if conf.no_symlinks, paths excluded with --exclude, and hidden
+                // paths unless they are shown with --hidden or ?hidden=true
+                let is_hidden = path
+                    .components()
+                    .any(|component| component.as_os_str().to_string_lossy().starts_with('.'));
+                let shows_hidden = || {
+                    head.uri
+                        .query()
+                        .and_then(|query| {
+                            web::Query::<listing::QueryParameters>::from_query(query).ok()
+                        })
+                        .and_then(|query| query.hidden)
+                        .unwrap_or(show_hidden)
+                };
+                !((no_symlinks && base_path.join(path).is_symlink())
+                    || exclusions
+                        .as_ref()
+                        .is_some_and(|exclusions| exclusions.excludes(path))
+                    || (is_hidden && !shows_hidden()))
             })
     };
 
@@ -326,6 +611,60 @@ fn configure_app(app: &mut web::ServiceConfig, conf: &MiniserveConfig) {
         if conf.file_upload {
             // Allow file upload
             app.service(web::resource("/upload").route(web::post().to(file_upload::upload_file)));
+        } else {
+            // Refuse uploads explicitly rather than with a 405
+            app.service(
+                web::resource("/upload").route(web::post().to(file_upload::upload_disabled)),
+            );
+        }
+        if conf.delete_enabled {
+            // Allow deleting files
+            app.service(web::resource("/delete").route(web::post().to(file_delete::delete_files)));
+        }
+        if conf.tar_enabled || conf.tar_gz_enabled || conf.tar_zst_enabled || conf.zip_enabled {
+            // Serve archives in the format requested by the Accept header
+            app.service(web::resource("/download").route(web::get().to(listing::download_handler)));
+        }
+        if conf.feed.is_some() {
+            // Serve the feed of recent files
+            app.service(web::resource("/feed.xml").route(web::get().to(feed::feed_handler)));
+        }
+        if conf.recent.is_some() {
+            // Serve the list of recent files
+            app.service(web::resource("/recent").route(web::get().to(recent::recent_handler)));
+        }
+        if conf.sitemap.is_some() {
+            // Serve the sitemap of the files
+            app.service(
+                web::resource("/sitemap.xml").route(web::get().to(sitemap::sitemap_handler)),
+            );
+        }
+        if conf.archive_jobs.is_some() {
+            // Generate archives in the background
+            app.service(
+                web::resource("/archive-jobs").route(web::post().to(archive_jobs::start_job)),
+            );
+            app.service(
+                web::resource("/archive-jobs/{id}").route(web::get().to(archive_jobs::job_status)),
+            );
+            app.service(
+                web::resource("/archive-jobs/{id}/download")
+                    .route(web::get().to(archive_jobs::download)),
+            );
+        }
+        if conf.openapi {
+            // Describe the HTTP API
+            app.service(
+                web::resource("/openapi.json").route(web::get().to(openapi::openapi_handler)),
+            );
+        }
+        if conf.checksums.is_some() {
+            // Answer with the checksums of files instead of their content
+            app.service(
+                web::resource("/{path:.*}")
+                    .guard(guard::fn_guard(checksums::is_requested))
+                    .route(web::get().to(checksums::file_checksum_handler)),
+            );
         }
         // Handle directories
         app.service(dir_service());
@@ -0,0 +1,69 @@
+use actix_web::{
+    body::{self, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{Method, StatusCode},
+    HttpResponse,
+};
+use comrak::{markdown_to_html, ComrakOptions};
+use futures::prelude::*;
+use percent_encoding::percent_decode_str;
+
+use crate::{checksums, listing, renderer, MiniserveConfig};
+
+/// Middleware to render Markdown files as HTML pages when `--render-markdown` is enabled
+///
+/// The file itself is still served by `actix_files`, so all the usual checks (hidden files,
+/// symlinks, ...) apply. Only successful responses to `GET` requests for `.md` files are
+/// rendered, and `?raw=true` (or `?raw=1`) skips rendering to get the original source.
+pub fn render_markdown_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let should_render = should_render(&req);
+
+    let fut = srv.call(req);
+
+    async move {
+        let res = fut.await?.map_into_boxed_body();
+
+        if !should_render || res.status() != StatusCode::OK {
+            return Ok(res);
+        }
+
+        let (req, res) = res.into_parts();
+        let source = body::to_bytes(res.into_body()).await.map_err(|e| {
+            actix_web::error::ErrorInternalServerError(format!("Failed to read file: {e}"))
+        })?;
+
+        let conf = req.app_data::<MiniserveConfig>().unwrap();
+        let file_name = percent_decode_str(req.path().rsplit('/').next().unwrap_or_default())
+            .decode_utf8_lossy()
+            .to_string();
+        let contents =
+            markdown_to_html(&String::from_utf8_lossy(&source), &ComrakOptions::default());
+
+        let res = HttpResponse::Ok()
+            .content_type(mime::TEXT_HTML_UTF_8)
+            .body(renderer::rendered_file(&file_name, &contents, conf).into_string());
+
+        Ok(ServiceResponse::new(req, res))
+    }
+}
+
+/// Returns `true` if the response to `req` is to be rendered as Markdown
+pub fn should_render(req: &ServiceRequest) -> bool {
+    req.app_data::<MiniserveConfig>()
+        .map(|conf| conf.render_markdown)
+        .unwrap_or(false)
+        && req.method() == Method::GET
+        && req.path().to_lowercase().ends_with(".md")
+        && !listing::extract_query_parameters(req.request())
+            .raw
+            .unwrap_or(false)
+        && !checksums::should_answer(req)
+}
@@ -0,0 +1,208 @@
+use actix_web::{
+    body::{self, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        Method, StatusCode,
+    },
+    HttpResponse,
+};
+use comrak::{markdown_to_html, ComrakOptions};
+use futures::prelude::*;
+use maud::html;
+use percent_encoding::percent_decode_str;
+
+use crate::{checksums, listing, markdown, renderer, MiniserveConfig};
+
+/// Converts the content of a file to the HTML shown when a browser requests it
+type HtmlView = fn(&str) -> String;
+
+/// Extensions of the files which have an HTML view, along with the function rendering it
+const HTML_VIEWS: &[(&str, HtmlView)] = &[
+    ("csv", csv_view),
+    ("json", json_view),
+    ("md", markdown_view),
+];
+
+/// Middleware serving an HTML view of some files to clients preferring HTML when
+/// `--negotiate-content` is enabled
+///
+/// Other clients still get the file as is, and `?raw=true` always does. Like for
+/// `--render-markdown`, the file itself is served by `actix_files` so all the usual checks apply.
+pub fn content_negotiation_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let view = html_view(&req);
+    let render = view.filter(|_| listing::accepts(req.request(), "text/html"));
+
+    let fut = srv.call(req);
+
+    async move {
+        let mut res = fut.await?.map_into_boxed_body();
+
+        if view.is_none() || !res.status().is_success() {
+            return Ok(res);
+        }
+
+        let view = match render {
+            Some(view) if res.status() == StatusCode::OK => view,
+            _ => {
+                // The same URL has several representations
+                res.headers_mut()
+                    .append(header::VARY, HeaderValue::from_static("accept"));
+                return Ok(res);
+            }
+        };
+
+        let (req, res) = res.into_parts();
+        let source = body::to_bytes(res.into_body()).await.map_err(|e| {
+            actix_web::error::ErrorInternalServerError(format!("Failed to read file: {e}"))
+        })?;
+
+        let conf = req.app_data::<MiniserveConfig>().unwrap();
+        let file_name = percent_decode_str(req.path().rsplit('/').next().unwrap_or_default())
+            .decode_utf8_lossy()
+            .to_string();
+        let contents = view(&String::from_utf8_lossy(&source));
+
+        let res = HttpResponse::Ok()
+            .content_type(mime::TEXT_HTML_UTF_8)
+            .insert_header((header::VARY, "accept"))
+            .body(renderer::rendered_file(&file_name, &contents, conf).into_string());
+
+        Ok(ServiceResponse::new(req, res))
+    }
+}
+
+/// Returns `true` if the response to `req` is to be replaced by an HTML view of the file
+pub fn should_render(req: &ServiceRequest) -> bool {
+    html_view(req).is_some()
+        && listing::accepts(req.request(), "text/html")
+        && !checksums::should_answer(req)
+}
+
+/// Returns the HTML view of the file requested by `req`, if content negotiation applies to it
+fn html_view(req: &ServiceRequest) -> Option<HtmlView> {
+    let enabled = req
+        .app_data::<MiniserveConfig>()
+        .map(|conf| conf.negotiate_content)
+        .unwrap_or(false)
+        && req.method() == Method::GET
+        && !listing::extract_query_parameters(req.request())
+            .raw
+            .unwrap_or(false)
+        // Markdown files are already rendered whatever the client accepts
+        && !markdown::should_render(req);
+    if !enabled {
+        return None;
+    }
+
+    let (_, extension) = req.path().rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    HTML_VIEWS
+        .iter()
+        .find(|(view_extension, _)| *view_extension == extension)
+        .map(|(_, view)| *view)
+}
+
+/// Renders a CSV file as a table, its first row being the header
+fn csv_view(source: &str) -> String {
+    let mut rows = parse_csv(source).into_iter();
+    let header = rows.next().unwrap_or_default();
+
+    html! {
+        table {
+            thead {
+                @for field in header {
+                    th { (field) }
+                }
+            }
+            tbody {
+                @for row in rows {
+                    tr {
+                        @for field in row {
+                            td { (field) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    .into_string()
+}
+
+/// Renders a JSON file pretty-printed, or as is if it isn't valid JSON
+fn json_view(source: &str) -> String {
+    let pretty = serde_json::from_str::<serde_json::Value>(source)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok());
+
+    html! {
+        pre { code { (pretty.as_deref().unwrap_or(source)) } }
+    }
+    .into_string()
+}
+
+/// Renders a Markdown file
+fn markdown_view(source: &str) -> String {
+    markdown_to_html(source, &ComrakOptions::default())
+}
+
+/// Splits CSV data into rows of fields, as described in RFC 4180
+///
+/// Fields may be quoted to contain commas, line breaks and doubled quotes.
+fn parse_csv(source: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("a,b\n1,2\n", vec![vec!["a", "b"], vec!["1", "2"]])]
+    #[case("a,b\r\n1,2", vec![vec!["a", "b"], vec!["1", "2"]])]
+    #[case("a,,c\n", vec![vec!["a", "", "c"]])]
+    #[case("\"a,b\",\"say \"\"hi\"\"\"\n", vec![vec!["a,b", "say \"hi\""]])]
+    #[case("\"multi\nline\",x\n", vec![vec!["multi\nline", "x"]])]
+    fn test_parse_csv(#[case] source: &str, #[case] expected: Vec<Vec<&str>>) {
+        assert_eq!(parse_csv(source), expected);
+    }
+}
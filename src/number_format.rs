@@ -0,0 +1,85 @@
+use bytesize::ByteSize;
+use clap::ValueEnum;
+
+/// Separators of thousands and decimals in the numbers shown in listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NumberFormat {
+    /// No thousands separator and a decimal point: 1234567.8
+    Plain,
+
+    /// Commas between thousands and a decimal point, as in English: 1,234,567.8
+    Comma,
+
+    /// Dots between thousands and a decimal comma, as in German: 1.234.567,8
+    Dot,
+
+    /// Narrow no-break spaces between thousands and a decimal comma, as in French: 1 234 567,8
+    Space,
+
+    /// Apostrophes between thousands and a decimal point, as in Swiss German: 1'234'567.8
+    Apostrophe,
+}
+
+impl NumberFormat {
+    /// Returns the separator of thousands, if any, and the decimal separator
+    fn separators(self) -> (Option<char>, char) {
+        match self {
+            Self::Plain => (None, '.'),
+            Self::Comma => (Some(','), '.'),
+            Self::Dot => (Some('.'), ','),
+            Self::Space => (Some('\u{202F}'), ','),
+            Self::Apostrophe => (Some('\''), '.'),
+        }
+    }
+
+    /// Formats an integer, with its thousands separated
+    pub fn integer(self, number: u64) -> String {
+        let digits = number.to_string();
+        let thousands_separator = match self.separators().0 {
+            Some(separator) => separator,
+            None => return digits,
+        };
+
+        let mut formatted = String::with_capacity(digits.len() * 4 / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                formatted.push(thousands_separator);
+            }
+            formatted.push(digit);
+        }
+        formatted
+    }
+
+    /// Formats a size in human-readable binary units (e.g. 1.2 MiB), with the decimal separator
+    pub fn size(self, size: ByteSize) -> String {
+        size.to_string_as(true)
+            .replace('.', &self.separators().1.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(NumberFormat::Plain, 1_234_567, "1234567")]
+    #[case(NumberFormat::Comma, 1_234_567, "1,234,567")]
+    #[case(NumberFormat::Dot, 1_234_567, "1.234.567")]
+    #[case(NumberFormat::Space, 1_234_567, "1\u{202F}234\u{202F}567")]
+    #[case(NumberFormat::Apostrophe, 1_234_567, "1'234'567")]
+    #[case(NumberFormat::Comma, 123_456, "123,456")]
+    #[case(NumberFormat::Comma, 999, "999")]
+    #[case(NumberFormat::Comma, 0, "0")]
+    fn test_integer(#[case] format: NumberFormat, #[case] number: u64, #[case] expected: &str) {
+        assert_eq!(format.integer(number), expected);
+    }
+
+    #[rstest]
+    #[case(NumberFormat::Plain, "1.2 MiB")]
+    #[case(NumberFormat::Dot, "1,2 MiB")]
+    fn test_size(#[case] format: NumberFormat, #[case] expected: &str) {
+        assert_eq!(format.size(ByteSize::b(1_234_567)), expected);
+    }
+}
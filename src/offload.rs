@@ -0,0 +1,96 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{header, StatusCode},
+    HttpResponse,
+};
+use futures::prelude::*;
+use percent_encoding::utf8_percent_encode;
+
+use crate::etag::requested_file;
+use crate::listing::percent_encode_sets::PATH_SEGMENT;
+use crate::{checksums, markdown, negotiation, MiniserveConfig};
+
+/// How the transfer of files is delegated to a reverse proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Offload {
+    /// Send an `X-Accel-Redirect` header (nginx) with the path of the file under this internal
+    /// location
+    XAccelRedirect(String),
+
+    /// Send an `X-Sendfile` header (Apache, lighttpd) with the absolute path of the file
+    XSendfile,
+}
+
+/// Middleware replacing the content of files by a header telling the reverse proxy to send the
+/// file itself, when `--x-accel-redirect` or `--x-sendfile` is enabled
+///
+/// Files are still requested from `actix_files`, so the usual access checks (hidden files,
+/// symlinks, ...) apply, and it must be wrapped by the authentication middleware. The proxy
+/// handles range requests on its own.
+pub fn offload_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let enabled = req
+        .app_data::<MiniserveConfig>()
+        .map(|conf| conf.offload.is_some())
+        .unwrap_or(false)
+        // Rendered files are not sent as is
+        && !markdown::should_render(&req)
+        && !negotiation::should_render(&req)
+        && !checksums::should_answer(&req);
+
+    let fut = srv.call(req);
+
+    async move {
+        let res = fut.await?.map_into_boxed_body();
+
+        let is_file_content = matches!(res.status(), StatusCode::OK | StatusCode::PARTIAL_CONTENT);
+        if !enabled || !is_file_content {
+            return Ok(res);
+        }
+
+        let conf = res.request().app_data::<MiniserveConfig>().unwrap();
+        let (path, offload) = match (requested_file(res.request().path(), conf), &conf.offload) {
+            (Some(path), Some(offload)) if path.is_file() => (path, offload),
+            _ => return Ok(res),
+        };
+
+        let offload_header = match offload {
+            Offload::XAccelRedirect(location) => {
+                let relative_path = path.strip_prefix(&conf.path).unwrap_or(&path);
+                let encoded_path = relative_path
+                    .iter()
+                    .map(|segment| {
+                        utf8_percent_encode(&segment.to_string_lossy(), PATH_SEGMENT).to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                (
+                    "X-Accel-Redirect",
+                    format!("{}/{encoded_path}", location.trim_end_matches('/')),
+                )
+            }
+            Offload::XSendfile => {
+                let path = path.canonicalize().unwrap_or(path);
+                ("X-Sendfile", path.to_string_lossy().to_string())
+            }
+        };
+
+        let mut offloaded = HttpResponse::Ok();
+        for (name, value) in res.headers() {
+            if ![header::CONTENT_LENGTH, header::CONTENT_RANGE].contains(name) {
+                offloaded.append_header((name.clone(), value.clone()));
+            }
+        }
+        offloaded.insert_header(offload_header);
+
+        Ok(res.into_response(offloaded.body(BoxBody::new(()))))
+    }
+}
@@ -0,0 +1,516 @@
+use actix_web::{HttpRequest, HttpResponse};
+use clap::crate_version;
+use serde_json::{json, Map, Value};
+use strum::IntoEnumIterator;
+
+use crate::archive::ArchiveMethod;
+use crate::MiniserveConfig;
+
+/// Serves an OpenAPI description of the HTTP API, limited to the features which are enabled
+pub async fn openapi_handler(req: HttpRequest) -> HttpResponse {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+
+    HttpResponse::Ok()
+        .content_type(mime::APPLICATION_JSON)
+        .body(openapi(conf).to_string())
+}
+
+/// Builds the OpenAPI document for the given configuration
+fn openapi(conf: &MiniserveConfig) -> Value {
+    let mut paths = Map::new();
+    paths.insert(
+        "/{path}".to_string(),
+        json!({ "get": listing_operation(conf) }),
+    );
+    if conf.file_upload {
+        paths.insert(
+            "/upload".to_string(),
+            json!({ "post": upload_operation(conf) }),
+        );
+    }
+    if conf.delete_enabled {
+        paths.insert("/delete".to_string(), json!({ "post": delete_operation() }));
+    }
+    let archive_methods = enabled_archive_methods(conf);
+    if !archive_methods.is_empty() {
+        let content = ArchiveMethod::iter()
+            .filter(|method| archive_methods.contains(&method.to_string()))
+            .map(|method| (method.content_type(), json!({})))
+            .collect::<Map<_, _>>();
+        paths.insert(
+            "/download".to_string(),
+            json!({
+                "get": {
+                    "summary": "Downloads an archive of a directory, in the format requested by the Accept header",
+                    "parameters": [query_parameter(
+                        "path",
+                        "Directory to archive, relative to the served directory",
+                        json!({ "type": "string" }),
+                    )],
+                    "responses": {
+                        "200": { "description": "The archive", "content": content },
+                        "406": { "description": "None of the accepted formats is enabled" },
+                    },
+                },
+            }),
+        );
+    }
+    if conf.feed.is_some() {
+        paths.insert(
+            "/feed.xml".to_string(),
+            json!({
+                "get": {
+                    "summary": "Atom feed of the most recently modified files",
+                    "responses": {
+                        "200": {
+                            "description": "The feed",
+                            "content": { "application/atom+xml": {} },
+                        },
+                    },
+                },
+            }),
+        );
+    }
+    if conf.recent.is_some() {
+        paths.insert(
+            "/recent".to_string(),
+            json!({
+                "get": {
+                    "summary": "Page listing the most recently modified files",
+                    "responses": {
+                        "200": {
+                            "description": "The list of files, newest first",
+                            "content": { "text/html": {} },
+                        },
+                    },
+                },
+            }),
+        );
+    }
+    if conf.sitemap.is_some() {
+        paths.insert(
+            "/sitemap.xml".to_string(),
+            json!({
+                "get": {
+                    "summary": "Sitemap of the files, for search engines",
+                    "responses": {
+                        "200": {
+                            "description": "The sitemap",
+                            "content": { "application/xml": {} },
+                        },
+                    },
+                },
+            }),
+        );
+    }
+    if conf.archive_jobs.is_some() {
+        let job_response = |description: &str| {
+            json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": { "$ref": "#/components/schemas/ArchiveJob" },
+                    },
+                },
+            })
+        };
+        let id_parameter = json!({
+            "name": "id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" },
+        });
+        paths.insert(
+            "/archive-jobs".to_string(),
+            json!({
+                "post": {
+                    "summary": "Starts generating an archive of a directory in the background",
+                    "parameters": [
+                        query_parameter(
+                            "path",
+                            "Directory to archive, relative to the served directory",
+                            json!({ "type": "string" }),
+                        ),
+                        query_parameter(
+                            "download",
+                            "Format of the archive",
+                            enum_schema(enabled_archive_methods(conf)),
+                        ),
+                    ],
+                    "responses": { "202": job_response("The job was started") },
+                },
+            }),
+        );
+        paths.insert(
+            "/archive-jobs/{id}".to_string(),
+            json!({
+                "get": {
+                    "summary": "Status and progress of an archive job",
+                    "parameters": [id_parameter.clone()],
+                    "responses": { "200": job_response("Status of the job") },
+                },
+            }),
+        );
+        paths.insert(
+            "/archive-jobs/{id}/download".to_string(),
+            json!({
+                "get": {
+                    "summary": "Downloads the archive of a finished job",
+                    "parameters": [id_parameter],
+                    "responses": {
+                        "200": {
+                            "description": "The archive",
+                            "content": { "application/octet-stream": {} },
+                        },
+                        "409": { "description": "The archive is not ready yet" },
+                    },
+                },
+            }),
+        );
+    }
+
+    let mut spec = json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "miniserve",
+            "version": crate_version!(),
+        },
+        "servers": [{ "url": format!("{}/", conf.route_prefix) }],
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "ListingEntry": {
+                    "type": "object",
+                    "required": ["name", "is_dir"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "is_dir": { "type": "boolean" },
+                        "size": {
+                            "type": "integer",
+                            "nullable": true,
+                            "description": "Size in bytes, null for directories",
+                        },
+                        "last_modified": {
+                            "type": "string",
+                            "format": "date-time",
+                            "nullable": true,
+                        },
+                        "checksum": {
+                            "type": "string",
+                            "description": "SHA-256 checksum of files, when requested",
+                        },
+                    },
+                },
+                "ArchiveJob": {
+                    "type": "object",
+                    "required": ["id", "status", "bytes_written", "download_url"],
+                    "properties": {
+                        "id": { "type": "string" },
+                        "status": enum_schema(["running", "done", "failed"]),
+                        "bytes_written": { "type": "integer" },
+                        "download_url": { "type": "string" },
+                        "error": { "type": "string" },
+                    },
+                },
+            },
+        },
+    });
+
+    if !conf.auth.is_empty() {
+        spec["components"]["securitySchemes"] = json!({
+            "basicAuth": { "type": "http", "scheme": "basic" },
+        });
+        spec["security"] = json!([{ "basicAuth": [] }]);
+    }
+
+    spec
+}
+
+/// Describes the route serving files and directory listings
+fn listing_operation(conf: &MiniserveConfig) -> Value {
+    let mut parameters = vec![
+        json!({
+            "name": "path",
+            "in": "path",
+            "required": true,
+            "description": "Path of a file or directory, relative to the served directory",
+            "schema": { "type": "string" },
+        }),
+        query_parameter(
+            "sort",
+            "Sorting method of listings",
+            enum_schema(["name", "size", "date"]),
+        ),
+        query_parameter(
+            "order",
+            "Sorting order of listings",
+            enum_schema(["asc", "desc"]),
+        ),
+        query_parameter(
+            "group",
+            "Grouping of directories relative to files in listings",
+            enum_schema(["first", "last", "none"]),
+        ),
+        query_parameter(
+            "type",
+            "Only lists the files of this type, guessed from their extension",
+            enum_schema(["image", "video", "audio", "text", "archive"]),
+        ),
+        query_parameter(
+            "q",
+            "Only lists the entries whose name contains this text, ignoring case",
+            json!({ "type": "string" }),
+        ),
+        query_parameter(
+            "page",
+            "Page of the HTML listing, from 1",
+            json!({ "type": "integer", "minimum": 0 }),
+        ),
+        query_parameter(
+            "per_page",
+            "Maximum number of entries in a page of the HTML listing, 1000 by default",
+            json!({ "type": "integer", "minimum": 1, "maximum": 10000 }),
+        ),
+        flag_parameter(
+            "hidden",
+            "Shows or leaves out hidden files in listings, archives and downloads, overriding --hidden",
+        ),
+        query_parameter(
+            "theme",
+            "Color scheme of the HTML listing, overriding the default and the remembered choice",
+            enum_schema(["squirrel", "archlinux", "zenburn", "monokai"]),
+        ),
+        flag_parameter(
+            "json",
+            "Returns the listing as JSON, as with `Accept: application/json`",
+        ),
+        query_parameter(
+            "limit",
+            "Maximum number of entries in a page of the JSON listing",
+            json!({ "type": "integer", "minimum": 1 }),
+        ),
+        query_parameter(
+            "cursor",
+            "Position of the page of the JSON listing, from the X-Next-Cursor header",
+            json!({ "type": "string" }),
+        ),
+        flag_parameter(
+            "ndjson",
+            "Streams the listing as JSON Lines, as with `Accept: application/x-ndjson`",
+        ),
+    ];
+
+    let archive_methods = enabled_archive_methods(conf);
+    if !archive_methods.is_empty() {
+        parameters.push(query_parameter(
+            "download",
+            "Downloads an archive of the directory",
+            enum_schema(archive_methods.clone()),
+        ));
+        parameters.push(query_parameter(
+            "archive",
+            "Lists the files an archive of the directory would include, with their total size",
+            enum_schema(["plan"]),
+        ));
+    }
+    if conf.checksums.is_some() {
+        parameters.push(query_parameter(
+            "checksums",
+            "Includes the SHA-256 checksums of files in JSON listings",
+            json!({ "type": "boolean" }),
+        ));
+        parameters.push(query_parameter(
+            "checksum",
+            "Serves the hex-encoded checksum of the file instead of its content",
+            json!({ "type": "string", "enum": ["md5", "sha1", "sha256"] }),
+        ));
+    }
+    if conf.render_markdown {
+        parameters.push(query_parameter(
+            "raw",
+            "Serves Markdown files as is instead of rendering them",
+            json!({ "type": "boolean" }),
+        ));
+    }
+
+    let mut responses = json!({
+        "200": {
+            "description": "A file, an archive or a directory listing",
+            "content": {
+                "text/html": {},
+                "application/json": {
+                    "schema": {
+                        "type": "array",
+                        "items": { "$ref": "#/components/schemas/ListingEntry" },
+                    },
+                },
+                "application/x-ndjson": {},
+                "application/octet-stream": {},
+            },
+            "headers": {
+                "X-Next-Cursor": {
+                    "description": "Cursor of the next page of the JSON listing",
+                    "schema": { "type": "string" },
+                },
+            },
+        },
+        "400": { "description": "Invalid cursor" },
+        "404": { "description": "No such file or directory" },
+    });
+    if !archive_methods.is_empty() {
+        responses["403"] = json!({ "description": "Archive creation is disabled" });
+    }
+    if conf.archive_rate_limiter.is_some() {
+        responses["429"] = json!({ "description": "Too many archive requests" });
+    }
+    if !conf.auth.is_empty() {
+        responses["401"] = json!({ "description": "Missing or invalid credentials" });
+    }
+
+    json!({
+        "summary": "Serves a file or lists a directory",
+        "parameters": parameters,
+        "responses": responses,
+    })
+}
+
+/// Describes the upload route
+fn upload_operation(conf: &MiniserveConfig) -> Value {
+    let mut properties = json!({
+        "file_to_upload": {
+            "type": "array",
+            "items": { "type": "string", "format": "binary" },
+        },
+    });
+    if conf.mkdir_enabled {
+        properties["mkdir"] = json!({
+            "type": "string",
+            "description": "Path of a directory to create",
+        });
+    }
+
+    let mut responses = json!({
+        "303": { "description": "Upload succeeded, redirects to the referring page" },
+        "403": { "description": "Upload not allowed to this directory" },
+    });
+    if conf.upload_quota.is_some() {
+        responses["507"] = json!({ "description": "Upload quota exceeded" });
+    }
+    if conf.max_upload_size.is_some() {
+        responses["413"] = json!({ "description": "Upload larger than the maximum size" });
+    }
+
+    json!({
+        "summary": "Uploads files or creates a directory",
+        "parameters": [
+            query_parameter(
+                "path",
+                "Directory to upload to, relative to the served directory",
+                json!({ "type": "string" }),
+            ),
+        ],
+        "requestBody": {
+            "required": true,
+            "content": {
+                "multipart/form-data": {
+                    "schema": { "type": "object", "properties": properties },
+                },
+            },
+        },
+        "responses": responses,
+    })
+}
+
+/// Describes the bulk delete route
+fn delete_operation() -> Value {
+    json!({
+        "summary": "Deletes several files or directories",
+        "parameters": [
+            query_parameter(
+                "path",
+                "Directory of the entries to delete, relative to the served directory",
+                json!({ "type": "string" }),
+            ),
+        ],
+        "requestBody": {
+            "required": true,
+            "content": {
+                "application/x-www-form-urlencoded": {
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "file": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Names of the entries to delete",
+                            },
+                        },
+                    },
+                },
+            },
+        },
+        "responses": {
+            "200": {
+                "description": "Outcome of the deletion of each entry",
+                "content": {
+                    "application/json": {
+                        "schema": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "path": { "type": "string" },
+                                    "deleted": { "type": "boolean" },
+                                    "error": { "type": "string" },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Returns the names of the archive methods which are enabled
+fn enabled_archive_methods(conf: &MiniserveConfig) -> Vec<String> {
+    ArchiveMethod::iter()
+        .filter(|method| {
+            method.is_enabled(
+                conf.tar_enabled,
+                conf.tar_gz_enabled,
+                conf.tar_zst_enabled,
+                conf.zip_enabled,
+            )
+        })
+        .map(|method| method.to_string())
+        .collect()
+}
+
+fn query_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+/// Describes a query parameter which only matters by its presence
+fn flag_parameter(name: &str, description: &str) -> Value {
+    let mut parameter = query_parameter(name, description, json!({ "type": "string" }));
+    parameter["allowEmptyValue"] = json!(true);
+    parameter
+}
+
+fn enum_schema<I, T>(values: I) -> Value
+where
+    I: IntoIterator<Item = T>,
+    T: Into<Value>,
+{
+    json!({
+        "type": "string",
+        "enum": values.into_iter().map(Into::into).collect::<Vec<_>>(),
+    })
+}
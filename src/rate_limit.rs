@@ -0,0 +1,336 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Sliding window rate limiter keyed by client IP address
+pub struct RateLimiter {
+    /// Maximum number of requests per client within `window`
+    max_requests: usize,
+
+    /// Length of the sliding window
+    window: Duration,
+
+    /// Times of the requests of each client within the current window
+    requests: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: usize, window: Duration) -> Self {
+        RateLimiter {
+            max_requests,
+            window,
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a request from `ip` made at `now`.
+    ///
+    /// If the client has exceeded its quota, the request is not recorded and the time to wait
+    /// before the next request is allowed is returned instead.
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut requests = self.requests.lock().unwrap();
+
+        // Forget requests which are out of the window, for all clients so the map can't grow
+        // without bounds
+        requests.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|&time| now.duration_since(time) >= self.window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = requests.entry(ip).or_default();
+        if times.len() >= self.max_requests {
+            let oldest = *times.front().unwrap();
+            return Err(self.window - now.duration_since(oldest));
+        }
+
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// Limits how many requests each client can have in progress at the same time
+pub struct ConcurrencyLimiter {
+    /// Maximum number of requests in progress per client
+    max_concurrent: usize,
+
+    /// Number of requests in progress for each client
+    in_progress: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(max_concurrent: usize) -> Self {
+        ConcurrencyLimiter {
+            max_concurrent,
+            in_progress: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a request from `ip`, unless the client already has too many in progress.
+    ///
+    /// The request is considered over once the returned permit is dropped.
+    pub fn acquire(self: &Arc<Self>, ip: IpAddr) -> Option<ConcurrencyPermit> {
+        let mut in_progress = self.in_progress.lock().unwrap();
+        let count = in_progress.entry(ip).or_default();
+        if *count >= self.max_concurrent {
+            return None;
+        }
+
+        *count += 1;
+        Some(ConcurrencyPermit {
+            limiter: Arc::clone(self),
+            ip,
+        })
+    }
+}
+
+/// Slot of a request in progress in a `ConcurrencyLimiter`
+pub struct ConcurrencyPermit {
+    limiter: Arc<ConcurrencyLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let mut in_progress = self.limiter.in_progress.lock().unwrap();
+        if let Some(count) = in_progress.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                in_progress.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// Spreads filesystem operations of directory walks (archives, feeds, ...) so that there are at
+/// most a given number per second, across all of them
+pub struct WalkThrottle {
+    /// Minimum time between two operations
+    interval: Duration,
+
+    /// Earliest time at which the next operation may happen
+    next: Mutex<Instant>,
+}
+
+impl WalkThrottle {
+    pub fn new(operations_per_second: u32) -> Self {
+        WalkThrottle {
+            interval: Duration::from_secs(1) / operations_per_second,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the next slot for an operation at or after `now`, returning how long to wait
+    /// before doing it
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut next = self.next.lock().unwrap();
+        let slot = (*next).max(now);
+        *next = slot + self.interval;
+        slot - now
+    }
+
+    /// Blocks the current thread until the next operation is allowed
+    pub fn wait(&self) {
+        let delay = self.reserve(Instant::now());
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// Token bucket limiting the bandwidth of each client, in bytes per second
+///
+/// Clients can burst up to one second worth of bytes after being idle.
+pub struct BandwidthLimiter {
+    /// Bytes per second allowed for each client
+    rate: f64,
+
+    /// Tokens of each client, in bytes, along with the time they were last updated. Tokens go
+    /// negative when a client takes more bytes than it has.
+    buckets: Mutex<HashMap<IpAddr, (f64, Instant)>>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        BandwidthLimiter {
+            rate: bytes_per_second as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes `bytes` from the bucket of `ip` at `now`, returning how long the client must wait
+    /// before taking more so that it stays within its rate
+    pub fn take(&self, ip: IpAddr, bytes: usize, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Forget clients whose bucket has refilled, so the map can't grow without bounds
+        buckets.retain(|_, bucket| refill(*bucket, self.rate, now) < self.rate);
+
+        let bucket = buckets.entry(ip).or_insert((self.rate, now));
+        take_from_bucket(bucket, self.rate, bytes, now)
+    }
+}
+
+/// Token bucket limiting the total bandwidth of all responses, in bytes per second
+///
+/// Responses can burst up to one second worth of bytes after being idle. Transfers taking small
+/// chunks in turn share the bandwidth fairly.
+pub struct TotalBandwidthLimiter {
+    /// Bytes per second allowed for all responses
+    rate: f64,
+
+    /// Tokens in bytes, along with the time they were last updated
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl TotalBandwidthLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second as f64;
+        TotalBandwidthLimiter {
+            rate,
+            bucket: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    /// Takes `bytes` from the bucket at `now`, returning how long the transfer must wait before
+    /// sending them so that all transfers stay within the rate
+    pub fn take(&self, bytes: usize, now: Instant) -> Duration {
+        take_from_bucket(&mut self.bucket.lock().unwrap(), self.rate, bytes, now)
+    }
+}
+
+/// Returns the tokens of `bucket` at `now`, refilled at `rate` up to one second worth of bytes
+fn refill((tokens, updated): (f64, Instant), rate: f64, now: Instant) -> f64 {
+    (tokens + now.saturating_duration_since(updated).as_secs_f64() * rate).min(rate)
+}
+
+/// Takes `bytes` from `bucket`, returning how long to wait until it is no longer in debt
+///
+/// Tokens go negative when more bytes are taken than the bucket holds.
+fn take_from_bucket(
+    bucket: &mut (f64, Instant),
+    rate: f64,
+    bytes: usize,
+    now: Instant,
+) -> Duration {
+    let tokens = refill(*bucket, rate, now) - bytes as f64;
+    *bucket = (tokens, now);
+    if tokens < 0.0 {
+        Duration::from_secs_f64(-tokens / rate)
+    } else {
+        Duration::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CLIENT_A: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 1));
+    const CLIENT_B: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+    #[test]
+    fn test_rate_limit_per_client() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(limiter.check(CLIENT_A, start), Ok(()));
+        assert_eq!(limiter.check(CLIENT_A, start), Ok(()));
+        assert_eq!(
+            limiter.check(CLIENT_A, start + Duration::from_secs(15)),
+            Err(Duration::from_secs(45))
+        );
+        assert_eq!(limiter.check(CLIENT_B, start), Ok(()));
+    }
+
+    #[test]
+    fn test_rate_limit_window_slides() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(limiter.check(CLIENT_A, start), Ok(()));
+        assert!(limiter
+            .check(CLIENT_A, start + Duration::from_secs(59))
+            .is_err());
+        assert_eq!(
+            limiter.check(CLIENT_A, start + Duration::from_secs(60)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_concurrency_limit_per_client() {
+        let limiter = Arc::new(ConcurrencyLimiter::new(2));
+
+        let first = limiter.acquire(CLIENT_A);
+        let second = limiter.acquire(CLIENT_A);
+        assert!(first.is_some() && second.is_some());
+        assert!(limiter.acquire(CLIENT_A).is_none());
+        assert!(limiter.acquire(CLIENT_B).is_some());
+
+        drop(first);
+        assert!(limiter.acquire(CLIENT_A).is_some());
+    }
+
+    #[test]
+    fn test_walk_throttle_spreads_operations() {
+        let throttle = WalkThrottle::new(10);
+        let start = Instant::now() + Duration::from_secs(1);
+
+        assert_eq!(throttle.reserve(start), Duration::ZERO);
+        assert_eq!(throttle.reserve(start), Duration::from_millis(100));
+        assert_eq!(throttle.reserve(start), Duration::from_millis(200));
+
+        // Time spent idle isn't saved up for later bursts
+        let later = start + Duration::from_secs(10);
+        assert_eq!(throttle.reserve(later), Duration::ZERO);
+        assert_eq!(throttle.reserve(later), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_bandwidth_limit_per_client() {
+        let limiter = BandwidthLimiter::new(1000);
+        let start = Instant::now();
+
+        // The first second worth of bytes is allowed right away
+        assert_eq!(limiter.take(CLIENT_A, 1000, start), Duration::ZERO);
+        assert_eq!(
+            limiter.take(CLIENT_A, 500, start),
+            Duration::from_millis(500)
+        );
+        assert_eq!(limiter.take(CLIENT_B, 1000, start), Duration::ZERO);
+
+        // The debt is paid off over time
+        let later = start + Duration::from_millis(500);
+        assert_eq!(
+            limiter.take(CLIENT_A, 100, later),
+            Duration::from_millis(100)
+        );
+
+        // Time spent idle is saved up to one second worth of bytes only
+        let idle = start + Duration::from_secs(60);
+        assert_eq!(limiter.take(CLIENT_A, 1000, idle), Duration::ZERO);
+        assert_eq!(limiter.take(CLIENT_A, 1000, idle), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_total_bandwidth_limit_is_shared() {
+        let limiter = TotalBandwidthLimiter::new(1000);
+        let start = Instant::now();
+
+        // Transfers taking turns wait for the bytes of each other
+        assert_eq!(limiter.take(1000, start), Duration::ZERO);
+        assert_eq!(limiter.take(250, start), Duration::from_millis(250));
+        assert_eq!(limiter.take(250, start), Duration::from_millis(500));
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.take(0, later), Duration::ZERO);
+        assert_eq!(limiter.take(1000, later), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,116 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::auth::{self, CurrentUser};
+use crate::errors::ContextualError;
+use crate::feed::{self, FeedEntry};
+use crate::renderer;
+use crate::MiniserveConfig;
+
+/// Keeps the `max_entries` most recently modified files among those it is extended with
+///
+/// When `anonymous` is set, files anonymous users may not see under its configuration are dropped.
+struct RecentFiles<'a> {
+    anonymous: Option<&'a MiniserveConfig>,
+    max_entries: usize,
+    heap: BinaryHeap<Reverse<(SystemTime, PathBuf)>>,
+}
+
+impl Extend<FeedEntry> for RecentFiles<'_> {
+    fn extend<I: IntoIterator<Item = FeedEntry>>(&mut self, iter: I) {
+        for entry in iter {
+            if let Some(conf) = self.anonymous {
+                if !auth::anonymous_allowed(conf, &entry.path) {
+                    continue;
+                }
+            }
+            self.heap.push(Reverse((entry.modified, entry.path)));
+            if self.heap.len() > self.max_entries {
+                // Drop the oldest file
+                self.heap.pop();
+            }
+        }
+    }
+}
+
+impl RecentFiles<'_> {
+    /// Returns the files kept, most recently modified first
+    fn into_entries(self) -> Vec<FeedEntry> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((modified, path))| FeedEntry { path, modified })
+            .collect()
+    }
+}
+
+/// Serves a page listing the most recently modified files under the served directory, when
+/// `--recent` is in use.
+pub async fn recent_handler(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let anonymous = auth::is_anonymous(conf, req.extensions().get::<CurrentUser>());
+
+    // The walk may be throttled, so it mustn't block the worker
+    let walk_conf = conf.clone();
+    let entries = web::block(move || {
+        let mut recent = RecentFiles {
+            anonymous: anonymous.then_some(&walk_conf),
+            max_entries: walk_conf.recent.unwrap_or_default(),
+            heap: BinaryHeap::new(),
+        };
+        feed::collect_files(
+            &walk_conf.path,
+            Path::new(""),
+            walk_conf.max_depth,
+            walk_conf.show_hidden,
+            &walk_conf,
+            &mut recent,
+        );
+        recent.into_entries()
+    })
+    .await
+    .map_err(|e| {
+        ContextualError::IoError("Failed to list files".to_string(), io::Error::other(e))
+    })?;
+
+    let title = conf
+        .title
+        .clone()
+        .unwrap_or_else(|| req.connection_info().host().into());
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(renderer::recent_files(&title, &entries, conf).into_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_most_recent_files() {
+        let mut recent = RecentFiles {
+            anonymous: None,
+            max_entries: 2,
+            heap: BinaryHeap::new(),
+        };
+        recent.extend([1, 3, 2, 0].map(|secs| FeedEntry {
+            path: PathBuf::from(secs.to_string()),
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+        }));
+
+        let paths = recent
+            .into_entries()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![PathBuf::from("3"), PathBuf::from("2")]);
+    }
+}
@@ -1,7 +1,7 @@
 use std::time::SystemTime;
 
 use actix_web::http::StatusCode;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 use chrono_humanize::Humanize;
 use clap::{crate_name, crate_version, ValueEnum};
 use fast_qr::{
@@ -11,17 +11,28 @@ use fast_qr::{
 };
 use http::Uri;
 use maud::{html, Markup, PreEscaped, DOCTYPE};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
 use strum::{Display, IntoEnumIterator};
 
 use crate::auth::CurrentUser;
 use crate::consts;
-use crate::listing::{Breadcrumb, Entry, QueryParameters, SortingMethod, SortingOrder};
+use crate::dir_config::{Column, DirConfig};
+use crate::disk_usage::DiskUsage;
+use crate::feed::{self, FeedEntry};
+use crate::listing::{
+    Breadcrumb, DirSummary, Entry, FileType, Pagination, QueryParameters, SortingMethod,
+    SortingOrder,
+};
+use crate::number_format::NumberFormat;
 use crate::{archive::ArchiveMethod, MiniserveConfig};
 
 #[allow(clippy::too_many_arguments)]
 /// Renders the file listing
 pub fn page(
     entries: Vec<Entry>,
+    pagination: Pagination,
+    summary: DirSummary,
     readme: Option<(String, String)>,
     abs_uri: &Uri,
     is_root: bool,
@@ -30,19 +41,51 @@ pub fn page(
     encoded_dir: &str,
     conf: &MiniserveConfig,
     current_user: Option<&CurrentUser>,
+    dir_config: &DirConfig,
 ) -> Markup {
-    // If query_params.raw is true, we want render a minimal directory listing
-    if query_params.raw.is_some() && query_params.raw.unwrap() {
-        return raw(entries, is_root);
+    // If query_params.raw is true or --minimal is set, we want render a minimal directory listing
+    if conf.minimal || query_params.raw == Some(true) {
+        return raw(entries, pagination, &query_params, is_root, conf.tz_utc);
     }
 
     let upload_route = format!("{}/upload", &conf.route_prefix);
     let (sort_method, sort_order) = (query_params.sort, query_params.order);
+    let file_type = query_params.file_type;
+    let search = query_params.search();
+    let hidden = query_params.hidden;
 
     let upload_action = build_upload_action(&upload_route, encoded_dir, sort_method, sort_order);
     let mkdir_action = build_mkdir_action(&upload_route, encoded_dir);
-
-    let title_path = breadcrumbs_to_path_string(breadcrumbs);
+    let delete_action = format!("{}/delete?path={}", &conf.route_prefix, encoded_dir);
+
+    let title = dir_config
+        .title
+        .clone()
+        .unwrap_or_else(|| breadcrumbs_to_path_string(breadcrumbs));
+    let (show_size, show_date) = (
+        dir_config.shows(Column::Size),
+        dir_config.shows(Column::Date),
+    );
+    let column_count = 1 + show_size as usize + show_date as usize;
+    let empty_colspan = column_count + conf.delete_enabled as usize;
+    let (default_color_scheme, default_color_scheme_dark) = match &dir_config.theme {
+        Some(theme) => (theme, theme),
+        None => (&conf.default_color_scheme, &conf.default_color_scheme_dark),
+    };
+    // Picked with ?theme=, this takes precedence over the one remembered by the theme selector
+    let chosen_color_scheme = query_params
+        .theme
+        .as_ref()
+        .map(|theme| format!("theme_{theme}"));
+    let is_empty = pagination.total == 0;
+    let is_page_empty = entries.is_empty();
+    let max_size = conf.size_bars.then(|| {
+        entries
+            .iter()
+            .filter_map(|entry| entry.size.map(|size| size.as_u64()))
+            .max()
+            .unwrap_or(0)
+    });
 
     let upload_allowed = conf.allowed_upload_dir.is_empty()
         || conf
@@ -53,19 +96,22 @@ pub fn page(
     html! {
         (DOCTYPE)
         html {
-            (page_header(&title_path, conf.file_upload, &conf.favicon_route, &conf.css_route))
+            (page_header(&title, conf.file_upload, &conf.favicon_route, &conf.css_route))
 
             body #drop-container
-                .(format!("default_theme_{}", conf.default_color_scheme))
-                .(format!("default_theme_dark_{}", conf.default_color_scheme_dark)) {
+                .(format!("default_theme_{default_color_scheme}"))
+                .(format!("default_theme_dark_{default_color_scheme_dark}"))
+                .(chosen_color_scheme.as_deref().unwrap_or_default())[chosen_color_scheme.is_some()] {
 
                 (PreEscaped(r#"
                     <script>
-                        // read theme from local storage and apply it to body
+                        // read theme from local storage and apply it to body, unless one was
+                        // chosen with ?theme=
                         const body = document.body;
                         var theme = localStorage.getItem('theme');
+                        var chosen = Array.from(body.classList).some(v=>v.startsWith("theme_"));
 
-                        if (theme != null && theme != 'default') {
+                        if (theme != null && theme != 'default' && !chosen) {
                             body.classList.add('theme_' + theme);
                         }
 
@@ -80,6 +126,23 @@ pub fn page(
 
                             localStorage.setItem('theme', name);
                         }
+
+                        // read the light/dark mode override from local storage, the default
+                        // themes otherwise follow prefers-color-scheme
+                        var colorMode = localStorage.getItem('color-mode');
+                        if (colorMode != null) {
+                            body.dataset.colorMode = colorMode;
+                        }
+
+                        // switches between the default light and dark themes, overriding
+                        // prefers-color-scheme, and saves the choice to local storage
+                        function toggleColorMode() {
+                            var isDark = body.dataset.colorMode
+                                ? body.dataset.colorMode == 'dark'
+                                : window.matchMedia('(prefers-color-scheme: dark)').matches;
+                            body.dataset.colorMode = isDark ? 'light' : 'dark';
+                            localStorage.setItem('color-mode', body.dataset.colorMode);
+                        }
                     </script>
                     "#))
 
@@ -87,7 +150,7 @@ pub fn page(
                     @if conf.file_upload {
                         div.form {
                             div.form_title {
-                                h1 { "Drop your file here to upload it" }
+                                h1 { "Drop your files here to upload them" }
                             }
                         }
                     }
@@ -112,7 +175,7 @@ pub fn page(
                                 // wrapped in span so the text doesn't shift slightly when it turns into a link
                                 span { bdi { (el.name) } }
                             } @else {
-                                a href=(parametrized_link(&el.link, sort_method, sort_order, false)) {
+                                a href=(parametrized_link(&el.link, sort_method, sort_order, false, hidden)) {
                                     bdi { (el.name) }
                                 }
                             }
@@ -120,20 +183,66 @@ pub fn page(
                         }
                     }
                     div.toolbar {
-                        @if conf.tar_enabled || conf.tar_gz_enabled || conf.zip_enabled {
+                        // Archives of an empty directory would be empty too
+                        @if !is_empty && (conf.tar_enabled || conf.tar_gz_enabled || conf.tar_zst_enabled || conf.zip_enabled) {
                             div.download {
                                 @for archive_method in ArchiveMethod::iter() {
-                                    @if archive_method.is_enabled(conf.tar_enabled, conf.tar_gz_enabled, conf.zip_enabled) {
-                                        (archive_button(archive_method, sort_method, sort_order))
+                                    @if archive_method.is_enabled(conf.tar_enabled, conf.tar_gz_enabled, conf.tar_zst_enabled, conf.zip_enabled) {
+                                        (archive_button(archive_method, sort_method, sort_order, hidden))
                                     }
                                 }
                             }
                         }
                         div.toolbar_box_group {
+                            div.toolbar_box {
+                                form.file-type method="GET" {
+                                    p { "Show only files of a type" }
+                                    div {
+                                        @if let (Some(method), Some(order)) = (sort_method, sort_order) {
+                                            input type="hidden" name="sort" value=(method.to_string());
+                                            input type="hidden" name="order" value=(order.to_string());
+                                        }
+                                        @if let Some(hidden) = hidden {
+                                            input type="hidden" name="hidden" value=(hidden.to_string());
+                                        }
+                                        @if let Some(search) = search {
+                                            input type="hidden" name="q" value=(search);
+                                        }
+                                        select name="type" onchange="this.form.submit()" {
+                                            option value="" selected[file_type.is_none()] { "All entries" }
+                                            @for choice in FileType::iter() {
+                                                option value=(choice.to_string()) selected[file_type == Some(choice)] { (choice.label()) }
+                                            }
+                                        }
+                                        noscript {
+                                            button type="submit" { "Filter" }
+                                        }
+                                    }
+                                }
+                            }
+                            div.toolbar_box {
+                                form.search method="GET" {
+                                    p { "Show only entries whose name contains" }
+                                    div {
+                                        @if let (Some(method), Some(order)) = (sort_method, sort_order) {
+                                            input type="hidden" name="sort" value=(method.to_string());
+                                            input type="hidden" name="order" value=(order.to_string());
+                                        }
+                                        @if let Some(hidden) = hidden {
+                                            input type="hidden" name="hidden" value=(hidden.to_string());
+                                        }
+                                        @if let Some(file_type) = file_type {
+                                            input type="hidden" name="type" value=(file_type.to_string());
+                                        }
+                                        input type="search" name="q" value=[search] placeholder="Search";
+                                        button type="submit" { "Search" }
+                                    }
+                                }
+                            }
                             @if conf.file_upload && upload_allowed {
                                 div.toolbar_box {
                                     form id="file_submit" action=(upload_action) method="POST" enctype="multipart/form-data" {
-                                        p { "Select a file to upload or drag it anywhere into the window" }
+                                        p { "Select files to upload or drag them anywhere into the window" }
                                         div {
                                             @match &conf.uploadable_media_type {
                                                 Some(accept) => {input #file-input accept=(accept) type="file" name="file_to_upload" required="" multiple {}},
@@ -144,6 +253,14 @@ pub fn page(
                                     }
                                 }
                             }
+                            @if conf.delete_enabled && !is_empty {
+                                div.toolbar_box {
+                                    p { "Select files and directories to delete" }
+                                    div {
+                                        button type="submit" form="bulk_delete" { "Delete selected" }
+                                    }
+                                }
+                            }
                             @if conf.mkdir_enabled {
                                 div.toolbar_box {
                                     form id="mkdir" action=(mkdir_action) method="POST" enctype="multipart/form-data" {
@@ -157,19 +274,64 @@ pub fn page(
                             }
                         }
                     }
+                    @if conf.delete_enabled {
+                        form id="bulk_delete" action=(delete_action) method="POST" onsubmit="return deleteSelected(this)" {}
+                        (PreEscaped(r#"
+                            <script>
+                                // asks for confirmation, then deletes the selected entries and
+                                // reports the ones which couldn't be deleted
+                                function deleteSelected(form) {
+                                    const data = new FormData(form);
+                                    const count = data.getAll('file').length;
+                                    if (count === 0 || !confirm('Delete ' + count + ' selected item(s)?')) {
+                                        return false;
+                                    }
+
+                                    fetch(form.action, { method: 'POST', body: new URLSearchParams(data) })
+                                        .then(res => res.json())
+                                        .then(results => {
+                                            const failures = results.filter(r => !r.deleted);
+                                            if (failures.length > 0) {
+                                                alert('Failed to delete:\n' + failures.map(r => r.path + ': ' + r.error).join('\n'));
+                                            }
+                                            location.reload();
+                                        });
+                                    return false;
+                                }
+                            </script>
+                            "#))
+                    }
+                    @if let Some(readme) = readme {
+                        div id="readme" {
+                            h3 id="readme-filename" { (readme.0) }
+                            div id="readme-contents" {
+                                (PreEscaped (readme.1))
+                            };
+                        }
+                    }
                     table {
                         thead {
-                            th.name { (build_link("name", "Name", sort_method, sort_order)) }
-                            th.size { (build_link("size", "Size", sort_method, sort_order)) }
-                            th.date { (build_link("date", "Last modification", sort_method, sort_order)) }
+                            @if conf.delete_enabled {
+                                th.select { }
+                            }
+                            th.name { (build_link("name", "Name", sort_method, sort_order, file_type, search, hidden)) }
+                            @if show_size {
+                                th.size { (build_link("size", "Size", sort_method, sort_order, file_type, search, hidden)) }
+                            }
+                            @if show_date {
+                                th.date { (build_link("date", "Last modification", sort_method, sort_order, file_type, search, hidden)) }
+                            }
                         }
                         tbody {
                             @if !is_root {
                                 tr {
-                                    td colspan="3" {
+                                    @if conf.delete_enabled {
+                                        td { }
+                                    }
+                                    td colspan=(column_count) {
                                         p {
                                             span.root-chevron { (chevron_left()) }
-                                            a.root href=(parametrized_link("../", sort_method, sort_order, false)) {
+                                            a.root href=(parametrized_link("../", sort_method, sort_order, false, hidden)) {
                                                 "Parent directory"
                                             }
                                         }
@@ -177,18 +339,26 @@ pub fn page(
                                 }
                             }
                             @for entry in entries {
-                                (entry_row(entry, sort_method, sort_order, false))
+                                (entry_row(entry, sort_method, sort_order, false, hidden, conf.delete_enabled, max_size, dir_config, conf.number_format, conf.tz_utc))
+                            }
+                            @if is_page_empty {
+                                tr {
+                                    td.empty colspan=(empty_colspan) {
+                                        @if !is_empty {
+                                            "No entries on this page"
+                                        } @else if search.is_some() {
+                                            "No matching entries"
+                                        } @else if file_type.is_some() {
+                                            "No files of this type"
+                                        } @else {
+                                            "This directory is empty"
+                                        }
+                                    }
+                                }
                             }
                         }
                     }
-                    @if let Some(readme) = readme {
-                        div id="readme" {
-                            h3 id="readme-filename" { (readme.0) }
-                            div id="readme-contents" {
-                                (PreEscaped (readme.1))
-                            };
-                        }
-                    }
+                    (pagination_links(pagination, &query_params))
                     a.back href="#top" {
                         (arrow_up())
                     }
@@ -196,6 +366,15 @@ pub fn page(
                         @if conf.show_wget_footer {
                             (wget_footer(abs_uri, conf.title.as_deref(), current_user.map(|x| &*x.name)))
                         }
+                        @if !is_empty {
+                            (summary_footer(summary, conf.number_format))
+                        }
+                        @if let Some(max_depth) = conf.max_depth.filter(|_| summary.dir_count > 0) {
+                            (depth_footer(max_depth))
+                        }
+                        @if let Some(disk_usage) = conf.disk_usage.as_ref().and_then(|monitor| monitor.get()) {
+                            (disk_usage_footer(disk_usage, conf.number_format))
+                        }
                         @if !conf.hide_version_footer {
                             (version_footer())
                         }
@@ -207,7 +386,13 @@ pub fn page(
 }
 
 /// Renders the file listing
-pub fn raw(entries: Vec<Entry>, is_root: bool) -> Markup {
+pub fn raw(
+    entries: Vec<Entry>,
+    pagination: Pagination,
+    query_params: &QueryParameters,
+    is_root: bool,
+    tz_utc: bool,
+) -> Markup {
     html! {
         (DOCTYPE)
         html {
@@ -223,7 +408,7 @@ pub fn raw(entries: Vec<Entry>, is_root: bool) -> Markup {
                             tr {
                                 td colspan="3" {
                                     p {
-                                        a.root href=(parametrized_link("../", None, None, true)) {
+                                        a.root href=(parametrized_link("../", None, None, true, query_params.hidden)) {
                                             ".."
                                         }
                                     }
@@ -231,15 +416,57 @@ pub fn raw(entries: Vec<Entry>, is_root: bool) -> Markup {
                             }
                         }
                         @for entry in entries {
-                            (entry_row(entry, None, None, true))
+                            (entry_row(entry, None, None, true, query_params.hidden, false, None, &DirConfig::default(), NumberFormat::Plain, tz_utc))
                         }
                     }
                 }
+                (pagination_links(pagination, query_params))
             }
         }
     }
 }
 
+/// Partial: links to the previous and next pages of a listing spanning several pages
+fn pagination_links(pagination: Pagination, query_params: &QueryParameters) -> Markup {
+    html! {
+        @if pagination.page_count() > 1 || pagination.page != 1 {
+            nav.pagination {
+                @if let Some(previous) = pagination.previous() {
+                    a.previous href=(page_link(query_params, previous)) { "Previous page" }
+                }
+                span { "Page " (pagination.page) " of " (pagination.page_count()) }
+                @if let Some(next) = pagination.next() {
+                    a.next href=(page_link(query_params, next)) { "Next page" }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the link to another page of the listing, keeping the other query parameters
+fn page_link(query_params: &QueryParameters, page: usize) -> String {
+    let mut link = format!("?page={page}");
+    if let Some(per_page) = query_params.per_page {
+        link = format!("{link}&per_page={per_page}");
+    }
+    if let (Some(method), Some(order)) = (query_params.sort, query_params.order) {
+        link = format!("{link}&sort={method}&order={order}");
+    }
+    if let Some(file_type) = query_params.file_type {
+        link = format!("{link}&type={file_type}");
+    }
+    if let Some(search) = query_params.search() {
+        link = format!("{link}&q={}", utf8_percent_encode(search, NON_ALPHANUMERIC));
+    }
+    if query_params.raw == Some(true) {
+        link = format!("{link}&raw=true");
+    }
+    if let Some(hidden) = query_params.hidden {
+        link = format!("{link}&hidden={hidden}");
+    }
+    link
+}
+
 /// Renders the QR code SVG
 fn qr_code_svg(url: &Uri, margin: usize) -> Result<String, QRCodeError> {
     let qr = QRBuilder::new(url.to_string())
@@ -268,6 +495,48 @@ fn version_footer() -> Markup {
     }
 }
 
+// Partial: number and total size of the files listed
+fn summary_footer(summary: DirSummary, number_format: NumberFormat) -> Markup {
+    let files = if summary.file_count == 1 {
+        "file"
+    } else {
+        "files"
+    };
+    html! {
+        div.dir-summary {
+            (format!(
+                "{} {files}, {} in total",
+                number_format.integer(summary.file_count),
+                number_format.size(summary.total_size)
+            ))
+        }
+    }
+}
+
+// Partial: notice that archives and recursive listings leave out deeper files, with --max-depth
+fn depth_footer(max_depth: usize) -> Markup {
+    let levels = if max_depth == 1 { "level" } else { "levels" };
+    html! {
+        div.truncated {
+            (format!("Archives and recursive listings are truncated to {max_depth} {levels} of directories"))
+        }
+    }
+}
+
+// Partial: disk usage footer
+fn disk_usage_footer(disk_usage: DiskUsage, number_format: NumberFormat) -> Markup {
+    html! {
+        div.disk-usage {
+            (format!(
+                "Disk usage: {} used, {} available, {} total",
+                number_format.size(disk_usage.used),
+                number_format.size(disk_usage.available),
+                number_format.size(disk_usage.total)
+            ))
+        }
+    }
+}
+
 fn wget_footer(abs_path: &Uri, root_dir_name: Option<&str>, current_user: Option<&str>) -> Markup {
     fn escape_apostrophes(x: &str) -> String {
         x.replace('\'', "'\"'\"'")
@@ -336,7 +605,8 @@ const THEME_PICKER_CHOICES: &[(&str, &str)] = &[
     ("Monokai (dark)", "monokai"),
 ];
 
-#[derive(Debug, Clone, ValueEnum, Display)]
+#[derive(Debug, Clone, ValueEnum, Display, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum ThemeSlug {
     #[strum(serialize = "squirrel")]
     Squirrel,
@@ -383,6 +653,11 @@ fn color_scheme_selector(hide_theme_selector: bool) -> Markup {
                     }
                 }
             }
+            div {
+                p #color-mode-toggle onclick="toggleColorMode()" title="Switch between the default light and dark themes" {
+                    "Light / dark"
+                }
+            }
         }
     }
 }
@@ -403,13 +678,14 @@ fn archive_button(
     archive_method: ArchiveMethod,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
+    hidden: Option<bool>,
 ) -> Markup {
-    let link = if sort_method.is_none() && sort_order.is_none() {
+    let link = if sort_method.is_none() && sort_order.is_none() && hidden.is_none() {
         format!("?download={archive_method}")
     } else {
         format!(
             "{}&download={}",
-            parametrized_link("", sort_method, sort_order, false),
+            parametrized_link("", sort_method, sort_order, false, hidden),
             archive_method
         )
     };
@@ -438,33 +714,42 @@ fn parametrized_link(
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
     raw: bool,
+    hidden: Option<bool>,
 ) -> String {
-    if raw {
-        return format!("{}?raw=true", make_link_with_trailing_slash(link));
-    }
+    let link = if raw {
+        format!("{}?raw=true", make_link_with_trailing_slash(link))
+    } else if let (Some(method), Some(order)) = (sort_method, sort_order) {
+        format!(
+            "{}?sort={}&order={}",
+            make_link_with_trailing_slash(link),
+            method,
+            order,
+        )
+    } else {
+        make_link_with_trailing_slash(link)
+    };
 
-    if let Some(method) = sort_method {
-        if let Some(order) = sort_order {
-            let parametrized_link = format!(
-                "{}?sort={}&order={}",
-                make_link_with_trailing_slash(link),
-                method,
-                order,
-            );
-
-            return parametrized_link;
-        }
-    }
+    with_hidden(link, hidden)
+}
 
-    make_link_with_trailing_slash(link)
+/// Adds `?hidden=` to `link` if it is set, so that hidden files stay shown or hidden
+fn with_hidden(link: String, hidden: Option<bool>) -> String {
+    match hidden {
+        Some(hidden) if link.contains('?') => format!("{link}&hidden={hidden}"),
+        Some(hidden) => format!("{link}?hidden={hidden}"),
+        None => link,
+    }
 }
 
-/// Partial: table header link
+/// Partial: table header link, keeping the `file_type` filter, the `search` and `hidden` if set
 fn build_link(
     name: &str,
     title: &str,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
+    file_type: Option<FileType>,
+    search: Option<&str>,
+    hidden: Option<bool>,
 ) -> Markup {
     let mut link = format!("?sort={name}&order=asc");
     let mut help = format!("Sort by {name} in ascending order");
@@ -484,6 +769,14 @@ fn build_link(
         }
     };
 
+    if let Some(file_type) = file_type {
+        link = format!("{link}&type={file_type}");
+    }
+    if let Some(search) = search {
+        link = format!("{link}&q={}", utf8_percent_encode(search, NON_ALPHANUMERIC));
+    }
+    let link = with_hidden(link, hidden);
+
     html! {
         span class=(class) {
             span.chevron { (chevron) }
@@ -492,38 +785,51 @@ fn build_link(
     }
 }
 
-/// Partial: row for an entry
+/// Partial: row for an entry, with a checkbox to select it if `selectable` is set, and a bar
+/// showing its size relative to `max_size` if set
+#[allow(clippy::too_many_arguments)]
 fn entry_row(
     entry: Entry,
     sort_method: Option<SortingMethod>,
     sort_order: Option<SortingOrder>,
     raw: bool,
+    hidden: Option<bool>,
+    selectable: bool,
+    max_size: Option<u64>,
+    dir_config: &DirConfig,
+    number_format: NumberFormat,
+    tz_utc: bool,
 ) -> Markup {
     html! {
         tr {
+            @if selectable {
+                td.select {
+                    input type="checkbox" name="file" value=(entry.name) form="bulk_delete" {}
+                }
+            }
             td {
                 p {
                     @if entry.is_dir() {
                         @if let Some(symlink_dest) = entry.symlink_info {
-                            a.symlink href=(parametrized_link(&entry.link, sort_method, sort_order, raw)) {
+                            a.symlink href=(parametrized_link(&entry.link, sort_method, sort_order, raw, hidden)) {
                                 (entry.name) "/"
                                 span.symlink-symbol { }
                                 a.directory {(symlink_dest) "/"}
                             }
                         }@else {
-                            a.directory href=(parametrized_link(&entry.link, sort_method, sort_order, raw)) {
+                            a.directory href=(parametrized_link(&entry.link, sort_method, sort_order, raw, hidden)) {
                                 (entry.name) "/"
                             }
                         }
                     } @else if entry.is_file() {
                         @if let Some(symlink_dest) = entry.symlink_info {
-                            a.symlink href=(&entry.link) {
+                            a.symlink href=(with_hidden(entry.link.clone(), hidden)) {
                                 (entry.name)
                                 span.symlink-symbol { }
                                 a.file {(symlink_dest)}
                             }
                         }@else {
-                            a.file href=(&entry.link) {
+                            a.file href=(with_hidden(entry.link.clone(), hidden)) {
                                 (entry.name)
                             }
                         }
@@ -531,27 +837,38 @@ fn entry_row(
                         @if !raw {
                             @if let Some(size) = entry.size {
                                 span.mobile-info.size {
-                                    (maud::display(size))
+                                    (number_format.size(size))
                                 }
                             }
                         }
                     }
                 }
             }
-            td.size-cell {
-                @if let Some(size) = entry.size {
-                    (maud::display(size))
-                }
-            }
-            td.date-cell {
-                @if let Some(modification_date) = convert_to_local(entry.last_modification_date) {
-                    span {
-                        (modification_date) " "
+            @if dir_config.shows(Column::Size) {
+                td.size-cell title=[entry.size.map(|size| format!("{} bytes", number_format.integer(size.as_u64())))] {
+                    @if let Some(size) = entry.size {
+                        (number_format.size(size))
+                        @if let Some(max_size) = max_size {
+                            div.size-bar {
+                                span style=(format!("width: {}%", size_percentage(size.as_u64(), max_size))) {}
+                            }
+                        }
+                    } @else {
+                        "-"
                     }
                 }
-                @if let Some(modification_timer) = humanize_systemtime(entry.last_modification_date) {
-                    span.history {
-                        (modification_timer)
+            }
+            @if dir_config.shows(Column::Date) {
+                td.date-cell {
+                    @if let Some(modification_date) = entry.last_modification_date {
+                        time datetime=(DateTime::<Utc>::from(modification_date).to_rfc3339()) {
+                            (format_date(modification_date, tz_utc)) " "
+                        }
+                        span.history {
+                            (modification_date.humanize())
+                        }
+                    } @else {
+                        "-"
                     }
                 }
             }
@@ -635,17 +952,139 @@ fn page_header(title: &str, file_upload: bool, favicon_route: &str, css_route: &
     }
 }
 
-/// Converts a SystemTime object to a strings tuple (date, time)
-fn convert_to_local(src_time: Option<SystemTime>) -> Option<String> {
-    src_time
-        .map(DateTime::<Local>::from)
-        .map(|date_time| date_time.format("%Y-%m-%d %H:%M:%S %:z").to_string())
+/// Formats a SystemTime in the time zone of the server, or in UTC if `utc` is set, which is only
+/// applied when rendering
+fn format_date(time: SystemTime, utc: bool) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+    match utc {
+        true => DateTime::<Utc>::from(time).format(FORMAT).to_string(),
+        false => DateTime::<Local>::from(time).format(FORMAT).to_string(),
+    }
+}
+
+/// Returns the size of a file as a percentage of the size of the largest one, rounded
+///
+/// All files are empty bars when the largest one is empty.
+fn size_percentage(size: u64, max_size: u64) -> u64 {
+    if max_size == 0 {
+        return 0;
+    }
+    (size as f64 * 100.0 / max_size as f64).round() as u64
+}
+
+/// Renders a file converted to HTML (Markdown, CSV, ...) as a standalone page
+pub fn rendered_file(file_name: &str, contents: &str, conf: &MiniserveConfig) -> Markup {
+    html! {
+        (DOCTYPE)
+        html {
+            (page_header(file_name, false, &conf.favicon_route, &conf.css_route))
+
+            body.(format!("default_theme_{}", conf.default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.default_color_scheme_dark)) {
+
+                (PreEscaped(r#"
+                    <script>
+                        // read theme from local storage and apply it to body
+                        var theme = localStorage.getItem('theme');
+                        if (theme != null && theme != 'default') {
+                            document.body.classList.add('theme_' + theme);
+                        }
+
+                        // read the light/dark mode override from local storage
+                        var colorMode = localStorage.getItem('color-mode');
+                        if (colorMode != null) {
+                            document.body.dataset.colorMode = colorMode;
+                        }
+                    </script>
+                    "#))
+
+                div.container {
+                    div id="readme" {
+                        h3 id="readme-filename" { (file_name) }
+                        div id="readme-contents" {
+                            (PreEscaped(contents))
+                        }
+                    }
+                    @if !conf.hide_version_footer {
+                        div.footer {
+                            (version_footer())
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
-/// Converts a SystemTime to a string readable by a human,
-/// and gives a rough approximation of the elapsed time since
-fn humanize_systemtime(time: Option<SystemTime>) -> Option<String> {
-    time.map(|time| time.humanize())
+/// Renders the list of the most recently modified files, newest first
+pub fn recent_files(title: &str, entries: &[FeedEntry], conf: &MiniserveConfig) -> Markup {
+    html! {
+        (DOCTYPE)
+        html {
+            (page_header(title, false, &conf.favicon_route, &conf.css_route))
+
+            body.(format!("default_theme_{}", conf.default_color_scheme))
+                .(format!("default_theme_dark_{}", conf.default_color_scheme_dark)) {
+
+                (PreEscaped(r#"
+                    <script>
+                        // read theme from local storage and apply it to body
+                        var theme = localStorage.getItem('theme');
+                        if (theme != null && theme != 'default') {
+                            document.body.classList.add('theme_' + theme);
+                        }
+
+                        // read the light/dark mode override from local storage
+                        var colorMode = localStorage.getItem('color-mode');
+                        if (colorMode != null) {
+                            document.body.dataset.colorMode = colorMode;
+                        }
+                    </script>
+                    "#))
+
+                div.container {
+                    h1.title { "Recently modified files" }
+                    table {
+                        thead {
+                            th.name { "Name" }
+                            th.date { "Last modification" }
+                        }
+                        tbody {
+                            @if entries.is_empty() {
+                                tr {
+                                    td colspan="2" { "No files" }
+                                }
+                            }
+                            @for entry in entries {
+                                tr {
+                                    td {
+                                        p {
+                                            a.file href=(feed::entry_url(&conf.route_prefix, &entry.path)) {
+                                                (entry.path.to_string_lossy())
+                                            }
+                                        }
+                                    }
+                                    td.date-cell {
+                                        time datetime=(DateTime::<Utc>::from(entry.modified).to_rfc3339()) {
+                                            (format_date(entry.modified, conf.tz_utc)) " "
+                                        }
+                                        span.history {
+                                            (entry.modified.humanize())
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    @if !conf.hide_version_footer {
+                        div.footer {
+                            (version_footer())
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Renders an error on the webpage
@@ -670,6 +1109,12 @@ pub fn render_error(
                         if (theme != null && theme != 'default') {
                             document.body.classList.add('theme_' + theme);
                         }
+
+                        // read the light/dark mode override from local storage
+                        var colorMode = localStorage.getItem('color-mode');
+                        if (colorMode != null) {
+                            document.body.dataset.colorMode = colorMode;
+                        }
                     </script>
                     "#))
 
@@ -762,4 +1207,17 @@ mod tests {
         let expected = to_html("-P '127.0.0.1:420' 'http://127.0.0.1:420");
         assert_eq!(to_be_tested, expected);
     }
+
+    #[test]
+    fn test_size_percentage() {
+        assert_eq!(size_percentage(50, 200), 25);
+        assert_eq!(size_percentage(200, 200), 100);
+        assert_eq!(size_percentage(0, 0), 0);
+    }
+
+    #[test]
+    fn test_format_date_utc() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        assert_eq!(format_date(time, true), "2001-09-09 01:46:40 +00:00");
+    }
 }
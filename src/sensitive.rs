@@ -0,0 +1,174 @@
+use std::path::{Component, Path, PathBuf};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+};
+use futures::prelude::*;
+
+use crate::auth_files::request_target;
+use crate::errors::ContextualError;
+use crate::file_upload::sanitize_path;
+use crate::MiniserveConfig;
+
+/// Names of files and directories which usually hold secrets or private history
+const SENSITIVE_NAMES: &[&str] = &[
+    ".aws",
+    ".docker",
+    ".env",
+    ".git",
+    ".gnupg",
+    ".htpasswd",
+    ".kube",
+    ".netrc",
+    ".npmrc",
+    ".pgpass",
+    ".pypirc",
+    ".ssh",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    "id_rsa",
+];
+
+/// How many levels of directories are scanned for sensitive files at startup
+pub const MAX_SCAN_DEPTH: usize = 3;
+
+/// Returns `true` if a file or directory named `name` is likely to hold secrets
+pub fn is_sensitive(name: &str) -> bool {
+    SENSITIVE_NAMES.contains(&name) || name.starts_with(".env.")
+}
+
+/// Returns `true` if one of the components of `path` is sensitive
+fn is_sensitive_path(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => is_sensitive(&name.to_string_lossy()),
+        _ => false,
+    })
+}
+
+/// Returns the sensitive entries under `root`, relative to it, down to `max_depth` levels of
+/// directories
+///
+/// Hidden entries are skipped unless `show_hidden` is set. Sensitive and symlinked directories
+/// are not walked.
+pub fn find_sensitive_files(root: &Path, show_hidden: bool, max_depth: usize) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    walk(root, Path::new(""), show_hidden, max_depth, &mut found);
+    found
+}
+
+fn walk(root: &Path, relative: &Path, show_hidden: bool, depth: usize, found: &mut Vec<PathBuf>) {
+    let read_dir = match root.join(relative).read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+
+    let mut entries = read_dir
+        .flatten()
+        .filter(|entry| show_hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = relative.join(entry.file_name());
+        if is_sensitive(&entry.file_name().to_string_lossy()) {
+            found.push(path);
+        } else if depth > 1 && entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            walk(root, &path, show_hidden, depth - 1, found);
+        }
+    }
+}
+
+/// Middleware hiding sensitive files and directories, when `--hide-sensitive` is in use
+///
+/// They are answered with 404 Not Found, like missing files, and archives of directories
+/// containing them are refused.
+pub fn hide_sensitive_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<B>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let result = if conf.hide_sensitive {
+        check_request(&req, conf)
+    } else {
+        Ok(())
+    };
+
+    let fut = result.map(|()| srv.call(req));
+    async move { fut?.await }
+}
+
+fn check_request(req: &ServiceRequest, conf: &MiniserveConfig) -> Result<(), ContextualError> {
+    let (target, wants_archive) = request_target(req, conf);
+    // Invalid paths are rejected later on
+    let relative = match sanitize_path(&target, true) {
+        Some(relative) => relative,
+        None => return Ok(()),
+    };
+
+    if is_sensitive_path(&relative) {
+        return Err(ContextualError::RouteNotFoundError(req.path().to_string()));
+    }
+    // Archives include hidden files, so all of them are looked for
+    if wants_archive
+        && !find_sensitive_files(&conf.path.join(&relative), true, usize::MAX).is_empty()
+    {
+        return Err(ContextualError::SensitiveDirectoryError(format!(
+            "/{}",
+            relative.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(".env", true)]
+    #[case(".env.local", true)]
+    #[case("id_rsa", true)]
+    #[case("id_rsa.pub", false)]
+    #[case("environment", false)]
+    #[case(".gitignore", false)]
+    fn test_is_sensitive(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(is_sensitive(name), expected);
+    }
+
+    #[rstest]
+    #[case(true, 3, &[".env", "a/.git", "a/b/id_rsa"])]
+    #[case(false, 3, &["a/b/id_rsa"])]
+    #[case(true, 2, &[".env", "a/.git"])]
+    fn test_find_sensitive_files(
+        #[case] show_hidden: bool,
+        #[case] max_depth: usize,
+        #[case] expected: &[&str],
+    ) {
+        let dir = assert_fs::TempDir::new().unwrap();
+        for file in [
+            ".env",
+            "a/.git/config",
+            "a/b/id_rsa",
+            "a/b/c/id_ed25519",
+            "a/file.txt",
+        ] {
+            dir.child(file).write_str("content").unwrap();
+        }
+
+        let expected = expected.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            find_sensitive_files(dir.path(), show_hidden, max_depth),
+            expected
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+use std::path::Path;
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::auth::{self, CurrentUser};
+use crate::errors::ContextualError;
+use crate::feed::{self, escape_xml, to_rfc3339};
+use crate::MiniserveConfig;
+
+/// Maximum number of URLs allowed in a sitemap by the protocol
+pub const MAX_SITEMAP_ENTRIES: usize = 50_000;
+
+/// Serves the robots.txt file given with `--robots-file`
+pub async fn robots_txt(req: HttpRequest) -> HttpResponse {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    HttpResponse::Ok()
+        .content_type(mime::TEXT_PLAIN_UTF_8)
+        .body(conf.robots_txt.clone().unwrap_or_default())
+}
+
+/// Serves a sitemap of the files under the served directory
+///
+/// Files are listed by path, with the same filters as the feed, up to the limit given with
+/// `--sitemap`.
+pub async fn sitemap_handler(req: HttpRequest) -> Result<HttpResponse, ContextualError> {
+    let conf = req.app_data::<MiniserveConfig>().unwrap();
+    let max_entries = conf.sitemap.unwrap_or_default();
+
+    let anonymous = auth::is_anonymous(conf, req.extensions().get::<CurrentUser>());
+
+    // The walk may be throttled, so it mustn't block the worker
+    let walk_conf = conf.clone();
+    let mut entries = web::block(move || {
+        let mut entries = Vec::new();
+        feed::collect_files(
+            &walk_conf.path,
+            Path::new(""),
+            walk_conf.max_depth,
+            walk_conf.show_hidden,
+            &walk_conf,
+            &mut entries,
+        );
+        entries
+    })
+    .await
+    .map_err(|e| {
+        ContextualError::IoError("Failed to list files".to_string(), io::Error::other(e))
+    })?;
+    if anonymous {
+        entries.retain(|entry| auth::anonymous_allowed(conf, &entry.path));
+    }
+    entries.sort_by(|e1, e2| e1.path.cmp(&e2.path));
+    entries.truncate(max_entries);
+
+    let base_url = format!(
+        "{}://{}{}",
+        req.connection_info().scheme(),
+        req.connection_info().host(),
+        conf.route_prefix
+    );
+
+    let mut sitemap = concat!(
+        r#"<?xml version="1.0" encoding="utf-8"?>"#,
+        "\n",
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+    )
+    .to_string();
+    for entry in entries {
+        sitemap.push_str(&format!(
+            "<url><loc>{loc}</loc><lastmod>{lastmod}</lastmod></url>",
+            loc = escape_xml(&feed::entry_url(&base_url, &entry.path)),
+            lastmod = to_rfc3339(entry.modified),
+        ));
+    }
+    sitemap.push_str("</urlset>\n");
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .body(sitemap))
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use actix_web::{
+    body::{BodySize, BodyStream, BoxBody, MessageBody, SizedStream},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    web::Bytes,
+};
+use futures::prelude::*;
+
+use crate::rate_limit::TotalBandwidthLimiter;
+use crate::MiniserveConfig;
+
+/// Size of the pieces throttled responses are sent in, so that concurrent transfers take turns
+const PIECE_SIZE: usize = 16 * 1024;
+
+/// Middleware limiting the total bandwidth of all responses, when `--max-total-rate` is in use
+///
+/// Bodies are sent in small pieces taken from a token bucket shared by all responses, so that
+/// concurrent transfers share the bandwidth fairly. Their size, if known, is kept.
+pub fn total_rate_middleware<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = actix_web::Result<ServiceResponse<BoxBody>>> + 'static
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody + 'static,
+    S::Future: 'static,
+{
+    let limiter = req
+        .app_data::<MiniserveConfig>()
+        .and_then(|conf| conf.total_rate_limiter.clone());
+
+    let fut = srv.call(req);
+
+    async move {
+        let res = fut.await?;
+        let limiter = match limiter {
+            Some(limiter) => limiter,
+            None => return Ok(res.map_into_boxed_body()),
+        };
+
+        Ok(res.map_body(|_, body| match body.size() {
+            BodySize::None | BodySize::Sized(0) => body.boxed(),
+            BodySize::Sized(size) => SizedStream::new(size, throttle(body, limiter)).boxed(),
+            BodySize::Stream => BodyStream::new(throttle(body, limiter)).boxed(),
+        }))
+    }
+}
+
+/// Streams `body` in pieces of at most `PIECE_SIZE` bytes, each one waiting for its turn in
+/// `limiter`
+fn throttle<B>(
+    body: B,
+    limiter: Arc<TotalBandwidthLimiter>,
+) -> impl Stream<Item = Result<Bytes, B::Error>>
+where
+    B: MessageBody + 'static,
+{
+    let body = Box::pin(body);
+    stream::unfold(
+        (body, Bytes::new(), limiter),
+        |(mut body, mut pending, limiter)| async move {
+            if pending.is_empty() {
+                match future::poll_fn(|cx| body.as_mut().poll_next(cx)).await? {
+                    Ok(bytes) => pending = bytes,
+                    Err(e) => return Some((Err(e), (body, pending, limiter))),
+                }
+            }
+
+            let piece = pending.split_to(pending.len().min(PIECE_SIZE));
+            let wait = limiter.take(piece.len(), Instant::now());
+            if !wait.is_zero() {
+                actix_web::rt::time::sleep(wait).await;
+            }
+            Some((Ok(piece), (body, pending, limiter)))
+        },
+    )
+}
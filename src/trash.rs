@@ -0,0 +1,118 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// Format of the names of the folders grouping the files deleted at the same time
+const FOLDER_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Directory where deleted files are moved to instead of being removed
+///
+/// Each deletion goes in a folder named after its time, where deleted files keep their path
+/// relative to the served directory. Folders older than `max_age` are purged.
+pub struct Trash {
+    dir: PathBuf,
+
+    /// If set, deleted files are removed from the trash after this long
+    max_age: Option<Duration>,
+}
+
+impl Trash {
+    pub fn new(dir: PathBuf, max_age: Option<Duration>) -> Self {
+        Trash { dir, max_age }
+    }
+
+    /// Moves `target` to `relative` in the folder of the deletions made at `now`
+    ///
+    /// The trash must be on the same filesystem as `target`, which is moved rather than copied.
+    pub fn put(&self, target: &Path, relative: &Path, now: DateTime<Utc>) -> io::Result<()> {
+        let dest = self
+            .dir
+            .join(now.format(FOLDER_FORMAT).to_string())
+            .join(relative);
+        if dest.symlink_metadata().is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is already in the trash", relative.display()),
+            ));
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(target, dest)
+    }
+
+    /// Removes the folders of the deletions made more than `max_age` before `now`
+    ///
+    /// Entries of the trash which weren't put there by miniserve are left alone.
+    pub fn purge(&self, now: DateTime<Utc>) {
+        let max_age = match self
+            .max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+        {
+            Some(max_age) => max_age,
+            None => return,
+        };
+        let read_dir = match self.dir.read_dir() {
+            Ok(read_dir) => read_dir,
+            Err(_) => return,
+        };
+
+        for entry in read_dir.flatten() {
+            let deleted_at = entry.file_name().to_str().and_then(|name| {
+                NaiveDateTime::parse_from_str(name, FOLDER_FORMAT)
+                    .ok()
+                    .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+            });
+            if deleted_at.is_some_and(|deleted_at| now - deleted_at > max_age) {
+                if let Err(e) = fs::remove_dir_all(entry.path()) {
+                    log::warn!("Failed to purge {}: {}", entry.path().display(), e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_trash_purges_old_deletions() {
+        let served = assert_fs::TempDir::new().unwrap();
+        let trash_dir = assert_fs::TempDir::new().unwrap();
+        served.child("old.txt").write_str("old").unwrap();
+        served.child("dir/new.txt").write_str("new").unwrap();
+        trash_dir.child("unrelated/file.txt").write_str("").unwrap();
+
+        let trash = Trash::new(
+            trash_dir.path().to_path_buf(),
+            Some(Duration::from_secs(3600)),
+        );
+        let start = Utc.with_ymd_and_hms(2023, 1, 1, 12, 0, 0).unwrap();
+        let later = start + chrono::Duration::minutes(30);
+        trash
+            .put(&served.path().join("old.txt"), Path::new("old.txt"), start)
+            .unwrap();
+        trash
+            .put(
+                &served.path().join("dir/new.txt"),
+                Path::new("dir/new.txt"),
+                later,
+            )
+            .unwrap();
+        trash_dir.child("20230101T120000Z/old.txt").assert("old");
+        trash_dir
+            .child("20230101T123000Z/dir/new.txt")
+            .assert("new");
+
+        trash.purge(start + chrono::Duration::minutes(90));
+        assert!(!trash_dir.child("20230101T120000Z").exists());
+        assert!(trash_dir.child("20230101T123000Z/dir/new.txt").exists());
+        assert!(trash_dir.child("unrelated/file.txt").exists());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::errors::ContextualError;
+
+/// Name of the file, at the root of the served directory, keeping track of the quota usage
+pub const SIDECAR_FILE_NAME: &str = ".miniserve-upload-quota";
+
+/// Global limit on the total size of uploaded files
+///
+/// The number of bytes used is persisted in a sidecar file so that the quota holds across
+/// restarts.
+pub struct UploadQuota {
+    /// Maximum total size of uploaded files
+    limit: u64,
+
+    /// File in which the number of used bytes is persisted
+    sidecar: PathBuf,
+
+    /// Bytes used by uploaded files and reserved by uploads in progress
+    usage: Mutex<Usage>,
+}
+
+/// Bytes counted against the quota
+struct Usage {
+    /// Bytes used by uploaded files, which are persisted
+    used: u64,
+
+    /// Bytes reserved by uploads in progress, which are settled when they are over
+    reserved: u64,
+}
+
+impl UploadQuota {
+    /// Creates a quota of `limit` bytes, restoring its usage from `sidecar` if it exists
+    pub fn new(limit: u64, sidecar: PathBuf) -> io::Result<Self> {
+        let used = match fs::read_to_string(&sidecar) {
+            Ok(content) => content
+                .trim()
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+
+        Ok(UploadQuota {
+            limit,
+            sidecar,
+            usage: Mutex::new(Usage { used, reserved: 0 }),
+        })
+    }
+
+    /// Reserves `size` bytes for an upload, failing if this would exceed the quota
+    pub fn reserve(&self, size: u64) -> Result<(), ContextualError> {
+        let mut usage = self.usage.lock().unwrap();
+        match usage
+            .used
+            .checked_add(usage.reserved)
+            .and_then(|total| total.checked_add(size))
+        {
+            Some(total) if total <= self.limit => {
+                usage.reserved += size;
+                Ok(())
+            }
+            _ => Err(ContextualError::InsufficientStorageError),
+        }
+    }
+
+    /// Settles a reservation of `reserved` bytes once the upload is over, `written` being the
+    /// number of bytes which were actually stored
+    pub fn commit(&self, reserved: u64, written: u64) -> Result<(), ContextualError> {
+        let mut usage = self.usage.lock().unwrap();
+        usage.reserved = usage.reserved.saturating_sub(reserved);
+        usage.used = usage.used.saturating_add(written);
+        self.persist(usage.used)
+    }
+
+    /// Gives `size` bytes back to the quota once files are deleted
+    ///
+    /// The deleted files may not have been uploaded, so the usage never goes below zero. Uploads
+    /// in progress keep their reservation.
+    pub fn release(&self, size: u64) -> Result<(), ContextualError> {
+        let mut usage = self.usage.lock().unwrap();
+        usage.used = usage.used.saturating_sub(size);
+        self.persist(usage.used)
+    }
+
+    /// Writes the number of `used` bytes to the sidecar file
+    fn persist(&self, used: u64) -> Result<(), ContextualError> {
+        fs::write(&self.sidecar, used.to_string()).map_err(|e| {
+            ContextualError::IoError(format!("Failed to write {}", self.sidecar.display()), e)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_quota_is_persisted() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let sidecar = dir.path().join(SIDECAR_FILE_NAME);
+
+        let quota = UploadQuota::new(100, sidecar.clone()).unwrap();
+        quota.reserve(60).unwrap();
+        quota.commit(60, 40).unwrap();
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), "40");
+
+        let quota = UploadQuota::new(100, sidecar).unwrap();
+        assert!(quota.reserve(61).is_err());
+        assert!(quota.reserve(60).is_ok());
+    }
+
+    #[test]
+    fn test_quota_is_released() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let sidecar = dir.path().join(SIDECAR_FILE_NAME);
+
+        let quota = UploadQuota::new(100, sidecar.clone()).unwrap();
+        quota.reserve(80).unwrap();
+        quota.commit(80, 80).unwrap();
+        quota.release(50).unwrap();
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), "30");
+
+        // Files which weren't uploaded may be deleted too
+        quota.release(50).unwrap();
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), "0");
+        assert!(quota.reserve(100).is_ok());
+    }
+
+    #[test]
+    fn test_release_keeps_reservations() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let sidecar = dir.path().join(SIDECAR_FILE_NAME);
+
+        let quota = UploadQuota::new(100, sidecar.clone()).unwrap();
+        quota.reserve(60).unwrap();
+        // Files which weren't uploaded are deleted while the upload is in progress
+        quota.release(50).unwrap();
+        assert!(quota.reserve(41).is_err());
+        quota.commit(60, 60).unwrap();
+        assert_eq!(fs::read_to_string(&sidecar).unwrap(), "60");
+        assert!(quota.reserve(41).is_err());
+        assert!(quota.reserve(40).is_ok());
+    }
+}
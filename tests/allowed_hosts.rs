@@ -0,0 +1,41 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+
+/// Only requests addressed to allowed hosts, `localhost` or the addresses listened on are
+/// answered with `--allowed-hosts`.
+#[rstest]
+#[case(Some("files.example.com"), StatusCode::OK)]
+#[case(Some("FILES.example.com:8080"), StatusCode::OK)]
+#[case(None, StatusCode::OK)]
+#[case(Some("127.0.0.1"), StatusCode::OK)]
+#[case(Some("evil.example.com"), StatusCode::BAD_REQUEST)]
+#[case(Some("192.0.2.1"), StatusCode::BAD_REQUEST)]
+fn hosts_are_checked(
+    #[with(&["--allowed-hosts", "files.example.com"])] server: TestServer,
+    #[case] host: Option<&str>,
+    #[case] expected: StatusCode,
+) -> Result<(), Error> {
+    let mut req = Client::new().get(server.url());
+    if let Some(host) = host {
+        req = req.header("Host", host);
+    }
+    assert_eq!(req.send()?.status(), expected);
+
+    Ok(())
+}
+
+/// Any host is accepted by default.
+#[rstest]
+fn hosts_are_not_checked_by_default(server: TestServer) -> Result<(), Error> {
+    let resp = Client::new()
+        .get(server.url())
+        .header("Host", "evil.example.com")
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
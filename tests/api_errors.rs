@@ -0,0 +1,80 @@
+mod fixtures;
+
+use fixtures::{server, server_no_stderr, Error, TestServer};
+use pretty_assertions::assert_eq;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use serde_json::Value;
+
+/// Returns the status, content type and body of the response to `path`
+fn get(
+    server: &TestServer,
+    path: &str,
+    accept: &str,
+) -> Result<(StatusCode, String, String), Error> {
+    let resp = Client::new()
+        .get(server.url().join(path)?)
+        .header("Accept", accept)
+        .send()?;
+    let content_type = resp.headers()["content-type"].to_str()?.to_string();
+    Ok((resp.status(), content_type, resp.text()?))
+}
+
+#[rstest]
+#[case("text/html")]
+#[case("*/*")]
+fn api_errors_are_json(
+    #[with(&["--api-errors"])] server_no_stderr: TestServer,
+    #[case] accept: &str,
+) -> Result<(), Error> {
+    let (status, content_type, body) = get(&server_no_stderr, "does-not-exist", accept)?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type, "application/json");
+
+    let error: Value = serde_json::from_str(&body)?;
+    assert_eq!(error["code"], 404);
+    assert!(error["error"].as_str().is_some_and(|msg| !msg.is_empty()));
+
+    Ok(())
+}
+
+#[rstest]
+fn api_errors_keep_authentication_challenge() -> Result<(), Error> {
+    let server = server_no_stderr(&["--api-errors", "-a", "user:password"]);
+
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().contains_key("www-authenticate"));
+
+    let error: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(
+        error,
+        serde_json::json!({
+            "error": "Invalid credentials for HTTP authentication",
+            "code": 401,
+        })
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn errors_are_not_json_by_default(server_no_stderr: TestServer) -> Result<(), Error> {
+    let (status, content_type, _) = get(&server_no_stderr, "does-not-exist", "text/html")?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(!content_type.starts_with("application/json"));
+
+    Ok(())
+}
+
+#[rstest]
+fn successful_responses_are_unchanged(
+    #[with(&["--api-errors"])] server: TestServer,
+) -> Result<(), Error> {
+    let (status, content_type, _) = get(&server, "", "text/html")?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/html"));
+
+    Ok(())
+}
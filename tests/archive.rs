@@ -1,10 +1,15 @@
-﻿mod fixtures;
+mod fixtures;
 
-use fixtures::{server, Error, TestServer};
+use assert_cmd::prelude::*;
+use fixtures::{port, server, tmpdir, wait_for_port, Error, TestServer};
 use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
-use select::predicate::Text;
+use select::predicate::{Class, Text};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
 
 #[rstest]
 fn archives_are_disabled(server: TestServer) -> Result<(), Error> {
@@ -56,3 +61,702 @@ fn test_tar_archives(#[with(&["-g"])] server: TestServer) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[rstest]
+fn test_tar_zst_archives(
+    #[with(&["--enable-tar-zst", "--tar-zst-level", "19"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Text).any(|x| x.text() == "Download .tar.zst"));
+    assert!(parsed.find(Text).all(|x| x.text() != "Download .tar.gz"));
+    assert_eq!(
+        reqwest::blocking::get(server.url().join("?download=tar_gz")?)?.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let resp =
+        reqwest::blocking::get(server.url().join("?download=tar_zst")?)?.error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], "application/zstd");
+    let body = zstd::decode_all(resp.bytes()?.as_ref())?;
+    let mut archive = tar::Archive::new(body.as_slice());
+    let paths = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let root = Path::new(server.path().file_name().unwrap());
+    assert!(paths.contains(&root.join("test.txt")));
+
+    Ok(())
+}
+
+/// /download picks the format of the archive from the Accept header
+#[rstest]
+#[case("application/zip", "application/zip")]
+#[case("application/gzip", "application/gzip")]
+#[case(
+    "application/x-7z-compressed, application/zip;q=0.9",
+    "application/zip"
+)]
+#[case("*/*", "application/gzip")]
+fn download_negotiates_archive_format(
+    #[case] accept: &str,
+    #[case] content_type: &str,
+    #[with(&["-g", "-z"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::Client::new()
+        .get(server.url().join("download?path=dira")?)
+        .header("Accept", accept)
+        .send()?
+        .error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], content_type);
+    assert_eq!(resp.headers()["vary"], "accept");
+    let body = resp.bytes()?;
+
+    match content_type {
+        "application/zip" => {
+            zip::ZipArchive::new(Cursor::new(body))?;
+        }
+        _ => {
+            let tar = libflate::gzip::Decoder::new(body.as_ref())?;
+            let mut archive = tar::Archive::new(tar);
+            archive.entries()?.count();
+        }
+    }
+
+    Ok(())
+}
+
+/// Disabled archive formats can't be requested from /download
+#[rstest]
+fn download_refuses_disabled_formats(#[with(&["-g"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::Client::new()
+        .get(server.url().join("download?path=dira")?)
+        .header("Accept", "application/zip")
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+
+    Ok(())
+}
+
+/// Files in zip archives are stored as is unless deflate is requested
+#[rstest]
+#[case(server(&["-z"]), zip::CompressionMethod::Stored)]
+#[case(server(&["-z", "--zip-compression", "deflate:9"]), zip::CompressionMethod::Deflated)]
+fn zip_compression_is_configurable(
+    #[case] server: TestServer,
+    #[case] method: zip::CompressionMethod,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join("?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(body))?;
+    let root = server.path().file_name().unwrap().to_string_lossy();
+    let mut entry = archive.by_name(&format!("{root}/test.txt"))?;
+    assert_eq!(entry.compression(), method);
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    assert_eq!(content, "Test Hello Yes");
+
+    Ok(())
+}
+
+/// Already compressed files are stored in zip archives with deflate compression, unless they
+/// are to be deflated too
+#[rstest]
+#[case(server(&["-z", "--zip-compression", "deflate:6"]), zip::CompressionMethod::Stored)]
+#[case(
+    server(&["-z", "--zip-compression", "deflate:6", "--zip-deflate-compressed"]),
+    zip::CompressionMethod::Deflated
+)]
+fn zip_stores_compressed_files(
+    #[case] server: TestServer,
+    #[case] jpeg_method: zip::CompressionMethod,
+) -> Result<(), Error> {
+    // A JPEG signature, then some data
+    let jpeg = [&b"\xFF\xD8\xFF\xE0"[..], &[0x42; 1024]].concat();
+    std::fs::write(server.path().join("photo.jpg"), jpeg)?;
+
+    let body = reqwest::blocking::get(server.url().join("?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(body))?;
+    let root = server.path().file_name().unwrap().to_string_lossy();
+    assert_eq!(
+        archive.by_name(&format!("{root}/photo.jpg"))?.compression(),
+        jpeg_method
+    );
+    assert_eq!(
+        archive.by_name(&format!("{root}/test.txt"))?.compression(),
+        zip::CompressionMethod::Deflated
+    );
+
+    Ok(())
+}
+
+/// Entry names of archives are normalized to NFC, unless `--no-archive-nfc` is given
+#[rstest]
+#[case(server(&["-r", "-z"]), "caf\u{E9}.txt")]
+#[case(server(&["-r", "-z", "--no-archive-nfc"]), "cafe\u{301}.txt")]
+fn archive_entry_names_are_normalized(
+    #[case] server: TestServer,
+    #[case] expected: &str,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("cafe\u{301}.txt"), "coffee")?;
+    let root = server.path().file_name().unwrap().to_string_lossy();
+    let expected = format!("{root}/{expected}");
+
+    let body = reqwest::blocking::get(server.url().join("?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let names = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert!(names.contains(&expected), "{expected:?} not in {names:?}");
+
+    let body = reqwest::blocking::get(server.url().join("?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(Cursor::new(body))?;
+    assert!(archive.file_names().any(|name| name == expected));
+
+    Ok(())
+}
+
+#[rstest]
+fn archives_are_rate_limited(
+    #[with(&["-r", "--archive-rate-limit", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    for _ in 0..2 {
+        assert_eq!(
+            reqwest::blocking::get(server.url().join("?download=tar")?)?.status(),
+            StatusCode::OK
+        );
+    }
+
+    let resp = reqwest::blocking::get(server.url().join("?download=tar")?)?;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = resp.headers()["retry-after"].to_str()?.parse()?;
+    assert!(retry_after > 0 && retry_after <= 60);
+
+    // Listings are not affected
+    assert_eq!(
+        reqwest::blocking::get(server.url())?.status(),
+        StatusCode::OK
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn archives_are_cached() -> Result<(), Error> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let server = server(&["-r", "--archive-cache", cache_dir.path().to_str().unwrap()]);
+
+    let first = reqwest::blocking::get(server.url().join("?download=tar")?)?.error_for_status()?;
+    assert_eq!(first.headers()["x-archive-cache"], "miss");
+    let first = first.bytes()?;
+
+    let second = reqwest::blocking::get(server.url().join("?download=tar")?)?.error_for_status()?;
+    assert_eq!(second.headers()["x-archive-cache"], "hit");
+    assert_eq!(second.bytes()?, first);
+
+    // Changing the directory invalidates the cached archive
+    std::fs::write(server.path().join("new_file"), "new content")?;
+    let third = reqwest::blocking::get(server.url().join("?download=tar")?)?.error_for_status()?;
+    assert_eq!(third.headers()["x-archive-cache"], "miss");
+    assert_ne!(third.bytes()?, first);
+
+    Ok(())
+}
+
+#[rstest]
+fn archive_downloads_can_be_resumed() -> Result<(), Error> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let server = server(&[
+        "-r",
+        "--archive-cache",
+        cache_dir.path().to_str().unwrap(),
+        "--archive-ranges",
+    ]);
+    let client = reqwest::blocking::Client::new();
+
+    // The whole archive is created before being sent, so its size is known
+    let full = client
+        .get(server.url().join("?download=tar")?)
+        .send()?
+        .error_for_status()?;
+    assert_eq!(full.headers()["x-archive-cache"], "miss");
+    assert_eq!(full.headers()["accept-ranges"], "bytes");
+    let full = full.bytes()?;
+    assert!(full.len() > 1000);
+
+    let partial = client
+        .get(server.url().join("?download=tar")?)
+        .header("Range", "bytes=1000-")
+        .send()?;
+    assert_eq!(partial.status(), reqwest::StatusCode::PARTIAL_CONTENT);
+    assert_eq!(partial.headers()["x-archive-cache"], "hit");
+    assert_eq!(
+        partial.headers()["content-range"],
+        format!("bytes 1000-{}/{}", full.len() - 1, full.len()).as_str()
+    );
+    assert_eq!(partial.bytes()?, full[1000..]);
+
+    Ok(())
+}
+
+/// Modification dates of archive entries are in UTC whatever the time zone of the server.
+#[rstest]
+#[case("UTC")]
+#[case("America/Los_Angeles")]
+#[case("Asia/Kathmandu")]
+fn archive_mtimes_are_utc(#[case] tz: &str) -> Result<(), Error> {
+    // 2021-06-15T12:34:56Z, with even seconds as ZIP dates have a resolution of 2 seconds
+    let timestamp = 1_623_760_496;
+    let port = port();
+    let tmpdir = tmpdir();
+    std::fs::create_dir(tmpdir.path().join("dated"))?;
+    let file = std::fs::File::create(tmpdir.path().join("dated/file.txt"))?;
+    file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp))?;
+
+    let child = Command::cargo_bin("miniserve")?
+        .env("TZ", tz)
+        .arg(tmpdir.path())
+        .args(["-p", &port.to_string(), "-r", "-z"])
+        .stdout(Stdio::null())
+        .spawn()?;
+    wait_for_port(port);
+    let server = TestServer::new(port, tmpdir, child, false);
+
+    let tar = reqwest::blocking::get(server.url().join("dated/?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut tar = tar::Archive::new(tar.as_ref());
+    let mut mtimes = tar
+        .entries()?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((entry.path()?.into_owned(), entry.header().mtime()?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    mtimes.retain(|(path, _)| path.ends_with("file.txt"));
+    assert_eq!(
+        mtimes,
+        vec![(Path::new("dated/file.txt").to_path_buf(), timestamp)]
+    );
+
+    let zip = reqwest::blocking::get(server.url().join("dated/?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut zip = zip::ZipArchive::new(Cursor::new(zip))?;
+    let mut entry = zip.by_name("dated/file.txt")?;
+    let mtime = entry.last_modified();
+    assert_eq!(
+        (
+            mtime.year(),
+            mtime.month(),
+            mtime.day(),
+            mtime.hour(),
+            mtime.minute(),
+            mtime.second()
+        ),
+        (2021, 6, 15, 12, 34, 56)
+    );
+    entry.read_to_end(&mut Vec::new())?;
+
+    Ok(())
+}
+
+/// The plan of an archive lists the files of the tarball, symlinks being left out with
+/// --no-symlinks, along with their total size
+#[cfg(unix)]
+#[rstest]
+#[case(server(&["-r", "-P"]), false)]
+#[case(server(&["-r"]), true)]
+fn archive_plan_lists_included_files(
+    #[case] server: TestServer,
+    #[case] followed: bool,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("dira/big.bin"), vec![0; 1000])?;
+    std::os::unix::fs::symlink(
+        server.path().join("test.txt"),
+        server.path().join("dira/linked_file.txt"),
+    )?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/?archive=plan")?)?
+        .error_for_status()?
+        .text()?;
+    let plan: serde_json::Value = serde_json::from_str(&body)?;
+    let files = plan["files"].as_array().unwrap();
+    let paths = files
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert!(paths.contains(&"dira/big.bin"));
+    assert_eq!(paths.contains(&"dira/linked_file.txt"), followed);
+    let total_size = files
+        .iter()
+        .map(|file| file["size"].as_u64().unwrap())
+        .sum::<u64>();
+    assert_eq!(plan["total_size"].as_u64(), Some(total_size));
+    for file in files {
+        let path = server.path().join(file["path"].as_str().unwrap());
+        assert_eq!(file["size"].as_u64(), Some(std::fs::metadata(path)?.len()));
+    }
+
+    // The tarball holds the files of the plan
+    let body = reqwest::blocking::get(server.url().join("dira/?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let mut tar_files = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            tar_files.push(entry.path()?.to_string_lossy().into_owned());
+        }
+    }
+    tar_files.sort();
+    assert_eq!(tar_files, paths);
+
+    Ok(())
+}
+
+/// Plans of archives are refused when archives are disabled
+#[rstest]
+fn archive_plan_is_disabled(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("?archive=plan")?)?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+/// Symlinks are left out of zip archives with --no-symlinks, so files outside of the served
+/// directory don't leak through them
+#[cfg(unix)]
+#[rstest]
+#[case(server(&["-z", "-P"]), false)]
+#[case(server(&["-z"]), true)]
+fn zip_archives_skip_symlinks(
+    #[case] server: TestServer,
+    #[case] followed: bool,
+) -> Result<(), Error> {
+    let outside = assert_fs::TempDir::new()?;
+    std::fs::write(outside.path().join("secret.txt"), "secret")?;
+    std::os::unix::fs::symlink(outside.path(), server.path().join("dira/linked_dir"))?;
+    std::os::unix::fs::symlink(
+        outside.path().join("secret.txt"),
+        server.path().join("dira/linked_file.txt"),
+    )?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(Cursor::new(body))?;
+    let names = archive.file_names().collect::<Vec<_>>();
+
+    assert!(names.contains(&"dira/test.txt"));
+    assert_eq!(names.contains(&"dira/linked_file.txt"), followed);
+    assert_eq!(names.contains(&"dira/linked_dir/secret.txt"), followed);
+
+    Ok(())
+}
+
+/// Hidden files and directories are archived only when shown, with --show-hidden or `?hidden=`
+#[rstest]
+#[case(server(&["-r", "-z"]), "", false)]
+#[case(server(&["-r", "-z"]), "&hidden=true", true)]
+#[case(server(&["-r", "-z", "--show-hidden"]), "", true)]
+#[case(server(&["-r", "-z", "--show-hidden"]), "&hidden=false", false)]
+fn archives_follow_hidden_files(
+    #[case] server: TestServer,
+    #[case] query: &str,
+    #[case] included: bool,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("dira/.secret"), "secret")?;
+    std::fs::create_dir(server.path().join("dira/.private"))?;
+    std::fs::write(server.path().join("dira/.private/notes.txt"), "notes")?;
+
+    let body = reqwest::blocking::get(server.url().join(&format!("dira/?download=tar{query}"))?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let names = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert!(names.iter().any(|name| name == "dira/test.txt"));
+    assert_eq!(names.iter().any(|name| name == "dira/.secret"), included);
+    assert_eq!(
+        names.iter().any(|name| name == "dira/.private/notes.txt"),
+        included
+    );
+
+    let body = reqwest::blocking::get(server.url().join(&format!("dira/?download=zip{query}"))?)?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(Cursor::new(body))?;
+    let names = archive.file_names().collect::<Vec<_>>();
+    assert!(names.contains(&"dira/test.txt"));
+    assert_eq!(names.contains(&"dira/.secret"), included);
+    assert_eq!(names.contains(&"dira/.private/notes.txt"), included);
+
+    Ok(())
+}
+
+#[rstest]
+fn tar_archives_keep_long_paths(#[with(&["-r"])] server: TestServer) -> Result<(), Error> {
+    let long_dir = Path::new("a_directory_with_a_rather_long_name")
+        .join("and_another_one_nested_inside_of_it")
+        .join("and_yet_another_one_to_go_past_the_limit");
+    std::fs::create_dir_all(server.path().join(&long_dir))?;
+    let long_path = long_dir.join("file_with_a_long_name.txt");
+    std::fs::write(server.path().join(&long_path), "content")?;
+    assert!(long_path.as_os_str().len() > 100);
+
+    let body = reqwest::blocking::get(server.url().join("?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let paths = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let root = server.path().file_name().unwrap();
+    assert!(paths.contains(&Path::new(root).join(&long_path)));
+
+    Ok(())
+}
+
+#[rstest]
+fn archive_disposition_encodes_utf8_names(
+    #[with(&["-r"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("café été"))?;
+
+    let resp = reqwest::blocking::get(server.url().join("café%20été/?download=tar")?)?
+        .error_for_status()?;
+    let disposition = resp.headers()["content-disposition"].to_str()?;
+    assert!(disposition.starts_with("attachment"));
+    assert!(disposition.contains("filename=\"caf_ _t_.tar\""));
+    assert!(disposition.contains("filename*=UTF-8''caf%C3%A9%20%C3%A9t%C3%A9.tar"));
+
+    Ok(())
+}
+
+#[rstest]
+fn empty_directories_have_valid_archives(
+    #[with(&["-r", "-g", "-z"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("empty"))?;
+
+    // The listing says the directory is empty, and doesn't offer archives of it
+    let body = reqwest::blocking::get(server.url().join("empty/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Text)
+        .any(|x| x.text() == "This directory is empty"));
+    assert!(parsed
+        .find(Text)
+        .all(|x| !x.text().starts_with("Download .")));
+
+    // Archives can still be requested, and only contain the directory itself
+    let body = reqwest::blocking::get(server.url().join("empty/?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let paths = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(paths, vec![Path::new("empty")]);
+
+    let body = reqwest::blocking::get(server.url().join("empty/?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(std::io::Cursor::new(body))?;
+    assert!(archive.is_empty());
+
+    Ok(())
+}
+
+#[rstest]
+fn non_empty_directories_have_no_empty_state(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Text)
+        .all(|x| x.text() != "This directory is empty"));
+
+    Ok(())
+}
+
+#[rstest]
+fn archive_creation_is_throttled(
+    #[with(&["-r", "--walk-throttle", "50"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("many"))?;
+    for i in 0..50 {
+        std::fs::write(server.path().join(format!("many/file{i}")), "content")?;
+    }
+
+    let archive_url = server.url().join("many/?download=tar")?;
+    let archive = std::thread::spawn(move || {
+        let start = std::time::Instant::now();
+        let body =
+            reqwest::blocking::get(archive_url).and_then(|resp| resp.error_for_status()?.bytes());
+        (start.elapsed(), body)
+    });
+
+    // Other requests are still served promptly meanwhile
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    for _ in 0..3 {
+        let start = std::time::Instant::now();
+        reqwest::blocking::get(server.url().join("test.txt")?)?.error_for_status()?;
+        assert!(start.elapsed() < std::time::Duration::from_millis(500));
+    }
+
+    // 51 entries at 50 per second
+    let (elapsed, body) = archive.join().unwrap();
+    let body = body?;
+    assert!(elapsed >= std::time::Duration::from_secs(1));
+    let mut archive = tar::Archive::new(body.as_ref());
+    assert_eq!(archive.entries()?.count(), 51);
+
+    Ok(())
+}
+
+/// Tarballs are streamed while they are created, so the download starts before the whole
+/// directory is archived and the archive is never held in memory
+#[rstest]
+#[case("tar")]
+#[case("tar_gz")]
+#[case("tar_zst")]
+fn tar_archives_are_streamed(
+    #[case] method: &str,
+    #[with(&["-r", "-g", "--enable-tar-zst", "--walk-throttle", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    // Large files which don't compress well, each one taking half a second to be reached
+    std::fs::create_dir(server.path().join("large"))?;
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    for i in 0..4 {
+        let content = (0..256 << 10)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(server.path().join(format!("large/file{i}")), content)?;
+    }
+
+    let start = std::time::Instant::now();
+    let mut resp =
+        reqwest::blocking::get(server.url().join(&format!("large/?download={method}"))?)?
+            .error_for_status()?;
+    let mut first_bytes = [0; 512];
+    resp.read_exact(&mut first_bytes)?;
+    let first_bytes_after = start.elapsed();
+    std::io::copy(&mut resp, &mut std::io::sink())?;
+    let done_after = start.elapsed();
+
+    // 5 entries at 2 per second
+    assert!(done_after >= std::time::Duration::from_secs(2));
+    assert!(
+        first_bytes_after < done_after / 2,
+        "first bytes after {first_bytes_after:?}, done after {done_after:?}"
+    );
+
+    Ok(())
+}
+
+/// Archives leave out the files deeper than --max-depth, counted from the archived directory
+#[rstest]
+fn archives_follow_max_depth(
+    #[with(&["-r", "-z", "--max-depth", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("very/top.txt"), "top")?;
+    std::fs::write(server.path().join("very/deeply/middle.txt"), "middle")?;
+
+    let body = reqwest::blocking::get(server.url().join("very/?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let names = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert!(names.iter().any(|name| name == "very/top.txt"));
+    assert!(names.iter().any(|name| name == "very/deeply/middle.txt"));
+    assert!(names.iter().any(|name| name == "very/deeply/nested"));
+    assert!(!names
+        .iter()
+        .any(|name| name == "very/deeply/nested/test.rs"));
+
+    let body = reqwest::blocking::get(server.url().join("very/?download=zip")?)?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(Cursor::new(body))?;
+    let names = archive.file_names().collect::<Vec<_>>();
+    assert!(names.contains(&"very/top.txt"));
+    assert!(names.contains(&"very/deeply/middle.txt"));
+    assert!(!names.contains(&"very/deeply/nested/test.rs"));
+
+    // Deeper directories are archived down to the same depth below them
+    let body = reqwest::blocking::get(server.url().join("very/deeply/?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let names = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert!(names.iter().any(|name| name == "deeply/nested/test.rs"));
+
+    Ok(())
+}
+
+/// Recursive listings stop at --max-depth, which listings with subdirectories mention
+#[rstest]
+fn listings_follow_max_depth(
+    #[with(&["--max-depth", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("very/deeply/middle.txt"), "middle")?;
+
+    let body = reqwest::blocking::get(server.url().join("very/?format=csv&recursive=true")?)?
+        .error_for_status()?
+        .text()?;
+    assert!(body.contains("deeply/middle.txt"));
+    assert!(!body.contains("test.rs"));
+
+    let body = reqwest::blocking::get(
+        server
+            .url()
+            .join("very/deeply/?format=csv&recursive=true")?,
+    )?
+    .error_for_status()?
+    .text()?;
+    assert!(body.contains("nested/test.rs"));
+
+    let body = reqwest::blocking::get(server.url().join("very/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let notice = parsed.find(Class("truncated")).next().unwrap().text();
+    assert_eq!(
+        notice,
+        "Archives and recursive listings are truncated to 2 levels of directories"
+    );
+
+    // Directories without subdirectories have nothing to truncate
+    let body =
+        reqwest::blocking::get(server.url().join("very/deeply/nested/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Class("truncated")).next().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DIRECTORIES};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+fn jobs_server(cache_dir: &Path, extra_args: &[&str]) -> TestServer {
+    let mut args = vec![
+        "-r",
+        "--archive-cache",
+        cache_dir.to_str().unwrap(),
+        "--archive-jobs",
+    ];
+    args.extend_from_slice(extra_args);
+    server(args)
+}
+
+/// Polls the status of the job at `status_url` until it isn't running anymore
+fn wait_for_job(client: &Client, server: &TestServer, status_url: &str) -> Result<Value, Error> {
+    let start = Instant::now();
+    loop {
+        let body = client
+            .get(server.url().join(status_url)?)
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let status: Value = serde_json::from_str(&body)?;
+        if status["status"] != "running" {
+            return Ok(status);
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "job never finished"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// A job is started, polled until complete, and its archive downloaded.
+#[rstest]
+fn archive_job_completes() -> Result<(), Error> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let server = jobs_server(cache_dir.path(), &[]);
+    std::fs::create_dir(server.path().join("jobdir"))?;
+    std::fs::write(server.path().join("jobdir/nested.txt"), "nested content")?;
+
+    let client = Client::new();
+    let resp = client
+        .post(
+            server
+                .url()
+                .join("archive-jobs?path=/jobdir/&download=tar")?,
+        )
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let status_url = resp.headers()["location"].to_str()?.to_string();
+    let status: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(
+        status_url,
+        format!("/archive-jobs/{}", status["id"].as_str().unwrap())
+    );
+
+    let status = wait_for_job(&client, &server, &status_url)?;
+    assert_eq!(status["status"], "done");
+    assert!(status["bytes_written"].as_u64().unwrap() > 0);
+
+    let resp = client
+        .get(
+            server
+                .url()
+                .join(status["download_url"].as_str().unwrap())?,
+        )
+        .send()?
+        .error_for_status()?;
+    assert!(resp.headers()["content-disposition"]
+        .to_str()?
+        .contains("jobdir.tar"));
+    let body = resp.bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let mut entries = archive
+        .entries()?
+        .map(|entry| {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content)?;
+            Ok((path, content))
+        })
+        .collect::<Result<Vec<(PathBuf, String)>, Error>>()?;
+    entries.sort();
+    assert_eq!(
+        entries,
+        vec![
+            (PathBuf::from("jobdir"), String::new()),
+            (
+                PathBuf::from("jobdir/nested.txt"),
+                "nested content".to_string()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+/// Jobs can't be downloaded before they are done, and unknown jobs don't exist.
+#[rstest]
+fn archive_job_not_ready() -> Result<(), Error> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let server = jobs_server(cache_dir.path(), &["--walk-throttle", "5"]);
+    for i in 0..5 {
+        std::fs::write(server.path().join(format!("dira/file{i}")), "content")?;
+    }
+
+    let client = Client::new();
+    let resp = client
+        .post(server.url().join(&format!(
+            "archive-jobs?path={}&download=tar",
+            DIRECTORIES[0]
+        ))?)
+        .send()?
+        .error_for_status()?;
+    let status: Value = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(status["status"], "running");
+
+    let resp = client
+        .get(
+            server
+                .url()
+                .join(status["download_url"].as_str().unwrap())?,
+        )
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let resp = client
+        .get(server.url().join("archive-jobs/unknown")?)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+/// Paths outside of the served directory and disabled formats are refused.
+#[rstest]
+#[case("archive-jobs?path=/not-there/&download=tar", StatusCode::BAD_REQUEST)]
+#[case("archive-jobs?path=/dira/&download=zip", StatusCode::FORBIDDEN)]
+#[case("archive-jobs?path=/dira/", StatusCode::BAD_REQUEST)]
+fn archive_job_invalid_requests(
+    #[case] query: &str,
+    #[case] expected: StatusCode,
+) -> Result<(), Error> {
+    let cache_dir = assert_fs::TempDir::new()?;
+    let server = jobs_server(cache_dir.path(), &[]);
+
+    let resp = Client::new().post(server.url().join(query)?).send()?;
+    assert_eq!(resp.status(), expected);
+
+    Ok(())
+}
@@ -177,3 +177,98 @@ fn auth_multiple_accounts_wrong_password(username: &str, password: &str) -> Resu
 
     Ok(())
 }
+
+#[rstest]
+fn auth_required_without_credentials() -> Result<(), Error> {
+    let server = server_no_stderr(&["--auth", "user:pass"]);
+    let response = Client::new().get(server.url()).send()?;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()["www-authenticate"],
+        "Basic realm=\"miniserve\""
+    );
+
+    Ok(())
+}
+
+/// Anonymous users only see the allowed files, authenticated users see everything.
+#[rstest]
+fn auth_anonymous_allowed_subset() -> Result<(), Error> {
+    let server = server_no_stderr(&["--auth", "user:pass", "--anonymous-allow", r"\.txt$"]);
+    let client = Client::new();
+
+    let body = client.get(server.url()).send()?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    for &file in FILES {
+        let listed = parsed.find(Text).any(|x| x.text() == file);
+        assert_eq!(listed, file.ends_with(".txt"), "{file}");
+    }
+
+    let allowed = client.get(server.url().join("test.txt")?).send()?;
+    assert_eq!(allowed.status(), StatusCode::OK);
+    let denied = client.get(server.url().join("test.html")?).send()?;
+    assert_eq!(denied.status(), StatusCode::NOT_FOUND);
+    let archive = client.get(server.url().join("?download=tar")?).send()?;
+    assert_eq!(archive.status(), StatusCode::UNAUTHORIZED);
+
+    let body = client
+        .get(server.url())
+        .basic_auth("user", Some("pass"))
+        .send()?
+        .error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    for &file in FILES {
+        assert!(parsed.find(Text).any(|x| x.text() == file));
+    }
+
+    let authenticated = client
+        .get(server.url().join("test.html")?)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(authenticated.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+/// Archives are not available anonymously from `/download` either, as they would contain the
+/// files hidden from anonymous users.
+#[rstest]
+fn auth_anonymous_download_refused() -> Result<(), Error> {
+    let server = server_no_stderr(&["--auth", "user:pass", "--anonymous-allow", r"\.txt$", "-z"]);
+    let client = Client::new();
+
+    let anonymous = client
+        .get(server.url().join("download")?)
+        .header("Accept", "application/zip")
+        .send()?;
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let authenticated = client
+        .get(server.url().join("download")?)
+        .header("Accept", "application/zip")
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(authenticated.status(), StatusCode::OK);
+
+    Ok(())
+}
+
+/// Archive plans are not available anonymously, as they would list the files hidden from
+/// anonymous users.
+#[rstest]
+fn auth_anonymous_archive_plan_refused() -> Result<(), Error> {
+    let server = server_no_stderr(&["--auth", "user:pass", "--anonymous-allow", r"\.txt$", "-z"]);
+    let client = Client::new();
+
+    let anonymous = client.get(server.url().join("?archive=plan")?).send()?;
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+    let authenticated = client
+        .get(server.url().join("?archive=plan")?)
+        .basic_auth("user", Some("pass"))
+        .send()?;
+    assert_eq!(authenticated.status(), StatusCode::OK);
+
+    Ok(())
+}
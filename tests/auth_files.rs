@@ -0,0 +1,219 @@
+mod fixtures;
+
+use std::fs;
+
+use fixtures::{server, Error, TestServer};
+use pretty_assertions::assert_eq;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+
+/// Credential of `testuser` with password `testpassword`
+const AUTH_LINE: &str =
+    "testuser:sha256:9f735e0df9a1ddc702bf0a1a7b83033f9f7153a00c29de82cedadc9957289b05";
+
+/// Creates `vault/secret.txt`, protected by an auth file, along with `open.txt` next to it
+fn create_vault(server: &TestServer) -> Result<(), Error> {
+    let vault = server.path().join("vault");
+    fs::create_dir_all(vault.join("inner"))?;
+    fs::write(
+        vault.join(".miniserve-auth"),
+        format!("# Vault\n{AUTH_LINE}\n"),
+    )?;
+    fs::write(vault.join("secret.txt"), "secret")?;
+    fs::write(vault.join("inner/deeper.txt"), "deeper")?;
+    fs::write(server.path().join("open.txt"), "open")?;
+    Ok(())
+}
+
+/// The subtree of an auth file requires its credentials, while its parent remains open.
+#[rstest]
+#[case("vault/")]
+#[case("vault/secret.txt")]
+#[case("vault/inner/deeper.txt")]
+fn auth_files_protect_their_subtree(
+    #[with(&["--auth-files"])] server: TestServer,
+    #[case] path: &str,
+) -> Result<(), Error> {
+    create_vault(&server)?;
+    let client = Client::new();
+
+    let status = client.get(server.url()).send()?.status();
+    assert_eq!(status, StatusCode::OK);
+    let status = client.get(server.url().join("open.txt")?).send()?.status();
+    assert_eq!(status, StatusCode::OK);
+
+    let url = server.url().join(path)?;
+    let resp = client.get(url.clone()).send()?;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().contains_key("www-authenticate"));
+
+    let status = client
+        .get(url.clone())
+        .basic_auth("testuser", Some("wrongpassword"))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let status = client
+        .get(url)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Auth files are ignored without `--auth-files`.
+#[rstest]
+fn auth_files_are_opt_in(server: TestServer) -> Result<(), Error> {
+    create_vault(&server)?;
+
+    let status = reqwest::blocking::get(server.url().join("vault/secret.txt")?)?.status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Auth files themselves are never served, even with their credentials and hidden files shown.
+#[rstest]
+#[case(server(&["--auth-files", "-H"]), "")]
+#[case(server(&["--auth-files"]), "?hidden=true")]
+fn auth_files_are_not_served(#[case] server: TestServer, #[case] query: &str) -> Result<(), Error> {
+    create_vault(&server)?;
+    let client = Client::new();
+
+    let status = client
+        .get(
+            server
+                .url()
+                .join(&format!("vault/.miniserve-auth{query}"))?,
+        )
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let listing = client
+        .get(server.url().join(&format!("vault/{query}"))?)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .error_for_status()?
+        .text()?;
+    assert!(listing.contains("secret.txt"));
+    assert!(!listing.contains(".miniserve-auth"));
+
+    Ok(())
+}
+
+/// Archives of an open directory containing a protected one are refused.
+#[rstest]
+fn auth_files_prevent_archives_of_parents(
+    #[with(&["--auth-files", "-r"])] server: TestServer,
+) -> Result<(), Error> {
+    create_vault(&server)?;
+
+    let status = reqwest::blocking::get(server.url().join("?download=tar")?)?.status();
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = Client::new()
+        .get(server.url().join("vault/?download=tar")?)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Archive plans of an open directory containing a protected one are refused as well, as they
+/// would list the protected files.
+#[rstest]
+fn auth_files_prevent_archive_plans_of_parents(
+    #[with(&["--auth-files", "-z"])] server: TestServer,
+) -> Result<(), Error> {
+    create_vault(&server)?;
+
+    let status = reqwest::blocking::get(server.url().join("?archive=plan")?)?.status();
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = reqwest::blocking::get(server.url().join("vault/?archive=plan")?)?.status();
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let status = Client::new()
+        .get(server.url().join("vault/?archive=plan")?)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Auth files are left out of archives, even when hidden files are shown.
+#[rstest]
+fn auth_files_are_not_archived(
+    #[with(&["--auth-files", "-H", "-r", "-z"])] server: TestServer,
+) -> Result<(), Error> {
+    create_vault(&server)?;
+
+    let body = Client::new()
+        .get(server.url().join("vault/?download=tar")?)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    let names = archive
+        .entries()?
+        .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert!(names.iter().any(|name| name == "vault/secret.txt"));
+    assert!(!names.iter().any(|name| name.ends_with(".miniserve-auth")));
+
+    let body = Client::new()
+        .get(server.url().join("vault/?download=zip")?)
+        .basic_auth("testuser", Some("testpassword"))
+        .send()?
+        .error_for_status()?
+        .bytes()?;
+    let archive = zip::ZipArchive::new(std::io::Cursor::new(body))?;
+    assert!(archive.file_names().any(|name| name == "vault/secret.txt"));
+    assert!(!archive
+        .file_names()
+        .any(|name| name.ends_with(".miniserve-auth")));
+
+    Ok(())
+}
+
+/// Both the global credentials and the ones of the auth file are required.
+#[rstest]
+#[case("testuser", "testpassword", StatusCode::OK)]
+#[case("otheruser", "otherpassword", StatusCode::UNAUTHORIZED)]
+fn auth_files_are_layered_on_global_auth(
+    #[with(&["--auth-files", "-a", "testuser:testpassword", "-a", "otheruser:otherpassword"])]
+    server: TestServer,
+    #[case] username: &str,
+    #[case] password: &str,
+    #[case] expected: StatusCode,
+) -> Result<(), Error> {
+    create_vault(&server)?;
+    let client = Client::new();
+
+    let status = client
+        .get(server.url())
+        .basic_auth(username, Some(password))
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    let status = client
+        .get(server.url().join("vault/secret.txt")?)
+        .basic_auth(username, Some(password))
+        .send()?
+        .status();
+    assert_eq!(status, expected);
+
+    Ok(())
+}
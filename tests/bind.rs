@@ -87,3 +87,38 @@ fn validate_printed_urls(tmpdir: TempDir, port: u16, #[case] args: &[&str]) -> R
 
     Ok(())
 }
+
+/// The whole server is under the printed random route, and the root is not found
+#[rstest]
+fn random_route_hides_root(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["-i", "127.0.0.1", "--random-route"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().unwrap();
+    // Kills the server when dropped, even if an assertion fails
+    let _server = TestServer::new(port, tmpdir, child, false);
+
+    let url_line = BufReader::new(stdout)
+        .lines()
+        .map(|line| line.expect("Error reading stdout"))
+        .find(|line| line.contains("http://"))
+        .expect("No URL printed");
+    let route = Regex::new(&format!(r"http://127\.0\.0\.1:{port}(/[0-9a-f]{{6}})\b"))
+        .unwrap()
+        .captures(&url_line)
+        .expect("No random route in the printed URL")[1]
+        .to_string();
+
+    let root_url = format!("http://127.0.0.1:{port}/");
+    let status = reqwest::blocking::get(root_url)?.status();
+    assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+    let route_url = format!("http://127.0.0.1:{port}{route}/");
+    reqwest::blocking::get(route_url)?.error_for_status()?;
+
+    Ok(())
+}
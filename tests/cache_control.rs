@@ -0,0 +1,49 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::header::CACHE_CONTROL;
+use rstest::rstest;
+use std::fs::File;
+
+/// Files get the policy of their extension or the default one, while listings get `no-cache`.
+#[rstest]
+fn cache_control_by_content_type(
+    #[with(&["--cache-max-age", "60", "--cache", "jpg=max-age=86400"])] server: TestServer,
+) -> Result<(), Error> {
+    File::create(server.path().join("photo.jpg"))?;
+
+    let resp = reqwest::blocking::get(server.url().join("photo.jpg")?)?.error_for_status()?;
+    assert_eq!(resp.headers().get(CACHE_CONTROL).unwrap(), "max-age=86400");
+
+    let resp = reqwest::blocking::get(server.url().join("test.txt")?)?.error_for_status()?;
+    assert_eq!(resp.headers().get(CACHE_CONTROL).unwrap(), "max-age=60");
+
+    for path in ["", "dira/"] {
+        let resp = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+        assert_eq!(resp.headers().get(CACHE_CONTROL).unwrap(), "no-cache");
+    }
+
+    Ok(())
+}
+
+/// Only files with an extension-specific policy get a header without `--cache-max-age`.
+#[rstest]
+fn cache_control_only_for_configured_extensions(
+    #[with(&["--cache", "jpg=max-age=86400"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("test.txt")?)?.error_for_status()?;
+    assert!(resp.headers().get(CACHE_CONTROL).is_none());
+
+    Ok(())
+}
+
+/// Without any policy, no `Cache-Control` header is sent.
+#[rstest]
+fn cache_control_not_set_by_default(server: TestServer) -> Result<(), Error> {
+    for path in ["", "test.txt"] {
+        let resp = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+        assert!(resp.headers().get(CACHE_CONTROL).is_none());
+    }
+
+    Ok(())
+}
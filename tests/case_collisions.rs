@@ -0,0 +1,77 @@
+// Names which only differ by case can't be created on case-insensitive filesystems
+#![cfg(not(any(windows, target_os = "macos")))]
+
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
+use fixtures::{port, Error};
+use rstest::rstest;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+fn colliding_tmpdir() -> Result<TempDir, Error> {
+    let tmpdir = TempDir::new()?;
+    for file in ["README.md", "readme.md", "dir/file.txt"] {
+        tmpdir.child(file).write_str("content")?;
+    }
+    Ok(tmpdir)
+}
+
+#[rstest]
+fn case_collisions_are_reported(port: u16) -> Result<(), Error> {
+    let tmpdir = colliding_tmpdir()?;
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--case-collisions", "warn"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Warnings are logged to stdout, before the server starts
+    let warning = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(|line| line.expect("Error reading stdout"))
+        .take_while(|line| !line.starts_with("Bound to"))
+        .find(|line| line.contains("only differ by case"));
+    child.kill()?;
+
+    let warning = warning.expect("No warning about case collisions");
+    assert!(warning.contains("'README.md' and 'readme.md'"));
+
+    Ok(())
+}
+
+#[rstest]
+fn case_collisions_are_denied(port: u16) -> Result<(), Error> {
+    let tmpdir = colliding_tmpdir()?;
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--case-collisions", "deny"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "The served directory contains names which only differ by case: 'README.md' and 'readme.md'",
+        ));
+
+    // Without collisions, the check passes
+    std::fs::remove_file(tmpdir.path().join("readme.md"))?;
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--case-collisions", "deny"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let started = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .any(|line| line.expect("Error reading stdout").starts_with("Bound to"));
+    child.kill()?;
+    assert!(started);
+
+    Ok(())
+}
@@ -1,9 +1,11 @@
 mod fixtures;
 
 use assert_cmd::prelude::*;
+use assert_fs::TempDir;
 use clap::{crate_name, crate_version, ValueEnum};
 use clap_complete::Shell;
-use fixtures::Error;
+use fixtures::{tmpdir, Error};
+use rstest::rstest;
 use std::process::Command;
 
 #[test]
@@ -54,3 +56,34 @@ fn print_completions_invalid_shell() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+/// Refuse to start on a path which doesn't exist.
+fn nonexistent_path_fails() -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg("/this/path/does/not/exist")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Failed to resolve path to be served '/this/path/does/not/exist'",
+        ));
+
+    Ok(())
+}
+
+/// Refuse to start when an upload directory leaves the served directory.
+#[rstest]
+#[case("../outside")]
+#[case("incoming/../../outside")]
+#[case("/tmp")]
+fn upload_dir_outside_served_dir_fails(tmpdir: TempDir, #[case] dir: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-u")
+        .arg(dir)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Invalid upload directory"));
+
+    Ok(())
+}
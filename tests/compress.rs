@@ -0,0 +1,43 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use rstest::rstest;
+use std::io::Read;
+
+/// Text files are compressed as negotiated with Accept-Encoding
+#[rstest]
+fn compresses_responses(#[with(&["--compress"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::Client::new()
+        .get(server.url().join("test.html")?)
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()?
+        .error_for_status()?;
+    assert_eq!(resp.headers()[CONTENT_ENCODING], "gzip");
+
+    let mut content = String::new();
+    libflate::gzip::Decoder::new(resp.bytes()?.as_ref())?.read_to_string(&mut content)?;
+    assert_eq!(content, "Test Hello Yes");
+
+    Ok(())
+}
+
+/// Responses are sent as is without --compress, and so are already compressed files
+#[rstest]
+#[case(server(&[] as &[&str]), "test.html")]
+#[case(server(&["--compress"]), "test.mkv")]
+#[case(server(&["--compress"]), "⎙.mp4")]
+fn sends_responses_as_is(#[case] server: TestServer, #[case] file: &str) -> Result<(), Error> {
+    let resp = reqwest::blocking::Client::new()
+        .get(server.url().join(file)?)
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()?
+        .error_for_status()?;
+    assert!(resp
+        .headers()
+        .get(CONTENT_ENCODING)
+        .is_none_or(|encoding| encoding == "identity"));
+    assert_eq!(resp.text()?, "Test Hello Yes");
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, tmpdir, wait_for_port, Error};
+use rstest::rstest;
+use select::{document::Document, predicate::Name};
+use std::process::{Command, Stdio};
+
+/// Options are read from the file given with `--config`, and those of the command line take
+/// precedence.
+#[rstest]
+fn options_are_read_from_config_file(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let config_file = tmpdir.path().join(".miniserve-config.toml");
+    let config = format!(
+        concat!(
+            "path = {path:?}\n",
+            "port = {port}\n",
+            "interfaces = [\"127.0.0.1\"]\n",
+            "title = \"From the file\"\n",
+            "upload-files = true\n",
+            "show-wget-footer = false\n",
+        ),
+        path = tmpdir.path(),
+        port = port,
+    );
+    std::fs::write(&config_file, config)?;
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config_file)
+        .arg("--title")
+        .arg("From the command line")
+        .stdout(Stdio::null())
+        .spawn()?;
+    wait_for_port(port);
+
+    let body = reqwest::blocking::get(format!("http://127.0.0.1:{port}"))?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert_eq!(title, "From the command line");
+    assert!(parsed.find(Name("form")).next().is_some());
+
+    child.kill()?;
+    child.wait()?;
+
+    Ok(())
+}
+
+/// Unknown keys are refused at startup.
+#[rstest]
+#[case("unknown-option = 1\n", "Unknown option 'unknown-option'")]
+#[case("port = \"not a port\"\n", "invalid value")]
+#[case("port = 8080\nport = 8081\n", "Failed to parse config file")]
+fn invalid_config_files_are_refused(
+    tmpdir: TempDir,
+    #[case] config: &str,
+    #[case] error: &str,
+) -> Result<(), Error> {
+    let config_file = tmpdir.path().join("config.toml");
+    std::fs::write(&config_file, config)?;
+
+    Command::cargo_bin("miniserve")?
+        .arg("--config")
+        .arg(&config_file)
+        .arg(tmpdir.path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(error));
+
+    Ok(())
+}
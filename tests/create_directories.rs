@@ -2,6 +2,7 @@ mod fixtures;
 
 use fixtures::{server, Error, TestServer, DIRECTORIES};
 use reqwest::blocking::{multipart, Client};
+use reqwest::StatusCode;
 use rstest::rstest;
 use select::document::Document;
 use select::predicate::{Attr, Text};
@@ -73,12 +74,12 @@ fn creating_directories_is_prevented(server: TestServer) -> Result<(), Error> {
 
     let client = Client::new();
     // This should fail
-    assert!(client
+    let status = client
         .post(server.url().join("/upload?path=/")?)
         .multipart(form)
         .send()?
-        .error_for_status()
-        .is_err());
+        .status();
+    assert_eq!(status, StatusCode::FORBIDDEN);
 
     // After creating, check whether the directory is now getting listed (shouldn't).
     let body = reqwest::blocking::get(server.url())?;
@@ -90,6 +91,34 @@ fn creating_directories_is_prevented(server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+/// This should fail because the directory names are blank or contain control characters
+#[rstest]
+#[case("")]
+#[case("  ")]
+#[case("new\nline")]
+#[case("tab\tbed")]
+fn creating_directories_with_invalid_names_fails(
+    #[with(&["--upload-files", "--mkdir"])] server: TestServer,
+    #[case] dir_name: &'static str,
+) -> Result<(), Error> {
+    let entry_count = || server.path().read_dir().unwrap().count();
+    let count_before = entry_count();
+
+    let form = multipart::Form::new();
+    let part = multipart::Part::text(dir_name);
+    let form = form.part("mkdir", part);
+
+    let status = Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(entry_count(), count_before);
+
+    Ok(())
+}
+
 /// This should fail because directory creation through symlinks should not be possible
 /// when the the no symlinks flag is set.
 #[rstest]
@@ -0,0 +1,61 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use pretty_assertions::assert_eq;
+use reqwest::blocking::get;
+use rstest::rstest;
+
+const DEFAULT_POLICY: &str =
+    "default-src 'self'; script-src 'none'; object-src 'none'; base-uri 'none'; form-action 'none'";
+
+/// Returns the `Content-Security-Policy` header of the response to `path`, if any
+fn csp(server: &TestServer, path: &str) -> Result<Option<String>, Error> {
+    let resp = get(server.url().join(path)?)?.error_for_status()?;
+    Ok(resp
+        .headers()
+        .get("content-security-policy")
+        .map(|policy| policy.to_str().unwrap().to_string()))
+}
+
+#[rstest]
+#[case(&["--csp"], DEFAULT_POLICY)]
+#[case(&["--csp", "default-src 'self'; script-src 'self'"], "default-src 'self'; script-src 'self'")]
+fn csp_is_sent_with_html_files(#[case] args: &[&str], #[case] expected: &str) -> Result<(), Error> {
+    let server = server(args);
+
+    assert_eq!(csp(&server, "test.html")?.as_deref(), Some(expected));
+
+    Ok(())
+}
+
+/// Other files and the listings generated by miniserve don't get the policy.
+#[rstest]
+#[case("test.txt")]
+#[case("")]
+fn csp_is_not_sent_with_other_responses(
+    #[with(&["--csp"])] server: TestServer,
+    #[case] path: &str,
+) -> Result<(), Error> {
+    assert_eq!(csp(&server, path)?, None);
+
+    Ok(())
+}
+
+/// Index files are user HTML even though they are served for a directory.
+#[rstest]
+fn csp_is_sent_with_index_files(
+    #[with(&["--csp", "--index", "index.html"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("index.html"), "<p>Index</p>")?;
+
+    assert_eq!(csp(&server, "")?.as_deref(), Some(DEFAULT_POLICY));
+
+    Ok(())
+}
+
+#[rstest]
+fn csp_is_not_sent_by_default(server: TestServer) -> Result<(), Error> {
+    assert_eq!(csp(&server, "test.html")?, None);
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DEEPLY_NESTED_FILE, DIRECTORIES, FILES};
+use rstest::rstest;
+
+/// Fetch the CSV listing at `path`, returning its rows split into fields.
+fn fetch_rows(server: &TestServer, path: &str) -> Result<Vec<Vec<String>>, Error> {
+    let resp = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], "text/csv; charset=utf-8");
+    Ok(resp
+        .text()?
+        .split_terminator("\r\n")
+        .map(parse_row)
+        .collect())
+}
+
+/// Split a CSV row into its fields, unquoting them.
+fn parse_row(row: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[rstest]
+fn csv_listing(server: TestServer) -> Result<(), Error> {
+    let rows = fetch_rows(&server, "?format=csv")?;
+
+    assert_eq!(rows[0], ["name", "size", "mtime", "is_dir"]);
+    assert_eq!(rows.len(), 1 + FILES.len() + DIRECTORIES.len() + 1);
+    for &file in FILES {
+        let row = rows.iter().find(|row| row[0] == file).expect(file);
+        assert_eq!(row[1], "Test Hello Yes".len().to_string());
+        assert!(!row[2].is_empty());
+        assert_eq!(row[3], "false");
+    }
+    for &dir in DIRECTORIES {
+        let row = rows
+            .iter()
+            .find(|row| row[0] == dir.trim_end_matches('/'))
+            .expect(dir);
+        assert_eq!((row[1].as_str(), row[3].as_str()), ("", "true"));
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn csv_listing_is_sorted(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("big.bin"), vec![0; 1024])?;
+
+    // Sorting follows the HTML listing, where the biggest files come first by default
+    let first_file = |rows: &[Vec<String>]| rows.iter().find(|row| row[3] == "false").cloned();
+    let rows = fetch_rows(&server, "?format=csv&sort=size")?;
+    assert_eq!(first_file(&rows).unwrap()[0], "big.bin");
+    let rows = fetch_rows(&server, "?format=csv&sort=size&order=asc")?;
+    assert_ne!(first_file(&rows).unwrap()[0], "big.bin");
+    assert_eq!(rows.last().unwrap()[0], "big.bin");
+
+    Ok(())
+}
+
+#[rstest]
+fn csv_listing_recursive(server: TestServer) -> Result<(), Error> {
+    let rows = fetch_rows(&server, "?format=csv&recursive=1")?;
+    let names = rows.iter().map(|row| row[0].as_str()).collect::<Vec<_>>();
+
+    for &dir in DIRECTORIES {
+        let file = format!("{dir}test.txt");
+        assert!(names.contains(&file.as_str()), "{file} is missing");
+        let row = rows.iter().find(|row| row[0] == file).unwrap();
+        assert_eq!(row[1], format!("This is {file}").len().to_string());
+    }
+    assert!(names.contains(&DEEPLY_NESTED_FILE));
+    assert!(rows.iter().skip(1).all(|row| row[3] == "false"));
+
+    Ok(())
+}
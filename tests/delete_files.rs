@@ -0,0 +1,109 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use fixtures::{server, tmpdir, Error, TestServer};
+use reqwest::blocking::Client;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Attr;
+use serde_json::Value;
+use std::process::Command;
+
+#[rstest]
+fn delete_is_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("id", "bulk_delete")).next().is_none());
+
+    let resp = Client::new()
+        .post(server.url().join("delete?path=/")?)
+        .form(&[("file", "test.txt")])
+        .send()?;
+    assert!(!resp.status().is_success());
+    assert!(server.path().join("test.txt").exists());
+
+    Ok(())
+}
+
+#[rstest]
+fn bulk_delete(#[with(&["--rm-files"])] server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let action = parsed
+        .find(Attr("id", "bulk_delete"))
+        .next()
+        .expect("Couldn't find element with id=bulk_delete")
+        .attr("action")
+        .expect("Bulk delete form doesn't have action attribute");
+    assert!(parsed.find(Attr("value", "test.txt")).next().is_some());
+
+    let resp = Client::new()
+        .post(server.url().join(action)?)
+        .form(&[
+            ("file", "test.txt"),
+            ("file", "dira"),
+            ("file", "missing.txt"),
+            ("file", "../escape.txt"),
+        ])
+        .send()?
+        .error_for_status()?;
+    let results: Vec<Value> = serde_json::from_str(&resp.text()?)?;
+    let deleted = results
+        .iter()
+        .map(|result| result["deleted"].as_bool().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(deleted, [true, true, false, false]);
+
+    assert!(!server.path().join("test.txt").exists());
+    assert!(!server.path().join("dira").exists());
+    assert!(server.path().join("test.html").exists());
+    assert!(server.path().join("dirb/test.txt").exists());
+
+    Ok(())
+}
+
+/// With `--trash-dir`, deleted files are moved to the trash, keeping their relative path.
+#[rstest]
+fn delete_moves_to_trash() -> Result<(), Error> {
+    let trash = assert_fs::TempDir::new()?;
+    let server = server(&["--rm-files", "--trash-dir", trash.path().to_str().unwrap()]);
+
+    let resp = Client::new()
+        .post(server.url().join("delete?path=/dirb")?)
+        .form(&[("file", "test.txt")])
+        .send()?
+        .error_for_status()?;
+    let results: Vec<Value> = serde_json::from_str(&resp.text()?)?;
+    assert_eq!(results[0]["deleted"], true);
+
+    let listing = reqwest::blocking::get(server.url().join("dirb/")?)?
+        .error_for_status()?
+        .text()?;
+    assert!(!listing.contains("test.txt"));
+    assert!(!server.path().join("dirb/test.txt").exists());
+
+    // Deletions are grouped in a folder named after their time
+    let folders = std::fs::read_dir(trash.path())?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(folders.len(), 1);
+    assert!(folders[0].path().join("dirb/test.txt").is_file());
+
+    Ok(())
+}
+
+/// The trash can't be inside the served directory, where deleted files would still be served.
+#[rstest]
+fn trash_dir_outside_of_served_dir() -> Result<(), Error> {
+    let tmpdir = tmpdir();
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("--rm-files")
+        .arg("--trash-dir")
+        .arg(tmpdir.path().join("trash"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "can't be inside the served directory",
+        ));
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::{document::Document, predicate::Name};
+
+const DIR_CONFIG: &str = r#"
+title = "Custom title"
+theme = "monokai"
+columns = ["date"]
+unknown = true
+"#;
+
+/// A .miniserve.toml file overrides the settings of the listing of its directory only.
+#[rstest]
+fn dir_config_overrides_listing(
+    #[with(&["--dir-config"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("dira/.miniserve.toml"), DIR_CONFIG)?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert_eq!(title, "Custom title");
+    let body = parsed.find(Name("body")).next().unwrap();
+    assert!(body
+        .attr("class")
+        .unwrap()
+        .contains("default_theme_monokai"));
+    assert!(parsed
+        .find(Name("th"))
+        .all(|th| th.attr("class") != Some("size")));
+    assert!(parsed
+        .find(Name("th"))
+        .any(|th| th.attr("class") == Some("date")));
+
+    let body = reqwest::blocking::get(server.url().join("dirb/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert!(title.ends_with("/dirb"));
+    assert!(parsed
+        .find(Name("th"))
+        .any(|th| th.attr("class") == Some("size")));
+
+    Ok(())
+}
+
+/// .miniserve.toml files are ignored without `--dir-config`.
+#[rstest]
+fn dir_config_is_opt_in(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("dira/.miniserve.toml"), DIR_CONFIG)?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let title = parsed.find(Name("title")).next().unwrap().text();
+    assert!(title.ends_with("/dira"));
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Class;
+
+/// Returns the disk usage shown in the footer of the root listing, if any
+fn disk_usage(server: &TestServer) -> Result<Option<String>, Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(Class("disk-usage"))
+        .next()
+        .map(|node| node.text()))
+}
+
+#[rstest]
+fn disk_usage_is_shown_in_footer(
+    #[with(&["--show-disk-usage"])] server: TestServer,
+) -> Result<(), Error> {
+    let disk_usage = disk_usage(&server)?.expect("no disk usage in the footer");
+
+    let figures = disk_usage
+        .trim_start_matches("Disk usage: ")
+        .split(", ")
+        .collect::<Vec<_>>();
+    assert_eq!(figures.len(), 3, "unexpected disk usage {disk_usage:?}");
+    for (figure, label) in figures.iter().zip(["used", "available", "total"]) {
+        let size = figure.strip_suffix(label).map(str::trim);
+        assert!(
+            size.is_some_and(|size| size.ends_with('B')),
+            "unexpected disk usage {disk_usage:?}"
+        );
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn disk_usage_is_hidden_by_default(server: TestServer) -> Result<(), Error> {
+    assert_eq!(disk_usage(&server)?, None);
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+/// Create two files with identical content but different modification dates.
+fn write_identical_files(server: &TestServer) {
+    let now = SystemTime::now();
+    for (name, age) in [("first.bin", 3600), ("second.bin", 60)] {
+        let mut file = File::create(server.path().join(name)).unwrap();
+        file.write_all(b"identical content").unwrap();
+        file.set_modified(now - Duration::from_secs(age)).unwrap();
+    }
+}
+
+fn etag_of(server: &TestServer, name: &str) -> Result<String, Error> {
+    let resp = reqwest::blocking::get(server.url().join(name)?)?.error_for_status()?;
+    Ok(resp.headers()["etag"].to_str()?.to_string())
+}
+
+/// By default, ETags depend on the modification date.
+#[rstest]
+fn etag_depends_on_mtime_by_default(server: TestServer) -> Result<(), Error> {
+    write_identical_files(&server);
+    assert_ne!(
+        etag_of(&server, "first.bin")?,
+        etag_of(&server, "second.bin")?
+    );
+
+    Ok(())
+}
+
+/// With --content-etag, identical small files get identical ETags.
+#[rstest]
+fn content_etag_identical_files(
+    #[with(&["--content-etag", "1KiB"])] server: TestServer,
+) -> Result<(), Error> {
+    write_identical_files(&server);
+    let etag = etag_of(&server, "first.bin")?;
+    assert_eq!(etag, etag_of(&server, "second.bin")?);
+
+    // Conditional requests use the content ETag as well
+    let status = Client::new()
+        .get(server.url().join("second.bin")?)
+        .header("If-None-Match", &etag)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+    let status = Client::new()
+        .get(server.url().join("test.txt")?)
+        .header("If-None-Match", &etag)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Files over the threshold keep the cheap ETag.
+#[rstest]
+fn content_etag_size_threshold(
+    #[with(&["--content-etag", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    write_identical_files(&server);
+    assert_ne!(
+        etag_of(&server, "first.bin")?,
+        etag_of(&server, "second.bin")?
+    );
+
+    Ok(())
+}
+
+/// Conditional requests still work for files over the threshold.
+#[rstest]
+fn content_etag_conditional_request_big_file(
+    #[with(&["--content-etag", "4"])] server: TestServer,
+) -> Result<(), Error> {
+    let etag = etag_of(&server, "test.txt")?;
+    let status = Client::new()
+        .get(server.url().join("test.txt")?)
+        .header("If-None-Match", &etag)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+    Ok(())
+}
+
+/// With --etag-strength weak, ETags derived from the modification date are weak, while
+/// content ETags stay strong.
+#[rstest]
+#[case(server(&[] as &[&str]), false)]
+#[case(server(&["--etag-strength", "strong"]), false)]
+#[case(server(&["--etag-strength", "weak"]), true)]
+fn etag_strength(#[case] server: TestServer, #[case] weak: bool) -> Result<(), Error> {
+    assert_eq!(etag_of(&server, "test.txt")?.starts_with("W/"), weak);
+
+    Ok(())
+}
+
+#[rstest]
+fn content_etag_stays_strong(
+    #[with(&["--etag-strength", "weak", "--content-etag", "1KiB"])] server: TestServer,
+) -> Result<(), Error> {
+    assert!(!etag_of(&server, "test.txt")?.starts_with("W/"));
+
+    Ok(())
+}
+
+/// A range is only served if If-Range strongly matches the ETag.
+#[rstest]
+#[case(&["--etag-strength", "weak"], StatusCode::OK)]
+#[case(&["--content-etag", "1KiB"], StatusCode::PARTIAL_CONTENT)]
+fn etag_if_range(#[case] args: &[&str], #[case] expected: StatusCode) -> Result<(), Error> {
+    let server = server(args);
+    let etag = etag_of(&server, "test.txt")?;
+
+    let status = Client::new()
+        .get(server.url().join("test.txt")?)
+        .header("Range", "bytes=0-1")
+        .header("If-Range", &etag)
+        .send()?
+        .status();
+    assert_eq!(status, expected);
+
+    Ok(())
+}
+
+/// Files are validated by their ETag and modification date, both of which change with the file.
+#[rstest]
+fn conditional_requests(server: TestServer) -> Result<(), Error> {
+    let url = server.url().join("test.txt")?;
+    let resp = reqwest::blocking::get(url.clone())?.error_for_status()?;
+    let etag = resp.headers()["etag"].to_str()?.to_string();
+    let last_modified = resp.headers()["last-modified"].to_str()?.to_string();
+
+    let status = Client::new()
+        .get(url.clone())
+        .header("If-None-Match", &etag)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    let status = Client::new()
+        .get(url.clone())
+        .header("If-Modified-Since", &last_modified)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+    let mut file = File::create(server.path().join("test.txt"))?;
+    file.write_all(b"Changed content")?;
+    file.set_modified(SystemTime::now() + Duration::from_secs(60))?;
+
+    let resp = Client::new()
+        .get(url.clone())
+        .header("If-None-Match", &etag)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_ne!(resp.headers()["etag"].to_str()?, etag);
+    assert_eq!(resp.text()?, "Changed content");
+    let status = Client::new()
+        .get(url)
+        .header("If-Modified-Since", &last_modified)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
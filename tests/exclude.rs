@@ -0,0 +1,80 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::document::Document;
+use select::node::Node;
+use std::path::PathBuf;
+
+/// Names of the entries listed at `path`
+fn listed_names(server: &TestServer, path: &str) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(|x: &Node| x.attr("class") == Some("file") || x.attr("class") == Some("directory"))
+        .map(|x| x.text())
+        .collect())
+}
+
+/// Paths of the files of the tarball of the served directory, under its top-level directory
+fn archived_paths(server: &TestServer) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(server.url().join("?download=tar")?)?
+        .error_for_status()?
+        .bytes()?;
+    let mut archive = tar::Archive::new(body.as_ref());
+    archive
+        .entries()?
+        .map(|entry| {
+            let path = entry?.path()?.into_owned();
+            let path = path.iter().skip(1).collect::<PathBuf>();
+            Ok(path.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
+}
+
+#[rstest]
+fn excluded_files_are_hidden(
+    #[with(&["-r", "--exclude", "*.mkv"])] server: TestServer,
+) -> Result<(), Error> {
+    let names = listed_names(&server, "/")?;
+    assert!(names.contains(&"test.txt".to_string()));
+    assert!(!names.contains(&"test.mkv".to_string()));
+    assert!(!listed_names(&server, "/dira/")?.contains(&"test.mkv".to_string()));
+
+    for path in ["test.mkv", "dira/test.mkv"] {
+        let status = reqwest::blocking::get(server.url().join(path)?)?.status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let paths = archived_paths(&server)?;
+    assert!(paths.contains(&"test.txt".to_string()));
+    assert!(paths.contains(&"dira/test.txt".to_string()));
+    assert!(!paths.iter().any(|path| path.ends_with(".mkv")));
+
+    Ok(())
+}
+
+#[rstest]
+fn excluded_directories_are_hidden(
+    #[with(&["-r", "--exclude", "dira/", "--exclude", "dirb/*.txt"])] server: TestServer,
+) -> Result<(), Error> {
+    let names = listed_names(&server, "/")?;
+    assert!(!names.contains(&"dira/".to_string()));
+    assert!(names.contains(&"dirb/".to_string()));
+    let names = listed_names(&server, "/dirb/")?;
+    assert!(!names.contains(&"test.txt".to_string()));
+    assert!(names.contains(&"test.mkv".to_string()));
+
+    for path in ["dira/", "dira/test.txt", "dirb/test.txt"] {
+        let status = reqwest::blocking::get(server.url().join(path)?)?.status();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    let paths = archived_paths(&server)?;
+    assert!(!paths.iter().any(|path| path.starts_with("dira")));
+    assert!(!paths.contains(&"dirb/test.txt".to_string()));
+    assert!(paths.contains(&"dirb/test.mkv".to_string()));
+
+    Ok(())
+}
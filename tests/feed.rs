@@ -0,0 +1,91 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DIRECTORIES, FILES, HIDDEN_FILES};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::StatusCode;
+use rstest::rstest;
+
+/// Parse the feed, failing on malformed XML, and return the titles of its entries.
+fn entry_titles(feed: &str) -> Result<Vec<String>, Error> {
+    let mut reader = Reader::from_str(feed);
+    let mut titles = Vec::new();
+    let mut in_entry = false;
+    let mut in_title = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.name().as_ref() == b"entry" => in_entry = true,
+            Event::End(e) if e.name().as_ref() == b"entry" => in_entry = false,
+            Event::Start(e) if e.name().as_ref() == b"title" => in_title = in_entry,
+            Event::End(e) if e.name().as_ref() == b"title" => in_title = false,
+            Event::Text(text) if in_title => titles.push(text.unescape()?.into_owned()),
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(titles)
+}
+
+/// The feed is not served by default.
+#[rstest]
+fn feed_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let status = reqwest::blocking::get(server.url().join("feed.xml")?)?.status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+/// The feed is valid XML containing the files of the served directory.
+#[rstest]
+fn feed_lists_files(#[with(&["--feed", "1000"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("feed.xml")?)?.error_for_status()?;
+    assert_eq!(
+        resp.headers()["content-type"],
+        "application/atom+xml; charset=utf-8"
+    );
+    let titles = entry_titles(&resp.text()?)?;
+
+    for &file in FILES {
+        assert!(titles.iter().any(|title| title == file));
+        for &dir in DIRECTORIES {
+            assert!(titles.iter().any(|title| *title == format!("{dir}{file}")));
+        }
+    }
+    for &file in HIDDEN_FILES {
+        assert!(titles.iter().all(|title| !title.contains(file)));
+    }
+
+    Ok(())
+}
+
+/// The number of entries in the feed is limited.
+#[rstest]
+#[case(server(&["--feed"]), 20)]
+#[case(server(&["--feed", "3"]), 3)]
+fn feed_entries_limited(#[case] server: TestServer, #[case] expected: usize) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join("feed.xml")?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(entry_titles(&body)?.len(), expected);
+
+    Ok(())
+}
+
+/// Hidden files are included in the feed with --hidden.
+#[rstest]
+fn feed_lists_hidden_files(
+    #[with(&["--feed", "1000", "--hidden"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join("feed.xml")?)?
+        .error_for_status()?
+        .text()?;
+    let titles = entry_titles(&body)?;
+
+    for &file in HIDDEN_FILES {
+        assert!(titles.iter().any(|title| title == file));
+    }
+
+    Ok(())
+}
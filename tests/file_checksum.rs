@@ -0,0 +1,78 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, RANGE};
+use reqwest::StatusCode;
+use rstest::rstest;
+
+/// The checksum of a file is served instead of its content with `?checksum=`.
+#[rstest]
+#[case("md5", "5d41402abc4b2a76b9719d911017c592")]
+#[case("SHA1", "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d")]
+#[case(
+    "sha256",
+    "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+)]
+fn file_checksum_is_served(
+    #[with(&["--checksums"])] server: TestServer,
+    #[case] algorithm: &str,
+    #[case] expected: &str,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("hello.txt"), "hello")?;
+
+    // Range requests still get the checksum of the whole file
+    let resp = Client::new()
+        .get(
+            server
+                .url()
+                .join(&format!("hello.txt?checksum={algorithm}"))?,
+        )
+        .header(RANGE, "bytes=0-1")
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers()[CONTENT_TYPE]
+        .to_str()?
+        .starts_with("text/plain"));
+    assert_eq!(resp.text()?, expected);
+
+    Ok(())
+}
+
+/// Unsupported algorithms are rejected.
+#[rstest]
+fn file_checksum_rejects_unknown_algorithm(
+    #[with(&["--checksums"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("hello.txt"), "hello")?;
+
+    let resp = reqwest::blocking::get(server.url().join("hello.txt?checksum=crc32")?)?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+/// Checksums are refused for files which can't be downloaded.
+#[rstest]
+fn file_checksum_hides_hidden_files(
+    #[with(&["--checksums"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join(".hidden.txt"), "hello")?;
+
+    let resp = reqwest::blocking::get(server.url().join(".hidden.txt?checksum=md5")?)?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+/// Files are served as usual without `--checksums`.
+#[rstest]
+fn file_checksum_is_opt_in(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("hello.txt"), "hello")?;
+
+    let resp =
+        reqwest::blocking::get(server.url().join("hello.txt?checksum=md5")?)?.error_for_status()?;
+    assert_eq!(resp.text()?, "hello");
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DIRECTORIES};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Class, Name, Predicate};
+
+/// Returns the names of the entries of the listing at `url`
+fn listed_entries(url: reqwest::Url) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(Name("a").and(Class("file").or(Class("directory"))))
+        .map(|link| link.text().trim_end_matches('/').to_string())
+        .collect())
+}
+
+#[rstest]
+fn listing_can_be_filtered_by_file_type(server: TestServer) -> Result<(), Error> {
+    let mut entries = listed_entries(server.url().join("?type=video")?)?;
+    entries.sort();
+    assert_eq!(entries, vec!["test.mkv", "⎙.mp4"]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case("?type=text", "test.txt")]
+#[case("?type=image", "image.PNG")]
+#[case("?type=archive", "files.zip")]
+fn file_types_are_guessed_from_extensions(
+    server: TestServer,
+    #[case] query: &str,
+    #[case] expected: &str,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join("image.PNG"), "")?;
+    std::fs::write(server.path().join("files.zip"), "")?;
+
+    let entries = listed_entries(server.url().join(query)?)?;
+    assert!(entries.contains(&expected.to_string()), "{entries:?}");
+    assert!(!entries.contains(&"test.mkv".to_string()), "{entries:?}");
+    for dir in DIRECTORIES {
+        assert!(!entries.contains(&dir.trim_end_matches('/').to_string()));
+    }
+
+    Ok(())
+}
+
+/// An empty filter shows all entries, and the selected filter is kept by the dropdown.
+#[rstest]
+#[case("?type=", "")]
+#[case("?type=audio", "audio")]
+fn file_type_dropdown_shows_selected_filter(
+    server: TestServer,
+    #[case] query: &str,
+    #[case] selected: &str,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join(query)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let selected_option = parsed
+        .find(
+            Name("select")
+                .and(Attr("name", "type"))
+                .descendant(Name("option")),
+        )
+        .find(|option| option.attr("selected").is_some())
+        .and_then(|option| option.attr("value").map(str::to_string));
+    assert_eq!(selected_option.as_deref(), Some(selected));
+
+    if query == "?type=" {
+        let entries = listed_entries(server.url().join(query)?)?;
+        assert!(entries.contains(&"test.mkv".to_string()));
+        assert!(entries.contains(&"dira".to_string()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "git")]
+
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use fixtures::{port, wait_for_port, Error, TestServer};
+use git2::{Repository, Signature};
+use rstest::rstest;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Commits the current content of `file_name` in `repo`, returning the id of the commit
+fn commit(repo: &Repository, file_name: &str, message: &str) -> Result<git2::Oid, Error> {
+    let mut index = repo.index()?;
+    index.add_path(Path::new(file_name))?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let signature = Signature::now("miniserve", "miniserve@example.com")?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents = parent.iter().collect::<Vec<_>>();
+    Ok(repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?)
+}
+
+fn git_server(tmpdir: TempDir, git_ref: &str) -> Result<TestServer, Error> {
+    let port = port();
+    let child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--git-ref", git_ref])
+        .stdout(Stdio::null())
+        .spawn()?;
+    wait_for_port(port);
+    Ok(TestServer::new(port, tmpdir, child, false))
+}
+
+/// Files are served as of the given ref, not as in the working directory.
+#[rstest]
+fn git_ref_serves_older_content() -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    let repo = Repository::init(tmpdir.path())?;
+
+    std::fs::write(tmpdir.path().join("notes.txt"), "first version")?;
+    let first = commit(&repo, "notes.txt", "First")?;
+    std::fs::write(tmpdir.path().join("notes.txt"), "second version")?;
+    commit(&repo, "notes.txt", "Second")?;
+    std::fs::write(tmpdir.path().join("untracked.txt"), "not committed")?;
+
+    let server = git_server(tmpdir, &first.to_string())?;
+
+    let body = reqwest::blocking::get(server.url().join("notes.txt")?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(body, "first version");
+
+    let listing = reqwest::blocking::get(server.url())?
+        .error_for_status()?
+        .text()?;
+    assert!(!listing.contains("untracked.txt"));
+
+    Ok(())
+}
+
+/// Branch names are resolved like commit ids.
+#[rstest]
+fn git_ref_resolves_branches() -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    let repo = Repository::init(tmpdir.path())?;
+
+    std::fs::write(tmpdir.path().join("notes.txt"), "on the branch")?;
+    let commit_id = commit(&repo, "notes.txt", "First")?;
+    repo.branch("published", &repo.find_commit(commit_id)?, false)?;
+    std::fs::write(tmpdir.path().join("notes.txt"), "work in progress")?;
+
+    let server = git_server(tmpdir, "published")?;
+
+    let body = reqwest::blocking::get(server.url().join("notes.txt")?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(body, "on the branch");
+
+    Ok(())
+}
+
+/// An unknown ref is an error on startup.
+#[rstest]
+fn git_ref_must_exist(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    Repository::init(tmpdir.path())?;
+
+    Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["--git-ref", "does-not-exist"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Couldn't resolve git ref \"does-not-exist\"",
+        ));
+
+    Ok(())
+}
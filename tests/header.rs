@@ -1,6 +1,6 @@
 mod fixtures;
 
-use fixtures::{server, Error};
+use fixtures::{server, Error, TestServer};
 use rstest::rstest;
 
 #[rstest(headers,
@@ -20,3 +20,36 @@ fn custom_header_set(headers: Vec<String>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// HSTS is never advertised over plain HTTP.
+#[rstest]
+fn hsts_header_not_set_without_tls(
+    #[with(&["--hsts", "--hsts-max-age", "600"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url())?;
+    assert!(resp.headers().get("strict-transport-security").is_none());
+
+    Ok(())
+}
+
+/// The Server header can be set to a custom value.
+#[rstest]
+fn custom_server_header_set(
+    #[with(&["--server-header", "my-server/1.0"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url())?;
+    assert_eq!(resp.headers().get("server").unwrap(), "my-server/1.0");
+
+    Ok(())
+}
+
+/// The Server header can be omitted, even if set through --header.
+#[rstest]
+fn server_header_omitted(
+    #[with(&["--no-server-header", "--header", "Server: leaked"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url())?;
+    assert!(resp.headers().get("server").is_none());
+
+    Ok(())
+}
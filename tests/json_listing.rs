@@ -0,0 +1,244 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DIRECTORIES, FILES};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use rstest::rstest;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::File;
+
+/// Fetch one page of the JSON listing, returning the names in it and the next cursor.
+fn fetch_page(server: &TestServer, query: &str) -> Result<(Vec<String>, Option<String>), Error> {
+    let resp = Client::new()
+        .get(server.url().join(&format!("?json&{query}"))?)
+        .send()?
+        .error_for_status()?;
+    let next_cursor = resp
+        .headers()
+        .get("x-next-cursor")
+        .map(|cursor| cursor.to_str().unwrap().to_string());
+    let entries: Vec<Value> = serde_json::from_str(&resp.text()?)?;
+    let names = entries
+        .iter()
+        .map(|entry| entry["name"].as_str().unwrap().to_string())
+        .collect();
+
+    Ok((names, next_cursor))
+}
+
+/// Paginating with the cursor neither skips nor repeats entries, even when the directory
+/// changes in between.
+#[rstest]
+#[case("sort=name&order=asc")]
+#[case("sort=name&order=desc")]
+#[case("sort=size&order=asc&group=first")]
+#[case("sort=date&order=desc")]
+fn json_listing_cursor_pagination(server: TestServer, #[case] sort: &str) -> Result<(), Error> {
+    let (all, next_cursor) = fetch_page(&server, sort)?;
+    assert!(next_cursor.is_none());
+    assert_eq!(all.len(), FILES.len() + DIRECTORIES.len() + 1);
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let query = match &cursor {
+            Some(cursor) => format!("{sort}&limit=3&cursor={cursor}"),
+            None => format!("{sort}&limit=3"),
+        };
+        let (names, next_cursor) = fetch_page(&server, &query)?;
+        assert!(names.len() <= 3);
+        seen.extend(names);
+
+        if cursor.is_none() {
+            // Add files on both sides of the cursor, these may or may not be listed
+            File::create(server.path().join("aaa-new.txt"))?;
+            File::create(server.path().join("zzz-new.txt"))?;
+        }
+
+        match next_cursor {
+            Some(next_cursor) => cursor = Some(next_cursor),
+            None => break,
+        }
+    }
+
+    let unique = seen.iter().collect::<HashSet<_>>();
+    assert_eq!(unique.len(), seen.len(), "entries were repeated");
+    for name in &all {
+        assert!(seen.contains(name), "{name} was skipped");
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn json_listing_invalid_cursor(server: TestServer) -> Result<(), Error> {
+    let status = Client::new()
+        .get(server.url().join("?json&limit=3&cursor=nope")?)
+        .send()?
+        .status();
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+/// The JSON Lines listing has every entry on its own line.
+#[rstest]
+#[case("?ndjson", None)]
+#[case("", Some("application/x-ndjson"))]
+fn ndjson_listing_streams_entries(
+    server: TestServer,
+    #[case] query: &str,
+    #[case] accept: Option<&str>,
+) -> Result<(), Error> {
+    let mut req = Client::new().get(server.url().join(query)?);
+    if let Some(accept) = accept {
+        req = req.header("Accept", accept);
+    }
+    let resp = req.send()?.error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], "application/x-ndjson");
+
+    let entries = resp
+        .text()?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<Value>, _>>()?;
+    let names: HashSet<_> = entries
+        .iter()
+        .map(|entry| entry["name"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(entries.len(), names.len());
+    for &file in FILES {
+        assert!(names.contains(file), "{file} is missing");
+    }
+    for &dir in DIRECTORIES {
+        assert!(names.contains(dir.trim_end_matches('/')));
+    }
+
+    Ok(())
+}
+
+/// Fetch the full JSON listing of `server`, without the modification dates which differ between
+/// fixtures.
+fn fetch_listing(server: &TestServer, path: &str) -> Result<Vec<Value>, Error> {
+    let body = reqwest::blocking::get(server.url().join(&format!("{path}?json"))?)?
+        .error_for_status()?
+        .text()?;
+    let mut entries: Vec<Value> = serde_json::from_str(&body)?;
+    for entry in &mut entries {
+        entry.as_object_mut().unwrap().remove("last_modified");
+    }
+    Ok(entries)
+}
+
+/// Fetching metadata concurrently lists the same entries, in the same order, as doing it serially.
+#[rstest]
+#[case("")]
+#[case("dira/")]
+fn json_listing_concurrency_is_deterministic(#[case] path: &str) -> Result<(), Error> {
+    let serial = server(&["-H", "-l"]);
+    let concurrent = server(&["-H", "-l", "--listing-concurrency", "4"]);
+
+    let expected = fetch_listing(&serial, path)?;
+    assert!(!expected.is_empty());
+    for _ in 0..3 {
+        assert_eq!(fetch_listing(&concurrent, path)?, expected);
+    }
+
+    Ok(())
+}
+
+/// Checksums of files can be requested in JSON listings with `--checksums`.
+#[rstest]
+#[case("?json&checksums=1")]
+#[case("?ndjson&checksums=1")]
+fn json_listing_includes_checksums(
+    #[with(&["--checksums"])] server: TestServer,
+    #[case] query: &str,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join(query)?)?
+        .error_for_status()?
+        .text()?;
+    let entries: Vec<Value> = if query.contains("ndjson") {
+        body.lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?
+    } else {
+        serde_json::from_str(&body)?
+    };
+    assert_eq!(entries.len(), FILES.len() + DIRECTORIES.len() + 1);
+
+    for entry in entries {
+        let name = entry["name"].as_str().unwrap();
+        if entry["is_dir"].as_bool().unwrap() {
+            assert!(entry.get("checksum").is_none(), "{name}");
+        } else {
+            let expected = hex::encode(Sha256::digest(std::fs::read(server.path().join(name))?));
+            assert_eq!(
+                entry["checksum"].as_str(),
+                Some(expected.as_str()),
+                "{name}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Checksums are left out unless requested, and unless enabled.
+#[rstest]
+#[case(server(&["--checksums"]), "?json")]
+#[case(server(&[] as &[&str]), "?json&checksums=1")]
+fn json_listing_omits_checksums(
+    #[case] server: TestServer,
+    #[case] query: &str,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join(query)?)?
+        .error_for_status()?
+        .text()?;
+    let entries: Vec<Value> = serde_json::from_str(&body)?;
+    assert!(entries.iter().all(|entry| entry.get("checksum").is_none()));
+
+    Ok(())
+}
+
+/// Directories are listed as JSON with `?json` or `Accept: application/json`.
+#[rstest]
+#[case("?json", "*/*")]
+#[case("", "application/json")]
+fn json_listing_is_negotiated(
+    server: TestServer,
+    #[case] query: &str,
+    #[case] accept: &str,
+) -> Result<(), Error> {
+    let body = Client::new()
+        .get(server.url().join(query)?)
+        .header("Accept", accept)
+        .send()?
+        .error_for_status()?
+        .text()?;
+    let entries: Vec<Value> = serde_json::from_str(&body)?;
+
+    for &file in FILES.iter().filter(|file| !file.starts_with('.')) {
+        let entry = entries
+            .iter()
+            .find(|entry| entry["name"] == file)
+            .unwrap_or_else(|| panic!("{file} is not listed"));
+        assert_eq!(entry["is_dir"], false);
+        assert!(entry["size"].is_u64());
+        let last_modified = entry["last_modified"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(last_modified).is_ok());
+    }
+    for &dir in DIRECTORIES {
+        let entry = entries
+            .iter()
+            .find(|entry| entry["name"] == dir.trim_end_matches('/'))
+            .unwrap_or_else(|| panic!("{dir} is not listed"));
+        assert_eq!(entry["is_dir"], true);
+        assert!(entry["size"].is_null());
+    }
+
+    Ok(())
+}
@@ -51,11 +51,11 @@ fn can_navigate_into_dirs_and_back(server: TestServer) -> Result<(), Error> {
     let initial_parsed = Document::from_read(initial_body)?;
     for &directory in DIRECTORIES {
         let dir_elem = get_link_from_text(&initial_parsed, directory).expect("Dir not found.");
-        let body = reqwest::blocking::get(&format!("{base_url}{dir_elem}"))?.error_for_status()?;
+        let body = reqwest::blocking::get(format!("{base_url}{dir_elem}"))?.error_for_status()?;
         let parsed = Document::from_read(body)?;
         let back_link =
             get_link_from_text(&parsed, "Parent directory").expect("Back link not found.");
-        let resp = reqwest::blocking::get(&format!("{base_url}{back_link}"))?;
+        let resp = reqwest::blocking::get(format!("{base_url}{back_link}"))?;
 
         // Now check that we can actually get back to the original location we came from using the
         // link.
@@ -147,3 +147,22 @@ fn can_navigate_using_breadcrumbs(
 
     Ok(())
 }
+
+#[rstest]
+/// Breadcrumbs percent-encode the links to ancestors and escape their names.
+fn breadcrumbs_encode_special_characters(server: TestServer) -> Result<(), Error> {
+    let ancestor = "a b&<i>%";
+    std::fs::create_dir_all(server.path().join(ancestor).join("child"))?;
+
+    let nested_url = server.url().join("a%20b&%3Ci%3E%25/child/")?;
+    let body = reqwest::blocking::get(nested_url)?.error_for_status()?;
+    let body = body.text()?;
+    assert!(!body.contains("<i>"));
+
+    let parsed = Document::from_read(body.as_bytes())?;
+    let ancestor_link = get_link_from_text(&parsed, ancestor).expect("Ancestor link not found.");
+    assert_eq!("/a%20b&%3Ci%3E%25/", ancestor_link);
+    assert_eq!(None, get_link_from_text(&parsed, "child"));
+
+    Ok(())
+}
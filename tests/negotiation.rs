@@ -0,0 +1,90 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, VARY};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::Name;
+
+const CSV_SOURCE: &str = "name,size\n\"a, b\",1\nc,2\n";
+
+fn get(
+    server: &TestServer,
+    path: &str,
+    accept: &str,
+) -> Result<reqwest::blocking::Response, Error> {
+    Ok(Client::new()
+        .get(server.url().join(path)?)
+        .header(ACCEPT, accept)
+        .send()?
+        .error_for_status()?)
+}
+
+/// Browsers get a table for CSV files, other clients the file as is.
+#[rstest]
+fn csv_is_negotiated(#[with(&["--negotiate-content"])] server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("data.csv"), CSV_SOURCE)?;
+
+    let resp = get(
+        &server,
+        "data.csv",
+        "text/html,application/xhtml+xml,*/*;q=0.8",
+    )?;
+    assert!(resp.headers()[CONTENT_TYPE]
+        .to_str()?
+        .starts_with("text/html"));
+    assert_eq!(resp.headers()[VARY], "accept");
+    let parsed = Document::from_read(resp)?;
+    let header = parsed
+        .find(Name("th"))
+        .map(|th| th.text())
+        .collect::<Vec<_>>();
+    assert_eq!(header, vec!["name", "size"]);
+    let cells = parsed
+        .find(Name("td"))
+        .map(|td| td.text())
+        .collect::<Vec<_>>();
+    assert_eq!(cells, vec!["a, b", "1", "c", "2"]);
+
+    let resp = get(&server, "data.csv", "text/csv")?;
+    assert_eq!(resp.headers()[VARY], "accept");
+    assert_eq!(resp.text()?, CSV_SOURCE);
+
+    let resp = get(&server, "data.csv?raw=true", "text/html")?;
+    assert_eq!(resp.text()?, CSV_SOURCE);
+
+    Ok(())
+}
+
+/// JSON and Markdown files also have an HTML view.
+#[rstest]
+#[case("data.json", "{\"a\":[1,2]}", "pre")]
+#[case("notes.md", "# Title\n", "h1")]
+fn other_formats_are_negotiated(
+    #[with(&["--negotiate-content"])] server: TestServer,
+    #[case] file_name: &str,
+    #[case] source: &str,
+    #[case] element: &str,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join(file_name), source)?;
+
+    let parsed = Document::from_read(get(&server, file_name, "text/html")?)?;
+    assert!(parsed.find(Name(element)).next().is_some());
+
+    assert_eq!(get(&server, file_name, "*/*")?.text()?, source);
+
+    Ok(())
+}
+
+/// Without the flag, files are always sent as is.
+#[rstest]
+fn csv_is_not_negotiated_by_default(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("data.csv"), CSV_SOURCE)?;
+
+    let resp = get(&server, "data.csv", "text/html")?;
+    assert!(resp.headers().get(VARY).is_none());
+    assert_eq!(resp.text()?, CSV_SOURCE);
+
+    Ok(())
+}
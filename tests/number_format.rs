@@ -0,0 +1,85 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Class, Name, Predicate};
+
+/// Sizes in listings are formatted with the separators of `--number-format`, the exact number
+/// of bytes being shown when hovering them.
+#[rstest]
+#[case(server(None::<&str>), "1.2 MiB", "1234567 bytes")]
+#[case(server(&["--number-format", "comma"]), "1.2 MiB", "1,234,567 bytes")]
+#[case(server(&["--number-format", "dot"]), "1,2 MiB", "1.234.567 bytes")]
+fn sizes_follow_number_format(
+    #[case] server: TestServer,
+    #[case] size: &str,
+    #[case] title: &str,
+) -> Result<(), Error> {
+    let file = std::fs::File::create(server.path().join("big.bin"))?;
+    file.set_len(1_234_567)?;
+
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let row = parsed
+        .find(Name("tr"))
+        .find(|row| {
+            row.find(Name("a").and(Attr("href", "/big.bin")))
+                .next()
+                .is_some()
+        })
+        .unwrap();
+    let size_cell = row.find(Class("size-cell")).next().unwrap();
+    assert_eq!(size_cell.text().trim(), size);
+    assert_eq!(size_cell.attr("title"), Some(title));
+
+    Ok(())
+}
+
+/// Files get their size in the listing, directories a dash
+#[rstest]
+fn sizes_are_shown_for_files(server: TestServer) -> Result<(), Error> {
+    std::fs::File::create(server.path().join("empty.txt"))?;
+    let mkv_size = std::fs::metadata(server.path().join("test.mkv"))?.len();
+
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let size_of = |href: &str| {
+        let row = parsed
+            .find(Name("tr"))
+            .find(|row| row.find(Name("a").and(Attr("href", href))).next().is_some())
+            .unwrap();
+        let size_cell = row.find(Class("size-cell")).next().unwrap();
+        size_cell.text().trim().to_string()
+    };
+    assert_eq!(size_of("/test.mkv"), format!("{mkv_size} B"));
+    assert_eq!(size_of("/empty.txt"), "0 B");
+    assert_eq!(size_of("/dira/"), "-");
+
+    Ok(())
+}
+
+/// The footer sums up the files of the listed directory, without descending into directories
+#[rstest]
+fn footer_shows_total_size(server: TestServer) -> Result<(), Error> {
+    for name in ["big.bin", "bigger.bin"] {
+        let file = std::fs::File::create(server.path().join(name))?;
+        file.set_len(1024 * 1024)?;
+    }
+    let visible_files = std::fs::read_dir(server.path())?
+        .filter_map(Result::ok)
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+        .count();
+
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let summary = parsed.find(Class("dir-summary")).next().unwrap();
+    // The other files are far too small to round the total up
+    assert_eq!(
+        summary.text(),
+        format!("{visible_files} files, 2.0 MiB in total")
+    );
+
+    Ok(())
+}
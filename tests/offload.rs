@@ -0,0 +1,45 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+
+#[rstest]
+fn files_are_sent_by_default(server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("test.txt")?)?.error_for_status()?;
+    assert!(resp.headers().get("x-accel-redirect").is_none());
+    assert!(resp.headers().get("x-sendfile").is_none());
+    assert_eq!(resp.text()?, "Test Hello Yes");
+
+    Ok(())
+}
+
+#[rstest]
+fn x_accel_redirect(
+    #[with(&["--x-accel-redirect", "/internal/"])] server: TestServer,
+) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("dira/test.txt")?)?.error_for_status()?;
+    assert_eq!(
+        resp.headers()["x-accel-redirect"],
+        "/internal/dira/test.txt"
+    );
+    assert_eq!(resp.text()?, "");
+
+    // Directory listings are still rendered by miniserve
+    let resp = reqwest::blocking::get(server.url())?.error_for_status()?;
+    assert!(resp.headers().get("x-accel-redirect").is_none());
+
+    Ok(())
+}
+
+#[rstest]
+fn x_sendfile(#[with(&["--x-sendfile"])] server: TestServer) -> Result<(), Error> {
+    let resp = reqwest::blocking::get(server.url().join("test.txt")?)?.error_for_status()?;
+    let expected = server.path().join("test.txt").canonicalize()?;
+    assert_eq!(
+        resp.headers()["x-sendfile"].to_str()?,
+        expected.to_str().unwrap()
+    );
+    assert_eq!(resp.text()?, "");
+
+    Ok(())
+}
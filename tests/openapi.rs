@@ -0,0 +1,67 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use reqwest::StatusCode;
+use rstest::rstest;
+use serde_json::Value;
+
+fn fetch_spec(server: &TestServer) -> Result<Value, Error> {
+    let resp = reqwest::blocking::get(server.url().join("openapi.json")?)?.error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], "application/json");
+    Ok(serde_json::from_str(&resp.text()?)?)
+}
+
+/// Names of the query parameters of the listing route
+fn listing_parameters(spec: &Value) -> Vec<&str> {
+    spec["paths"]["/{path}"]["get"]["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|param| param["name"].as_str().unwrap())
+        .collect()
+}
+
+#[rstest]
+fn openapi_is_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let status = reqwest::blocking::get(server.url().join("openapi.json")?)?.status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[rstest]
+fn openapi_describes_listing(#[with(&["--openapi"])] server: TestServer) -> Result<(), Error> {
+    let spec = fetch_spec(&server)?;
+
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    let parameters = listing_parameters(&spec);
+    for param in ["sort", "order", "json", "limit", "cursor", "ndjson"] {
+        assert!(parameters.contains(&param), "{param} is missing");
+    }
+    // Disabled features are not described
+    assert!(!parameters.contains(&"download"));
+    assert!(spec["paths"].get("/upload").is_none());
+
+    Ok(())
+}
+
+#[rstest]
+fn openapi_describes_enabled_features(
+    #[with(&["--openapi", "-r", "-z", "-u"])] server: TestServer,
+) -> Result<(), Error> {
+    let spec = fetch_spec(&server)?;
+
+    let download = spec["paths"]["/{path}"]["get"]["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|param| param["name"] == "download")
+        .expect("download parameter is missing");
+    assert_eq!(
+        download["schema"]["enum"],
+        serde_json::json!(["tar", "zip"])
+    );
+    assert!(spec["paths"]["/upload"]["post"].is_object());
+
+    Ok(())
+}
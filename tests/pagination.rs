@@ -0,0 +1,112 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Class, Name, Predicate};
+
+/// Names of the files on a page of a listing, and links to the previous and next pages
+struct ListedPage {
+    files: Vec<String>,
+    previous: Option<String>,
+    next: Option<String>,
+}
+
+/// Returns the page of the listing at `url`
+fn listed_page(url: reqwest::Url) -> Result<ListedPage, Error> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let files = parsed
+        .find(Name("a").and(Class("file")))
+        .map(|link| link.text())
+        .collect();
+    let link = |class| {
+        parsed
+            .find(Name("a").and(Class(class)))
+            .next()
+            .and_then(|link| link.attr("href").map(str::to_string))
+    };
+    Ok(ListedPage {
+        files,
+        previous: link("previous"),
+        next: link("next"),
+    })
+}
+
+/// Creates 25 files named from `page-00.txt` to `page-24.txt` in a fresh directory
+fn create_files(server: &TestServer) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("pages"))?;
+    for i in 0..25 {
+        std::fs::write(server.path().join(format!("pages/page-{i:02}.txt")), "")?;
+    }
+    Ok(())
+}
+
+#[rstest]
+fn listing_is_paginated(server: TestServer) -> Result<(), Error> {
+    create_files(&server)?;
+
+    let page = listed_page(
+        server
+            .url()
+            .join("pages/?page=2&per_page=10&sort=name&order=asc")?,
+    )?;
+    let expected = (10..20)
+        .map(|i| format!("page-{i}.txt"))
+        .collect::<Vec<_>>();
+    assert_eq!(page.files, expected);
+    assert_eq!(
+        page.previous.as_deref(),
+        Some("?page=1&per_page=10&sort=name&order=asc")
+    );
+    assert_eq!(
+        page.next.as_deref(),
+        Some("?page=3&per_page=10&sort=name&order=asc")
+    );
+
+    let page = listed_page(server.url().join("pages/?page=3&per_page=10")?)?;
+    assert_eq!(page.files.len(), 5);
+    assert_eq!(page.next, None);
+
+    Ok(())
+}
+
+/// Pages before the first and after the last one are empty but valid listings.
+#[rstest]
+#[case("pages/?page=0&per_page=10", None, Some("?page=1&per_page=10"))]
+#[case("pages/?page=4&per_page=10", Some("?page=3&per_page=10"), None)]
+fn out_of_range_pages_are_empty(
+    server: TestServer,
+    #[case] path: &str,
+    #[case] expected_previous: Option<&str>,
+    #[case] expected_next: Option<&str>,
+) -> Result<(), Error> {
+    create_files(&server)?;
+
+    let body = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert_eq!(parsed.find(Name("a").and(Class("file"))).count(), 0);
+    let empty = parsed.find(Class("empty")).next().unwrap();
+    assert_eq!(empty.text().trim(), "No entries on this page");
+    let link = |class| {
+        parsed
+            .find(Name("a").and(Class(class)))
+            .next()
+            .and_then(|link| link.attr("href"))
+    };
+    assert_eq!(link("previous"), expected_previous);
+    assert_eq!(link("next"), expected_next);
+
+    Ok(())
+}
+
+/// Listings fitting in one page have no links to other pages.
+#[rstest]
+fn small_listings_are_not_paginated(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("class", "pagination")).next().is_none());
+
+    Ok(())
+}
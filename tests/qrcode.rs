@@ -89,6 +89,30 @@ fn qrcode_shown_in_tty_when_enabled(tmpdir: TempDir, port: u16) -> Result<(), Er
     Ok(())
 }
 
+#[rstest]
+// Disabled for Windows because `fake_tty` does not currently support it.
+#[cfg(not(windows))]
+fn qrcode_uses_https_with_tls(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut template = Command::cargo_bin("miniserve")?;
+    template
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-q")
+        .args(["--tls-cert", "tests/data/cert.pem"])
+        .args(["--tls-key", "tests/data/key_pkcs8.pem"])
+        .arg(tmpdir.path());
+
+    let output = run_in_faketty_kill_and_get_stdout(&template)?;
+
+    let qr_urls = output
+        .lines()
+        .filter(|line| line.starts_with("QR code for "))
+        .collect::<Vec<_>>();
+    assert!(!qr_urls.is_empty());
+    assert!(qr_urls.iter().all(|line| line.contains("https://")));
+    Ok(())
+}
+
 #[rstest]
 fn qrcode_hidden_in_non_tty_when_enabled(tmpdir: TempDir, port: u16) -> Result<(), Error> {
     let mut child = Command::cargo_bin("miniserve")?
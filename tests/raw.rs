@@ -104,3 +104,26 @@ fn raw_mode_links_to_directories_end_with_raw_true(
 
     Ok(())
 }
+
+/// With --minimal, listings have the file links but no CSS or JavaScript
+#[rstest]
+#[case("")]
+#[case("dira/")]
+fn minimal_mode_has_no_style_or_script(
+    #[case] dir: &str,
+    #[with(&["--minimal"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(format!("{}{}", server.url(), dir))?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    assert!(parsed.find(Name("a")).next().is_some());
+    if dir.is_empty() {
+        assert!(parsed.find(Name("a")).any(|a| a.text() == "test.txt"));
+    }
+    assert_eq!(parsed.find(Name("style")).count(), 0);
+    assert_eq!(parsed.find(Name("script")).count(), 0);
+    assert_eq!(parsed.find(Name("link")).count(), 0);
+    assert_eq!(parsed.find(Name("svg")).count(), 0);
+
+    Ok(())
+}
@@ -2,7 +2,7 @@ mod fixtures;
 
 use fixtures::{server, Error, TestServer, DIRECTORIES, FILES};
 use rstest::rstest;
-use select::predicate::Attr;
+use select::predicate::{Attr, Name};
 use select::{document::Document, node::Node};
 use std::fs::{remove_file, File};
 use std::io::Write;
@@ -51,7 +51,7 @@ fn show_root_readme_contents(
     for &file in FILES {
         assert!(parsed.find(|x: &Node| x.text() == file).next().is_some());
     }
-    // ...in addition to the readme contents below the file listing.
+    // ...in addition to the readme contents above the file listing.
     assert_readme_contents(&parsed, readme_name);
     remove_file(readme_path).unwrap();
     Ok(())
@@ -83,7 +83,7 @@ fn show_nested_readme_contents(
         for &file in FILES {
             assert!(parsed.find(|x: &Node| x.text() == file).next().is_some());
         }
-        // ...in addition to the readme contents below the file listing.
+        // ...in addition to the readme contents above the file listing.
         assert_readme_contents(&parsed, readme_name);
         remove_file(readme_path).unwrap();
     }
@@ -124,4 +124,55 @@ fn assert_readme_contents(parsed_dom: &Document, filename: &str) {
         .text()
         .trim()
         .contains(&format!("Contents of {filename}")));
+
+    // The readme comes before the file listing
+    let readme = parsed_dom.find(Attr("id", "readme")).next().unwrap();
+    let table = parsed_dom.find(Name("table")).next().unwrap();
+    assert!(readme.index() < table.index());
+}
+
+/// Markdown readmes are rendered as HTML, leaving out raw HTML
+#[rstest]
+fn readme_markdown_is_rendered(#[with(&["--readme"])] server: TestServer) -> Result<(), Error> {
+    std::fs::write(
+        server.path().join("dira/README.md"),
+        "# Shared folder\n\n<script>alert('readme')</script>\n\n[link](javascript:alert('link'))\n",
+    )?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/")?)?
+        .error_for_status()?
+        .text()?;
+    let parsed = Document::from_read(body.as_bytes())?;
+    let contents = parsed.find(Attr("id", "readme-contents")).next().unwrap();
+    assert_eq!(
+        contents.find(Name("h1")).next().unwrap().text(),
+        "Shared folder"
+    );
+    assert!(contents.find(Name("script")).next().is_none());
+    assert!(!body.contains("alert("));
+    assert!(contents
+        .find(Name("a"))
+        .all(|link| !link.attr("href").unwrap_or_default().contains("javascript")));
+
+    Ok(())
+}
+
+/// Plain text readmes are escaped
+#[rstest]
+fn readme_text_is_escaped(#[with(&["--readme"])] server: TestServer) -> Result<(), Error> {
+    std::fs::write(
+        server.path().join("dira/README.txt"),
+        "<script>alert('readme')</script>",
+    )?;
+
+    let body = reqwest::blocking::get(server.url().join("dira/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let contents = parsed.find(Attr("id", "readme-contents")).next().unwrap();
+    assert!(contents.find(Name("script")).next().is_none());
+    assert_eq!(
+        contents.find(Name("pre")).next().unwrap().text(),
+        "<script>alert('readme')</script>"
+    );
+
+    Ok(())
 }
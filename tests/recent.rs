@@ -0,0 +1,81 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DEEPLY_NESTED_FILE, DIRECTORIES};
+use reqwest::blocking::{multipart, Client};
+use reqwest::StatusCode;
+use rstest::rstest;
+use select::{document::Document, predicate::Name};
+
+/// Returns the links of the /recent page, newest first
+fn recent_links(server: &TestServer) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(server.url().join("recent")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(Name("tbody"))
+        .flat_map(|tbody| tbody.find(Name("a")))
+        .filter_map(|a| a.attr("href").map(str::to_string))
+        .collect())
+}
+
+/// An uploaded file shows at the top of the recent files, wherever it is in the tree.
+#[rstest]
+fn uploaded_file_is_most_recent(
+    #[with(&["-u", "--recent", "3"])] server: TestServer,
+) -> Result<(), Error> {
+    let part = multipart::Part::text("this should be uploaded")
+        .file_name("uploaded.txt")
+        .mime_str("text/plain")?;
+    let form = multipart::Form::new().part("file_to_upload", part);
+    Client::new()
+        .post(
+            server
+                .url()
+                .join(&format!("/upload?path=/{}", DIRECTORIES[0]))?,
+        )
+        .multipart(form)
+        .send()?
+        .error_for_status()?;
+
+    let links = recent_links(&server)?;
+    assert_eq!(links.len(), 3);
+    assert_eq!(links[0], format!("/{}uploaded.txt", DIRECTORIES[0]));
+
+    Ok(())
+}
+
+/// Hidden files are left out of the recent files unless they are shown.
+#[rstest]
+fn recent_files_skip_hidden(#[with(&["--recent"])] server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join(".hidden_recent"), "hidden")?;
+
+    let links = recent_links(&server)?;
+    assert!(!links.is_empty());
+    assert!(links.iter().all(|link| !link.contains(".hidden_recent")));
+
+    Ok(())
+}
+
+/// Files deeper than --max-depth are left out of the recent files.
+#[rstest]
+fn recent_files_follow_max_depth(
+    #[with(&["--recent", "1000", "--max-depth", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    let links = recent_links(&server)?;
+    assert!(links
+        .iter()
+        .any(|link| link.starts_with(&format!("/{}", DIRECTORIES[0]))));
+    assert!(links
+        .iter()
+        .all(|link| *link != format!("/{DEEPLY_NESTED_FILE}")));
+
+    Ok(())
+}
+
+/// The page is only served with `--recent`.
+#[rstest]
+fn recent_files_is_opt_in(server: TestServer) -> Result<(), Error> {
+    let status = reqwest::blocking::get(server.url().join("recent")?)?.status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
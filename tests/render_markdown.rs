@@ -0,0 +1,62 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Name};
+use std::fs::File;
+use std::io::Write;
+
+const MARKDOWN_SOURCE: &str = "# Title\n\nSome *emphasis*.\n\n<script>alert(1)</script>\n";
+
+fn write_markdown_file(server: &TestServer) {
+    let mut file = File::create(server.path().join("notes.md")).unwrap();
+    file.write_all(MARKDOWN_SOURCE.as_bytes()).unwrap();
+}
+
+/// Markdown files are served as-is by default.
+#[rstest]
+fn markdown_served_raw_by_default(server: TestServer) -> Result<(), Error> {
+    write_markdown_file(&server);
+    let body = reqwest::blocking::get(server.url().join("notes.md")?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(body, MARKDOWN_SOURCE);
+
+    Ok(())
+}
+
+/// Markdown files are rendered to sanitized HTML with --render-markdown.
+#[rstest]
+fn markdown_rendered(#[with(&["--render-markdown"])] server: TestServer) -> Result<(), Error> {
+    write_markdown_file(&server);
+    let resp = reqwest::blocking::get(server.url().join("notes.md")?)?.error_for_status()?;
+    assert!(resp.headers()["content-type"]
+        .to_str()?
+        .starts_with("text/html"));
+
+    let parsed = Document::from_read(resp)?;
+    let contents = parsed.find(Attr("id", "readme-contents")).next().unwrap();
+    assert_eq!(contents.find(Name("h1")).next().unwrap().text(), "Title");
+    assert_eq!(contents.find(Name("em")).next().unwrap().text(), "emphasis");
+    assert!(contents.find(Name("script")).next().is_none());
+
+    Ok(())
+}
+
+/// The original Markdown source is served when requested raw.
+#[rstest]
+#[case("notes.md?raw=1")]
+#[case("notes.md?raw=true")]
+fn markdown_raw_override(
+    #[with(&["--render-markdown"])] server: TestServer,
+    #[case] path: &str,
+) -> Result<(), Error> {
+    write_markdown_file(&server);
+    let body = reqwest::blocking::get(server.url().join(path)?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(body, MARKDOWN_SOURCE);
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, FILES};
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Class, Name, Predicate};
+
+/// Returns the names of the entries of the listing at `url`
+fn listed_entries(url: reqwest::Url) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(url)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(Name("a").and(Class("file").or(Class("directory"))))
+        .map(|link| link.text().trim_end_matches('/').to_string())
+        .collect())
+}
+
+#[rstest]
+#[case("?q=mkv")]
+#[case("?q=MKV")]
+fn listing_can_be_searched(server: TestServer, #[case] query: &str) -> Result<(), Error> {
+    let entries = listed_entries(server.url().join(query)?)?;
+    assert_eq!(entries, vec!["test.mkv"]);
+
+    Ok(())
+}
+
+/// An empty search lists all entries.
+#[rstest]
+fn empty_search_lists_everything(server: TestServer) -> Result<(), Error> {
+    let entries = listed_entries(server.url().join("?q=")?)?;
+    assert_eq!(entries, listed_entries(server.url())?);
+    for file in FILES {
+        assert!(entries.contains(&file.to_string()));
+    }
+
+    Ok(())
+}
+
+/// The search is kept by the search box and the column headers.
+#[rstest]
+fn search_is_kept(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join("?q=a+b")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let search_box = parsed
+        .find(
+            Name("input")
+                .and(Attr("name", "q"))
+                .and(Attr("type", "search")),
+        )
+        .next()
+        .unwrap();
+    assert_eq!(search_box.attr("value"), Some("a b"));
+    let size_header = parsed.find(Name("th").and(Class("size"))).next().unwrap();
+    let link = size_header.find(Name("a")).next().unwrap();
+    assert_eq!(link.attr("href"), Some("?sort=size&order=asc&q=a%20b"));
+
+    Ok(())
+}
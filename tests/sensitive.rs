@@ -0,0 +1,103 @@
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use assert_fs::fixture::TempDir;
+use assert_fs::prelude::*;
+use fixtures::{port, server, Error, TestServer};
+use reqwest::StatusCode;
+use rstest::rstest;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+/// Sensitive files are reported at startup
+#[rstest]
+fn sensitive_files_are_reported(port: u16) -> Result<(), Error> {
+    let tmpdir = TempDir::new()?;
+    tmpdir.child(".env").write_str("SECRET=1")?;
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("-p")
+        .arg(port.to_string())
+        .arg("-H")
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Warnings are logged to stdout, before the server starts
+    let warning = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map(|line| line.expect("Error reading stdout"))
+        .take_while(|line| !line.starts_with("Bound to"))
+        .find(|line| line.contains("usually holds secrets"));
+    child.kill()?;
+
+    let warning = warning.expect("No warning about sensitive files");
+    assert!(warning.contains("'.env'"));
+
+    Ok(())
+}
+
+/// Sensitive files are left out of listings and can't be downloaded with `--hide-sensitive`.
+#[rstest]
+#[case(server(&["-H"]), "", true)]
+#[case(server(&["-H", "--hide-sensitive"]), "", false)]
+#[case(server(&["--hide-sensitive"]), "?hidden=true", false)]
+fn sensitive_files_can_be_hidden(
+    #[case] server: TestServer,
+    #[case] query: &str,
+    #[case] visible: bool,
+) -> Result<(), Error> {
+    std::fs::write(server.path().join(".env"), "SECRET=1")?;
+    std::fs::create_dir(server.path().join("project"))?;
+    std::fs::write(server.path().join("project/id_rsa"), "PRIVATE KEY")?;
+
+    let listing = reqwest::blocking::get(server.url().join(query)?)?
+        .error_for_status()?
+        .text()?;
+    assert_eq!(listing.contains(".env"), visible);
+    assert!(listing.contains("test.txt"));
+
+    for path in [".env", "project/id_rsa"] {
+        let status =
+            reqwest::blocking::get(server.url().join(&format!("{path}{query}"))?)?.status();
+        let expected = if visible {
+            StatusCode::OK
+        } else {
+            StatusCode::NOT_FOUND
+        };
+        assert_eq!(status, expected);
+    }
+
+    Ok(())
+}
+
+/// Archives of directories containing sensitive files are refused with `--hide-sensitive`.
+#[rstest]
+fn sensitive_files_prevent_archives(
+    #[with(&["--hide-sensitive", "-r"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("project"))?;
+    std::fs::write(server.path().join("project/.env"), "SECRET=1")?;
+
+    let status = reqwest::blocking::get(server.url().join("project/?download=tar")?)?.status();
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let status = reqwest::blocking::get(server.url().join("dira/?download=tar")?)?.status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
+
+/// Archive plans of directories containing sensitive files are refused with `--hide-sensitive`.
+#[rstest]
+fn sensitive_files_prevent_archive_plans(
+    #[with(&["--hide-sensitive", "-z"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("project"))?;
+    std::fs::write(server.path().join("project/id_rsa"), "PRIVATE KEY")?;
+
+    let status = reqwest::blocking::get(server.url().join("project/?archive=plan")?)?.status();
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let status = reqwest::blocking::get(server.url().join("dira/?archive=plan")?)?.status();
+    assert_eq!(status, StatusCode::OK);
+
+    Ok(())
+}
@@ -3,13 +3,17 @@ mod fixtures;
 use assert_cmd::prelude::*;
 use assert_fs::fixture::TempDir;
 use fixtures::{
-    port, server, server_no_stderr, tmpdir, Error, TestServer, DIRECTORIES, FILES,
+    port, server, server_no_stderr, tmpdir, wait_for_port, Error, TestServer, DIRECTORIES, FILES,
     HIDDEN_DIRECTORIES, HIDDEN_FILES,
 };
 use http::StatusCode;
 use regex::Regex;
 use rstest::rstest;
-use select::{document::Document, node::Node, predicate::Attr};
+use select::{
+    document::Document,
+    node::Node,
+    predicate::{Attr, Name, Predicate},
+};
 use std::process::{Command, Stdio};
 use std::thread::sleep;
 use std::time::Duration;
@@ -122,6 +126,69 @@ fn serves_requests_no_hidden_files_without_flag(server: TestServer) -> Result<()
     Ok(())
 }
 
+/// `?hidden=` shows or leaves out hidden files whatever --show-hidden says, and links keep it
+#[rstest]
+#[case(server(None::<&str>), "", false)]
+#[case(server(None::<&str>), "?hidden=true", true)]
+#[case(server(&["--show-hidden"]), "", true)]
+#[case(server(&["--show-hidden"]), "?hidden=false", false)]
+fn serves_hidden_files_on_request(
+    #[case] server: TestServer,
+    #[case] query: &str,
+    #[case] shown: bool,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join(".private"))?;
+    std::fs::write(server.path().join(".private/.secret"), "secret")?;
+
+    let body = reqwest::blocking::get(server.url().join(query)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let link = parsed
+        .find(Name("a").and(|x: &Node| x.text() == ".private/"))
+        .next()
+        .and_then(|x| x.attr("href"));
+    assert_eq!(link.is_some(), shown);
+
+    if let Some(link) = link {
+        let body = reqwest::blocking::get(server.url().join(link)?)?.error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        let link = parsed
+            .find(Name("a").and(|x: &Node| x.text() == ".secret"))
+            .next()
+            .and_then(|x| x.attr("href"))
+            .unwrap();
+        let content = reqwest::blocking::get(server.url().join(link)?)?
+            .error_for_status()?
+            .text()?;
+        assert_eq!(content, "secret");
+    }
+
+    let status =
+        reqwest::blocking::get(server.url().join(&format!(".private/.secret{query}"))?)?.status();
+    assert_eq!(status.is_success(), shown);
+
+    Ok(())
+}
+
+/// The other listings follow `?hidden=` as well
+#[rstest]
+#[case("?format=csv&recursive=true")]
+#[case("?format=urls&recursive=true")]
+#[case("?ndjson")]
+fn lists_hidden_files_on_request(server: TestServer, #[case] query: &str) -> Result<(), Error> {
+    std::fs::write(server.path().join(".secret"), "secret")?;
+
+    let body = reqwest::blocking::get(server.url().join(query)?)?
+        .error_for_status()?
+        .text()?;
+    assert!(!body.contains(".secret"));
+    let body = reqwest::blocking::get(server.url().join(&format!("{query}&hidden=true"))?)?
+        .error_for_status()?
+        .text()?;
+    assert!(body.contains(".secret"));
+
+    Ok(())
+}
+
 #[rstest]
 #[case(true, false, server(&["--no-symlinks"]))]
 #[case(true, true, server(&["--no-symlinks", "--show-symlink-info"]))]
@@ -162,8 +229,8 @@ fn serves_requests_symlinks(
 
         // If symlinks are deactivated, none should be shown in the listing.
         assert_eq!(node.is_none(), no_symlinks);
-        if node.is_some() && show_symlink_info {
-            assert_eq!(node.unwrap().attr("class").unwrap(), "symlink");
+        if let Some(node) = node.filter(|_| show_symlink_info) {
+            assert_eq!(node.attr("class").unwrap(), "symlink");
         }
 
         // If following symlinks is deactivated, we can just skip this iteration as we assorted
@@ -249,6 +316,27 @@ fn index_fallback_to_listing(#[case] server: TestServer) -> Result<(), Error> {
     Ok(())
 }
 
+/// The index file of a directory is served as HTML in place of its listing, directories without
+/// one being listed.
+#[rstest]
+fn serves_index_html(
+    #[with(&["--index", "index.html"])] server_no_stderr: TestServer,
+) -> Result<(), Error> {
+    let index = "<html><body>My static site</body></html>";
+    std::fs::write(server_no_stderr.path().join("index.html"), index)?;
+
+    let resp = reqwest::blocking::get(server_no_stderr.url())?.error_for_status()?;
+    assert_eq!(resp.headers()["content-type"], "text/html; charset=utf-8");
+    assert_eq!(resp.text()?, index);
+
+    let body =
+        reqwest::blocking::get(server_no_stderr.url().join(DIRECTORIES[0])?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("class", "file")).next().is_some());
+
+    Ok(())
+}
+
 #[rstest]
 #[case(server_no_stderr(&["--spa", "--index", FILES[0]]), "/")]
 #[case(server_no_stderr(&["--spa", "--index", FILES[0]]), "/spa-route")]
@@ -267,6 +355,33 @@ fn serve_index_instead_of_404_in_spa_mode(
     Ok(())
 }
 
+/// Paths which don't exist get the index file with --spa, unlike existing files and directories,
+/// and a 404 as usual without it.
+#[rstest]
+fn spa_serves_index_for_unknown_routes(
+    #[with(&["--spa", "--index", "index.html"])] server_no_stderr: TestServer,
+) -> Result<(), Error> {
+    let route = server_no_stderr.url().join("some/deep/route")?;
+    let resp = reqwest::blocking::get(route.clone())?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let index = "<html><body>My single page app</body></html>";
+    std::fs::write(server_no_stderr.path().join("index.html"), index)?;
+
+    let resp = reqwest::blocking::get(route)?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text()?, index);
+
+    let resp = reqwest::blocking::get(server_no_stderr.url().join(FILES[0])?)?;
+    assert_eq!(resp.text()?, "Test Hello Yes");
+    let body =
+        reqwest::blocking::get(server_no_stderr.url().join(DIRECTORIES[0])?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Attr("class", "file")).next().is_some());
+
+    Ok(())
+}
+
 #[rstest]
 #[case(server(&["--route-prefix", "foobar"]))]
 #[case(server(&["--route-prefix", "/foobar/"]))]
@@ -282,6 +397,32 @@ fn serves_requests_with_route_prefix(#[case] server: TestServer) -> Result<(), E
     Ok(())
 }
 
+/// Links to entries and the upload form are under the route prefix
+#[rstest]
+fn listing_links_use_route_prefix(
+    #[with(&["--route-prefix", "/files", "-u"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join("files/")?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let entry_links = parsed
+        .find(Attr("class", "file").or(Attr("class", "directory")))
+        .filter_map(|link| link.attr("href"))
+        .collect::<Vec<_>>();
+    assert!(entry_links.contains(&"/files/test.txt"));
+    assert!(entry_links.contains(&"/files/dira/"));
+    assert!(entry_links.iter().all(|link| link.starts_with("/files/")));
+
+    let upload_action = parsed
+        .find(Attr("id", "file_submit"))
+        .next()
+        .and_then(|form| form.attr("action"))
+        .ok_or("No upload form")?;
+    assert!(upload_action.starts_with("/files/upload?"));
+
+    Ok(())
+}
+
 #[rstest]
 #[case(server_no_stderr(&[] as &[&str]), "/[a-f0-9]+")]
 #[case(server_no_stderr(&["--random-route"]), "/[a-f0-9]+")]
@@ -303,3 +444,163 @@ fn serves_requests_static_file_check(
 
     Ok(())
 }
+
+/// Directories are grouped relative to files, whatever the sorting.
+#[rstest]
+#[case(server(&["--group-dirs", "first"]), "?sort=name&order=desc", true)]
+#[case(server(&["--group-dirs", "first"]), "?sort=size&order=asc", true)]
+#[case(server(&["--dirs-first"]), "", true)]
+#[case(server(&["--group-dirs", "last"]), "?sort=name&order=asc", false)]
+#[case(server(&["--group-dirs", "last"]), "?group=first", true)]
+#[case(server(None::<&str>), "?group=last", false)]
+fn serves_requests_with_grouped_dirs(
+    #[case] server: TestServer,
+    #[case] query: &str,
+    #[case] dirs_first: bool,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url().join(query)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let is_dir = parsed
+        .find(|x: &Node| x.name() == Some("a"))
+        .filter_map(|x| match x.attr("class") {
+            Some("directory") => Some(true),
+            Some("file") => Some(false),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(is_dir.len(), FILES.len() + DIRECTORIES.len() + 1);
+
+    // Once we leave the first group, we never go back to it
+    let first_group_len = is_dir.iter().take_while(|&&d| d == dirs_first).count();
+    assert!(is_dir[first_group_len..].iter().all(|&d| d != dirs_first));
+    assert!(first_group_len > 0 && first_group_len < is_dir.len());
+
+    Ok(())
+}
+
+/// The title replaces the host in the page title and the heading, escaped
+#[rstest]
+fn serves_custom_title(
+    #[with(&["--title", "My <b>files</b>"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let body = body.text()?;
+    assert!(!body.contains("<b>"));
+
+    let parsed = Document::from_read(body.as_bytes())?;
+    let title = parsed.find(Name("title")).next().ok_or("No title")?;
+    assert_eq!(title.text(), "My <b>files</b>");
+    let heading = parsed.find(Name("h1")).next().ok_or("No heading")?;
+    assert!(heading.text().starts_with("My <b>files</b>"));
+
+    Ok(())
+}
+
+/// Modification dates are shown in UTC with --tz-utc
+#[rstest]
+fn serves_modification_dates(#[with(&["--tz-utc"])] server: TestServer) -> Result<(), Error> {
+    let file = std::fs::File::options()
+        .write(true)
+        .open(server.path().join("test.txt"))?;
+    file.set_modified(std::time::UNIX_EPOCH + Duration::from_secs(1_000_000_000))?;
+
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let row = parsed
+        .find(Name("tr"))
+        .find(|row| row.find(Attr("href", "/test.txt")).next().is_some())
+        .ok_or("No row for test.txt")?;
+    let date = row.find(Name("time")).next().ok_or("No date")?;
+    assert_eq!(date.text().trim(), "2001-09-09 01:46:40 +00:00");
+    assert_eq!(date.attr("datetime"), Some("2001-09-09T01:46:40+00:00"));
+
+    Ok(())
+}
+
+/// Entries are sorted by the requested column, whose header links to the reverse order. Without
+/// an order, the largest files come first.
+#[rstest]
+#[case("?sort=size&order=desc")]
+#[case("?sort=size")]
+fn serves_requests_sorted_by_size(server: TestServer, #[case] query: &str) -> Result<(), Error> {
+    std::fs::write(server.path().join("large.bin"), vec![0; 10_000])?;
+    std::fs::write(server.path().join("medium.bin"), vec![0; 1_000])?;
+
+    let body = reqwest::blocking::get(server.url().join(query)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+
+    let files = parsed
+        .find(Attr("class", "file"))
+        .filter_map(|x| x.attr("href"))
+        .collect::<Vec<_>>();
+    assert_eq!(files[..2], ["/large.bin", "/medium.bin"]);
+    assert_eq!(files.len(), FILES.len() + 2);
+
+    let size_header = parsed
+        .find(Attr("title", "Sort by size in ascending order"))
+        .next()
+        .unwrap();
+    assert_eq!(size_header.attr("href"), Some("?sort=size&order=asc"));
+
+    Ok(())
+}
+
+/// Range requests get the requested part of files, full responses advertise range support.
+#[rstest]
+#[case("bytes=0-3", "bytes 0-3/14", "Test")]
+#[case("bytes=5-", "bytes 5-13/14", "Hello Yes")]
+// Only the first of several ranges is sent
+#[case("bytes=0-3,10-", "bytes 0-3/14", "Test")]
+fn serves_requests_with_range(
+    server: TestServer,
+    #[case] range: &str,
+    #[case] content_range: &str,
+    #[case] expected: &str,
+) -> Result<(), Error> {
+    let client = reqwest::blocking::Client::new();
+
+    let resp = client.get(server.url().join("test.txt")?).send()?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["accept-ranges"], "bytes");
+
+    let resp = client
+        .get(server.url().join("test.txt")?)
+        .header("Range", range)
+        .send()?;
+    assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(resp.headers()["content-range"], content_range);
+    assert_eq!(resp.text()?, expected);
+
+    Ok(())
+}
+
+/// A file given instead of a directory is served at the root, without archives
+#[rstest]
+fn serves_single_file(tmpdir: TempDir, port: u16) -> Result<(), Error> {
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path().join("test.txt"))
+        .arg("-p")
+        .arg(port.to_string())
+        .args(["-r", "-z"])
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    wait_for_port(port);
+
+    let url = format!("http://localhost:{port}");
+    let resp = reqwest::blocking::get(&url)?.error_for_status()?;
+    assert!(resp.headers()["content-type"]
+        .to_str()?
+        .starts_with("text/plain"));
+    assert_eq!(resp.text()?, "Test Hello Yes");
+
+    let resp = reqwest::blocking::get(format!("{url}/?download=tar"))?.error_for_status()?;
+    assert_eq!(resp.text()?, "Test Hello Yes");
+    let resp = reqwest::blocking::get(format!("{url}/download"))?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    child.kill()?;
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DEEPLY_NESTED_FILE, DIRECTORIES, FILES, HIDDEN_FILES};
+use percent_encoding::percent_decode_str;
+use pretty_assertions::assert_eq;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::StatusCode;
+use rstest::rstest;
+
+/// Parse the sitemap, failing on malformed XML, and return its URLs.
+fn sitemap_urls(sitemap: &str) -> Result<Vec<String>, Error> {
+    let mut reader = Reader::from_str(sitemap);
+    let mut urls = Vec::new();
+    let mut in_loc = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.name().as_ref() == b"loc" => in_loc = true,
+            Event::End(e) if e.name().as_ref() == b"loc" => in_loc = false,
+            Event::Text(text) if in_loc => urls.push(text.unescape()?.into_owned()),
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(urls)
+}
+
+/// Returns the paths of the files in the sitemap at `url`, relative to `base`
+fn sitemap_paths(url: reqwest::Url, base: &str) -> Result<Vec<String>, Error> {
+    let resp = reqwest::blocking::get(url)?.error_for_status()?;
+    assert_eq!(
+        resp.headers()["content-type"],
+        "application/xml; charset=utf-8"
+    );
+    sitemap_urls(&resp.text()?)?
+        .iter()
+        .map(|url| {
+            let path = url
+                .strip_prefix(base)
+                .ok_or_else(|| format!("{url} is not under {base}"))?;
+            Ok(percent_decode_str(path).decode_utf8()?.into_owned())
+        })
+        .collect()
+}
+
+/// The sitemap is not served by default.
+#[rstest]
+fn sitemap_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let status = reqwest::blocking::get(server.url().join("sitemap.xml")?)?.status();
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+/// The sitemap is valid XML with the absolute URLs of the files, without hidden ones.
+#[rstest]
+fn sitemap_lists_files(#[with(&["--sitemap"])] server: TestServer) -> Result<(), Error> {
+    let paths = sitemap_paths(server.url().join("sitemap.xml")?, server.url().as_str())?;
+
+    for &file in FILES {
+        assert!(
+            paths.iter().any(|path| path == file),
+            "{file} not in {paths:?}"
+        );
+        for &dir in DIRECTORIES {
+            assert!(paths.iter().any(|path| *path == format!("{dir}{file}")));
+        }
+    }
+    for &file in HIDDEN_FILES {
+        assert!(paths.iter().all(|path| !path.contains(file)));
+    }
+
+    Ok(())
+}
+
+/// URLs include the route prefix.
+#[rstest]
+fn sitemap_respects_route_prefix(
+    #[with(&["--sitemap", "--route-prefix", "prefix"])] server: TestServer,
+) -> Result<(), Error> {
+    let base = server.url().join("prefix/")?;
+    let paths = sitemap_paths(base.join("sitemap.xml")?, base.as_str())?;
+
+    assert!(paths.iter().any(|path| path == "test.txt"));
+
+    Ok(())
+}
+
+/// The number of URLs in the sitemap is limited.
+#[rstest]
+fn sitemap_entries_limited(#[with(&["--sitemap", "3"])] server: TestServer) -> Result<(), Error> {
+    let paths = sitemap_paths(server.url().join("sitemap.xml")?, server.url().as_str())?;
+    assert_eq!(paths.len(), 3);
+
+    Ok(())
+}
+
+/// Files deeper than --max-depth are left out of the sitemap.
+#[rstest]
+fn sitemap_follows_max_depth(
+    #[with(&["--sitemap", "--max-depth", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    let paths = sitemap_paths(server.url().join("sitemap.xml")?, server.url().as_str())?;
+
+    assert!(paths
+        .iter()
+        .any(|path| *path == format!("{}{}", DIRECTORIES[0], FILES[0])));
+    assert!(paths.iter().all(|path| path != DEEPLY_NESTED_FILE));
+
+    Ok(())
+}
+
+/// The robots.txt file is served as is at the root, whatever the route prefix.
+#[rstest]
+fn robots_file_is_served() -> Result<(), Error> {
+    let robots_dir = assert_fs::TempDir::new()?;
+    let robots_file = robots_dir.path().join("robots.txt");
+    std::fs::write(&robots_file, "User-agent: *\nAllow: /\n")?;
+    let server = server(&[
+        "--robots-file",
+        robots_file.to_str().unwrap(),
+        "--route-prefix",
+        "prefix",
+    ]);
+
+    let resp = reqwest::blocking::get(server.url().join("robots.txt")?)?.error_for_status()?;
+    assert_eq!(resp.text()?, "User-agent: *\nAllow: /\n");
+
+    Ok(())
+}
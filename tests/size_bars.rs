@@ -0,0 +1,70 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Class, Descendant, Name};
+
+/// Returns the widths of the size bars of the listing at `path`, by file name
+fn size_bars(server: &TestServer, path: &str) -> Result<Vec<(String, String)>, Error> {
+    let body = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    Ok(parsed
+        .find(Name("tr"))
+        .filter_map(|row| {
+            let name = row.find(Class("file")).next()?.text();
+            let bar = row
+                .find(Descendant(Class("size-bar"), Name("span")))
+                .next()?;
+            Some((name, bar.attr("style")?.to_string()))
+        })
+        .collect())
+}
+
+/// The largest file has a full bar, others are scaled to it.
+#[rstest]
+fn size_bars_are_relative_to_largest_file(
+    #[with(&["--size-bars"])] server: TestServer,
+) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("sized"))?;
+    std::fs::write(server.path().join("sized/large"), vec![0; 4000])?;
+    std::fs::write(server.path().join("sized/small"), vec![0; 1000])?;
+    std::fs::write(server.path().join("sized/empty"), "")?;
+
+    let mut bars = size_bars(&server, "sized/")?;
+    bars.sort();
+    assert_eq!(
+        bars,
+        vec![
+            ("empty".to_string(), "width: 0%".to_string()),
+            ("large".to_string(), "width: 100%".to_string()),
+            ("small".to_string(), "width: 25%".to_string()),
+        ]
+    );
+
+    Ok(())
+}
+
+/// Directories with only empty files don't divide by zero.
+#[rstest]
+fn size_bars_with_empty_files(#[with(&["--size-bars"])] server: TestServer) -> Result<(), Error> {
+    std::fs::create_dir(server.path().join("zeros"))?;
+    std::fs::write(server.path().join("zeros/a"), "")?;
+    std::fs::write(server.path().join("zeros/b"), "")?;
+
+    let bars = size_bars(&server, "zeros/")?;
+    assert_eq!(bars.len(), 2);
+    assert!(bars.iter().all(|(_, width)| width == "width: 0%"));
+
+    Ok(())
+}
+
+/// There are no size bars by default.
+#[rstest]
+fn size_bars_disabled_by_default(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed.find(Class("size-bar")).next().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use select::document::Document;
+use select::predicate::{Attr, Name};
+
+/// Classes of the body of the listing at `path`
+fn body_classes(server: &TestServer, path: &str) -> Result<Vec<String>, Error> {
+    let body = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let body = parsed.find(Name("body")).next().ok_or("No body")?;
+    Ok(body
+        .attr("class")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect())
+}
+
+#[rstest]
+#[case(server(&[] as &[&str]), "default_theme_squirrel")]
+#[case(server(&["--color-scheme", "zenburn"]), "default_theme_zenburn")]
+#[case(server(&["--color-scheme-dark", "monokai"]), "default_theme_dark_monokai")]
+fn listing_uses_color_scheme(#[case] server: TestServer, #[case] class: &str) -> Result<(), Error> {
+    let classes = body_classes(&server, "/")?;
+    assert!(classes.iter().any(|c| c == class), "{classes:?}");
+    assert!(!classes.iter().any(|c| c.starts_with("theme_")));
+
+    Ok(())
+}
+
+#[rstest]
+fn theme_query_picks_color_scheme(
+    #[with(&["--color-scheme", "zenburn"])] server: TestServer,
+) -> Result<(), Error> {
+    let classes = body_classes(&server, "/?theme=archlinux")?;
+    assert!(
+        classes.iter().any(|c| c == "theme_archlinux"),
+        "{classes:?}"
+    );
+
+    Ok(())
+}
+
+/// Like other invalid query parameters, unknown themes are ignored
+#[rstest]
+fn unknown_theme_is_ignored(server: TestServer) -> Result<(), Error> {
+    let classes = body_classes(&server, "/?theme=neon")?;
+    assert!(classes.iter().any(|c| c == "default_theme_squirrel"));
+    assert!(!classes.iter().any(|c| c.starts_with("theme_")));
+
+    Ok(())
+}
+
+#[rstest]
+fn color_mode_toggle_is_shown(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Attr("id", "color-mode-toggle"))
+        .next()
+        .is_some());
+
+    Ok(())
+}
+
+#[rstest]
+fn color_mode_toggle_is_hidden_with_theme_selector(
+    #[with(&["--hide-theme-selector"])] server: TestServer,
+) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    assert!(parsed
+        .find(Attr("id", "color-mode-toggle"))
+        .next()
+        .is_none());
+
+    Ok(())
+}
+
+/// The default dark theme follows prefers-color-scheme, unless overridden by the toggle
+#[rstest]
+fn stylesheet_follows_color_mode(server: TestServer) -> Result<(), Error> {
+    let body = reqwest::blocking::get(server.url())?.error_for_status()?;
+    let parsed = Document::from_read(body)?;
+    let css_route = parsed
+        .find(Attr("rel", "stylesheet"))
+        .next()
+        .and_then(|link| link.attr("href"))
+        .ok_or("No stylesheet")?;
+    let css = reqwest::blocking::get(server.url().join(css_route)?)?.text()?;
+
+    assert!(css.contains("@media (prefers-color-scheme: dark)"));
+    assert!(css.contains(":not([data-color-mode=light]).default_theme_dark_monokai"));
+    assert!(css.contains("[data-color-mode=dark].default_theme_dark_monokai"));
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+/// Send the start of a request and never finish it, returning the time until the server gives
+/// up on the connection and what it sent before.
+fn stall_request(server: &TestServer) -> Result<(Duration, String), Error> {
+    let mut stream = TcpStream::connect(("localhost", server.port()))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let start = Instant::now();
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n")?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok((start.elapsed(), response))
+}
+
+#[rstest]
+fn incomplete_request_is_dropped(
+    #[with(&["--client-timeout", "1"])] server: TestServer,
+) -> Result<(), Error> {
+    let (elapsed, response) = stall_request(&server)?;
+    assert!(
+        elapsed < Duration::from_secs(4),
+        "dropped after {elapsed:?}"
+    );
+    assert!(response.is_empty() || response.starts_with("HTTP/1.1 408"));
+
+    Ok(())
+}
+
+#[rstest]
+fn client_timeout_is_configurable(
+    #[with(&["--client-timeout", "3"])] server: TestServer,
+) -> Result<(), Error> {
+    let (elapsed, _) = stall_request(&server)?;
+    assert!(
+        elapsed >= Duration::from_secs(2),
+        "dropped after {elapsed:?}"
+    );
+
+    Ok(())
+}
@@ -1,9 +1,9 @@
 mod fixtures;
 
 use assert_cmd::Command;
-use fixtures::{server, Error, TestServer, FILES};
+use fixtures::{port, server, wait_for_port, Error, TestServer, FILES};
 use predicates::str::contains;
-use reqwest::blocking::ClientBuilder;
+use reqwest::{blocking::ClientBuilder, redirect::Policy, StatusCode};
 use rstest::rstest;
 use select::{document::Document, node::Node};
 
@@ -53,3 +53,71 @@ fn wrong_path_key() -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Files which don't hold a certificate or a key are reported with their path.
+#[rstest]
+#[case(&["--tls-cert", "tests/data/key_pkcs8.pem", "--tls-key", "tests/data/key_pkcs8.pem"], "Error: No PEM certificate in TLS certificate \"tests/data/key_pkcs8.pem\"")]
+#[case(&["--tls-cert", "tests/data/cert.pem", "--tls-key", "tests/data/cert.pem"], "Error: No supported private key in TLS key \"tests/data/cert.pem\"")]
+fn invalid_cert_or_key(#[case] args: &[&str], #[case] error: &str) -> Result<(), Error> {
+    Command::cargo_bin("miniserve")?
+        .args(args)
+        .assert()
+        .failure()
+        .stderr(contains(error));
+
+    Ok(())
+}
+
+/// With --hsts, the Strict-Transport-Security header is sent over TLS.
+#[rstest]
+#[case(server(&[
+        "--tls-cert", "tests/data/cert.pem",
+        "--tls-key", "tests/data/key_pkcs8.pem",
+        "--hsts", "--hsts-max-age", "600",
+]), "max-age=600")]
+#[case(server(&[
+        "--tls-cert", "tests/data/cert.pem",
+        "--tls-key", "tests/data/key_pkcs8.pem",
+        "--hsts", "--hsts-include-subdomains",
+]), "max-age=31536000; includeSubDomains")]
+fn hsts_header_set(#[case] server: TestServer, #[case] expected: &str) -> Result<(), Error> {
+    let client = ClientBuilder::new()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let resp = client.get(server.url()).send()?.error_for_status()?;
+
+    assert_eq!(
+        resp.headers().get("strict-transport-security").unwrap(),
+        expected
+    );
+
+    Ok(())
+}
+
+/// With --redirect-http, plain HTTP requests are redirected to the same URL over HTTPS.
+#[rstest]
+fn redirects_http_to_https() -> Result<(), Error> {
+    let http_port = port();
+    let server = server(&[
+        "--tls-cert",
+        "tests/data/cert.pem",
+        "--tls-key",
+        "tests/data/key_pkcs8.pem",
+        "--redirect-http",
+        &http_port.to_string(),
+    ]);
+    wait_for_port(http_port);
+
+    let client = ClientBuilder::new().redirect(Policy::none()).build()?;
+    let resp = client
+        .get(format!("http://localhost:{http_port}/someDir/?sort=size"))
+        .send()?;
+
+    assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(
+        resp.headers()["location"],
+        format!("https://localhost:{}/someDir/?sort=size", server.port()).as_str()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer};
+use rstest::rstest;
+use std::time::{Duration, Instant};
+
+/// Concurrent downloads share the total rate, fairly
+#[rstest]
+fn downloads_share_max_total_rate(
+    #[with(&["--max-total-rate", "100KB"])] server: TestServer,
+) -> Result<(), Error> {
+    for name in ["first.bin", "second.bin"] {
+        std::fs::write(server.path().join(name), vec![b'a'; 200_000])?;
+    }
+
+    let start = Instant::now();
+    let downloads = ["first.bin", "second.bin"].map(|name| {
+        let url = server.url().join(name).unwrap();
+        std::thread::spawn(move || -> reqwest::Result<(Duration, usize)> {
+            let resp = reqwest::blocking::get(url)?.error_for_status()?;
+            assert_eq!(resp.content_length(), Some(200_000));
+            let len = resp.bytes()?.len();
+            Ok((start.elapsed(), len))
+        })
+    });
+
+    let mut last = Duration::ZERO;
+    for download in downloads {
+        let (elapsed, len) = download.join().unwrap()?;
+        assert_eq!(len, 200_000);
+        // Each download gets its share, rather than waiting for the other one to finish
+        assert!(elapsed >= Duration::from_millis(1500), "{elapsed:?}");
+        last = last.max(elapsed);
+    }
+    // The first 100KB are sent right away, the other 300KB take 3 seconds
+    assert!(last >= Duration::from_millis(2800), "{last:?}");
+    assert!(last < Duration::from_secs(5), "{last:?}");
+
+    Ok(())
+}
+
+/// Without --max-total-rate, responses are not slowed down
+#[rstest]
+fn downloads_are_not_limited_by_default(server: TestServer) -> Result<(), Error> {
+    std::fs::write(server.path().join("big.bin"), vec![b'a'; 1_000_000])?;
+
+    let start = Instant::now();
+    let body = reqwest::blocking::get(server.url().join("big.bin")?)?
+        .error_for_status()?
+        .bytes()?;
+    assert_eq!(body.len(), 1_000_000);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    Ok(())
+}
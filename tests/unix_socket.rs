@@ -0,0 +1,66 @@
+#![cfg(unix)]
+
+mod fixtures;
+
+use assert_cmd::prelude::*;
+use fixtures::{tmpdir, Error};
+use rstest::rstest;
+use std::io::{Read, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Sends a GET request for `path` over the Unix domain socket at `socket`
+fn get(socket: &std::path::Path, path: &str) -> Result<String, Error> {
+    let mut stream = UnixStream::connect(socket)?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+#[rstest]
+fn serves_over_unix_socket() -> Result<(), Error> {
+    let tmpdir = tmpdir();
+    let socket_dir = assert_fs::TempDir::new()?;
+    let socket = socket_dir.path().join("miniserve.sock");
+
+    let mut child = Command::cargo_bin("miniserve")?
+        .arg(tmpdir.path())
+        .arg("--unix-socket")
+        .arg(&socket)
+        .args(["--unix-socket-mode", "600"])
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    let start = Instant::now();
+    while !socket.exists() && start.elapsed() < Duration::from_secs(1) {
+        sleep(Duration::from_millis(100));
+    }
+    let metadata = std::fs::symlink_metadata(&socket);
+    let response = get(&socket, "/");
+
+    // The socket is removed once stopped
+    Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()?;
+    child.wait()?;
+    assert!(!socket.exists());
+
+    let metadata = metadata?;
+    assert!(metadata.file_type().is_socket());
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+    let response = response?;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    for file in ["test.txt", "dira/"] {
+        assert!(response.contains(file), "{file} not listed");
+    }
+
+    Ok(())
+}
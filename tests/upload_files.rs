@@ -7,7 +7,11 @@ use rstest::rstest;
 use select::document::Document;
 use select::predicate::{Attr, Text};
 use std::fs::create_dir_all;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream};
 use std::path::Path;
+use std::time::{Duration, Instant};
+use url::Url;
 
 #[rstest]
 fn uploading_files_works(#[with(&["-u"])] server: TestServer) -> Result<(), Error> {
@@ -46,6 +50,39 @@ fn uploading_files_works(#[with(&["-u"])] server: TestServer) -> Result<(), Erro
     Ok(())
 }
 
+#[rstest]
+fn uploading_multiple_files_works(#[with(&["-u"])] server: TestServer) -> Result<(), Error> {
+    let test_file_names = ["first upload.txt", "second upload.txt"];
+
+    let form = test_file_names
+        .iter()
+        .try_fold(multipart::Form::new(), |form, name| {
+            let part = multipart::Part::text(format!("contents of {name}"))
+                .file_name(*name)
+                .mime_str("text/plain")?;
+            Ok::<_, Error>(form.part("file_to_upload", part))
+        })?;
+
+    Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?
+        .error_for_status()?;
+
+    // Every part got saved and is getting listed
+    let body = reqwest::blocking::get(server.url())?;
+    let parsed = Document::from_read(body)?;
+    for name in test_file_names {
+        assert!(parsed.find(Text).any(|x| x.text() == name));
+        assert_eq!(
+            std::fs::read_to_string(server.path().join(name))?,
+            format!("contents of {name}")
+        );
+    }
+
+    Ok(())
+}
+
 #[rstest]
 fn uploading_files_is_prevented(server: TestServer) -> Result<(), Error> {
     let test_file_name = "uploaded test file.txt";
@@ -116,6 +153,40 @@ fn uploading_files_is_restricted(#[case] server: TestServer) -> Result<(), Error
     Ok(())
 }
 
+/// Uploads are only accepted in the upload directories, the upload form being only shown there
+#[rstest]
+fn uploading_files_to_sibling_dir_is_prevented(
+    #[with(&["-u", "incoming"])] server: TestServer,
+) -> Result<(), Error> {
+    create_dir_all(server.path().join("incoming"))?;
+    create_dir_all(server.path().join("outgoing"))?;
+
+    let upload = |dir: &str| -> Result<reqwest::StatusCode, Error> {
+        let part = multipart::Part::text("uploaded").file_name("file.txt");
+        let form = multipart::Form::new().part("file_to_upload", part);
+        Ok(Client::new()
+            .post(server.url().join(&format!("/upload?path={dir}"))?)
+            .multipart(form)
+            .send()?
+            .status())
+    };
+    assert_eq!(upload("incoming")?, reqwest::StatusCode::OK);
+    assert_eq!(upload("outgoing")?, reqwest::StatusCode::FORBIDDEN);
+    assert!(server.path().join("incoming/file.txt").exists());
+    assert!(!server.path().join("outgoing/file.txt").exists());
+
+    for (dir, has_form) in [("incoming/", true), ("outgoing/", false)] {
+        let body = reqwest::blocking::get(server.url().join(dir)?)?.error_for_status()?;
+        let parsed = Document::from_read(body)?;
+        assert_eq!(
+            parsed.find(Attr("id", "file_submit")).next().is_some(),
+            has_form
+        );
+    }
+
+    Ok(())
+}
+
 /// This tests that we can upload files to the directory specified by --allow-upload-dir
 #[rstest]
 #[case(server(&["-u", "someDir"]), vec!["someDir"])]
@@ -275,3 +346,336 @@ fn set_media_type(
 
     Ok(())
 }
+
+/// Uploads are rejected once they would exceed the upload quota
+/// Uploads `contents` as "duplicate.txt" to the root of the served directory
+fn upload_duplicate_file(
+    server: &TestServer,
+    contents: &str,
+) -> Result<reqwest::StatusCode, Error> {
+    let part = multipart::Part::text(contents.to_string())
+        .file_name("duplicate.txt")
+        .mime_str("text/plain")?;
+    let form = multipart::Form::new().part("file_to_upload", part);
+    let response = Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?;
+    Ok(response.status())
+}
+
+#[rstest]
+fn uploading_duplicate_files_renames_them(
+    #[with(&["-u", "--on-duplicate", "rename"])] server: TestServer,
+) -> Result<(), Error> {
+    assert!(upload_duplicate_file(&server, "first")?.is_success());
+    assert!(upload_duplicate_file(&server, "second")?.is_success());
+    assert!(upload_duplicate_file(&server, "third")?.is_success());
+
+    let contents = |name| std::fs::read_to_string(server.path().join(name)).unwrap();
+    assert_eq!(contents("duplicate.txt"), "first");
+    assert_eq!(contents("duplicate (1).txt"), "second");
+    assert_eq!(contents("duplicate (2).txt"), "third");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(server(&["-u"]), "first")]
+#[case(server(&["-u", "--on-duplicate", "error"]), "first")]
+#[case(server(&["-u", "--on-duplicate", "overwrite"]), "second")]
+#[case(server(&["-u", "-o"]), "second")]
+fn uploading_duplicate_files_follows_on_duplicate(
+    #[case] server: TestServer,
+    #[case] kept_contents: &str,
+) -> Result<(), Error> {
+    assert!(upload_duplicate_file(&server, "first")?.is_success());
+    let status = upload_duplicate_file(&server, "second")?;
+    if kept_contents == "first" {
+        assert_eq!(status, reqwest::StatusCode::CONFLICT);
+    } else {
+        assert!(status.is_success());
+    }
+    assert_eq!(
+        std::fs::read_to_string(server.path().join("duplicate.txt"))?,
+        kept_contents
+    );
+
+    Ok(())
+}
+
+/// Files configuring miniserve can't be uploaded, even when hidden files are shown
+#[rstest]
+#[case(".miniserve-auth")]
+#[case(".miniserve.toml")]
+#[case("dira/.miniserve-auth")]
+fn uploading_reserved_names_is_prevented(
+    #[with(&["-u", "-H", "--auth-files", "--dir-config"])] server: TestServer,
+    #[case] file_name: &str,
+) -> Result<(), Error> {
+    let part = multipart::Part::text("testuser:testpassword")
+        .file_name(file_name.to_string())
+        .mime_str("text/plain")?;
+    let form = multipart::Form::new().part("file_to_upload", part);
+    let status = Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?
+        .status();
+
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert!(!server.path().join(file_name).exists());
+
+    Ok(())
+}
+
+#[rstest]
+fn uploading_files_respects_quota(
+    #[with(&["-u", "--upload-quota", "3000"])] server: TestServer,
+) -> Result<(), Error> {
+    let client = Client::new();
+    let upload = |file_name: &str| -> Result<reqwest::StatusCode, Error> {
+        let part = multipart::Part::bytes(vec![b'a'; 1000])
+            .file_name(file_name.to_string())
+            .mime_str("text/plain")?;
+        let form = multipart::Form::new().part("file_to_upload", part);
+        Ok(client
+            .post(server.url().join("/upload?path=/")?)
+            .multipart(form)
+            .send()?
+            .status())
+    };
+
+    assert_eq!(upload("first.txt")?, reqwest::StatusCode::OK);
+    assert_eq!(upload("second.txt")?, reqwest::StatusCode::OK);
+    assert_eq!(
+        upload("third.txt")?,
+        reqwest::StatusCode::INSUFFICIENT_STORAGE
+    );
+
+    assert!(server.path().join("second.txt").exists());
+    assert!(!server.path().join("third.txt").exists());
+
+    Ok(())
+}
+
+/// The usage of the quota can't be seen nor tampered with, and deleting files frees their size
+#[rstest]
+fn upload_quota_usage_is_protected(
+    #[with(&["-u", "-H", "--rm-files", "--upload-quota", "2500"])] server: TestServer,
+) -> Result<(), Error> {
+    let client = Client::new();
+    let upload = |file_name: &str| -> Result<reqwest::StatusCode, Error> {
+        let part = multipart::Part::bytes(vec![b'a'; 1000])
+            .file_name(file_name.to_string())
+            .mime_str("text/plain")?;
+        let form = multipart::Form::new().part("file_to_upload", part);
+        Ok(client
+            .post(server.url().join("/upload?path=/")?)
+            .multipart(form)
+            .send()?
+            .status())
+    };
+    let sidecar = ".miniserve-upload-quota";
+
+    assert_eq!(upload("first.txt")?, reqwest::StatusCode::OK);
+    assert_eq!(upload(sidecar)?, reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(upload("second.txt")?, reqwest::StatusCode::OK);
+    assert!(server.path().join(sidecar).exists());
+
+    let listing = client
+        .get(server.url())
+        .send()?
+        .error_for_status()?
+        .text()?;
+    assert!(!listing.contains(sidecar));
+    let status = client.get(server.url().join(sidecar)?).send()?.status();
+    assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+    client
+        .post(server.url().join("delete?path=/")?)
+        .form(&[("file", sidecar)])
+        .send()?
+        .error_for_status()?;
+    assert!(server.path().join(sidecar).exists());
+
+    assert_eq!(
+        upload("third.txt")?,
+        reqwest::StatusCode::INSUFFICIENT_STORAGE
+    );
+    client
+        .post(server.url().join("delete?path=/")?)
+        .form(&[("file", "first.txt")])
+        .send()?
+        .error_for_status()?;
+    assert!(!server.path().join("first.txt").exists());
+    assert_eq!(upload("third.txt")?, reqwest::StatusCode::OK);
+
+    Ok(())
+}
+
+/// Uploads larger than --max-upload-size are rejected, whether their size is announced or not,
+/// and leave no partial file behind
+#[rstest]
+fn uploading_files_respects_max_size(
+    #[with(&["-u", "--max-upload-size", "2K"])] server: TestServer,
+) -> Result<(), Error> {
+    let part = multipart::Part::bytes(vec![b'a'; 1000]).file_name("small.txt");
+    let form = multipart::Form::new().part("file_to_upload", part);
+    let status = Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?
+        .status();
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert!(server.path().join("small.txt").exists());
+
+    // Uploads are sent by hand, as the server doesn't wait for the rest of the body to answer
+    let upload = |headers: &str, body: &str| -> Result<String, Error> {
+        let mut stream = TcpStream::connect(("localhost", server.port()))?;
+        write!(
+            stream,
+            "POST /upload?path=/ HTTP/1.1\r\n\
+            Host: localhost\r\n\
+            Content-Type: multipart/form-data; boundary=boundary\r\n\
+            {headers}\r\n\
+            {body}"
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    };
+
+    // The announced size is enough to reject the upload, before receiving its body
+    let response = upload("Content-Length: 10000\r\n", "")?;
+    assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+
+    // Without a known length, the body is checked while received
+    let part = format!(
+        "--boundary\r\n\
+        Content-Disposition: form-data; name=\"file_to_upload\"; filename=\"streamed.txt\"\r\n\
+        Content-Type: text/plain\r\n\r\n\
+        {}",
+        "a".repeat(3000)
+    );
+    let chunk = format!("{:x}\r\n{part}\r\n", part.len());
+    let response = upload("Transfer-Encoding: chunked\r\n", &chunk)?;
+    assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+    assert!(!server.path().join("streamed.txt").exists());
+
+    Ok(())
+}
+
+/// Uploads beyond the per-client concurrency limit are rejected while the others are in progress
+#[rstest]
+fn uploading_files_respects_concurrency_limit(
+    #[with(&["-u", "--upload-concurrency", "2"])] server: TestServer,
+) -> Result<(), Error> {
+    // Start uploads which never complete, the server waits for the rest of their body
+    let mut stalled_uploads = Vec::new();
+    for i in 0..2 {
+        let mut stream = TcpStream::connect(("localhost", server.port()))?;
+        let part = format!(
+            "--boundary\r\n\
+            Content-Disposition: form-data; name=\"file_to_upload\"; filename=\"stalled{i}.txt\"\r\n\
+            Content-Type: text/plain\r\n\r\n\
+            partial content"
+        );
+        write!(
+            stream,
+            "POST /upload?path=/ HTTP/1.1\r\n\
+            Host: localhost\r\n\
+            Content-Type: multipart/form-data; boundary=boundary\r\n\
+            Content-Length: 100000\r\n\r\n\
+            {part}"
+        )?;
+        stalled_uploads.push(stream);
+    }
+    std::thread::sleep(Duration::from_millis(200));
+
+    let upload = || -> Result<reqwest::StatusCode, Error> {
+        let part = multipart::Part::text("this should be uploaded")
+            .file_name("file.txt")
+            .mime_str("text/plain")?;
+        let form = multipart::Form::new().part("file_to_upload", part);
+        Ok(Client::new()
+            .post(server.url().join("/upload?path=/")?)
+            .multipart(form)
+            .send()?
+            .status())
+    };
+    assert_eq!(upload()?, reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+    // Once the other uploads are aborted, uploading works again
+    drop(stalled_uploads);
+    std::thread::sleep(Duration::from_millis(200));
+    assert_eq!(upload()?, reqwest::StatusCode::OK);
+
+    Ok(())
+}
+
+/// Clients uploading at the same time are each slowed down to their own bandwidth
+#[rstest]
+fn uploading_files_respects_rate_per_ip(
+    #[with(&["-u", "--upload-rate-per-ip", "100KB"])] server: TestServer,
+) -> Result<(), Error> {
+    let url = Url::parse(&format!("http://127.0.0.1:{}/upload?path=/", server.port()))?;
+    let uploads = ["127.0.0.1", "127.0.0.2"].map(|ip| {
+        let url = url.clone();
+        std::thread::spawn(move || -> reqwest::Result<Duration> {
+            let client = Client::builder()
+                .local_address(ip.parse::<IpAddr>().unwrap())
+                .build()?;
+            // The first 100KB are allowed right away, the rest takes 2 seconds
+            let part = multipart::Part::bytes(vec![b'a'; 300_000])
+                .file_name(format!("{ip}.txt"))
+                .mime_str("text/plain")?;
+            let form = multipart::Form::new().part("file_to_upload", part);
+            let start = Instant::now();
+            client
+                .post(url)
+                .multipart(form)
+                .send()?
+                .error_for_status()?;
+            Ok(start.elapsed())
+        })
+    });
+
+    for upload in uploads {
+        let elapsed = upload.join().unwrap()?;
+        assert!(elapsed >= Duration::from_millis(1800), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(4), "{elapsed:?}");
+    }
+    assert!(server.path().join("127.0.0.2.txt").exists());
+
+    Ok(())
+}
+
+/// Files saved before a field of the upload fails are kept, and count against the quota
+#[rstest]
+fn upload_quota_counts_files_kept_after_failures(
+    #[with(&["-u", "--upload-quota", "5000"])] server: TestServer,
+) -> Result<(), Error> {
+    let kept = multipart::Part::bytes(vec![b'a'; 1500])
+        .file_name("kept.txt")
+        .mime_str("text/plain")?;
+    let refused = multipart::Part::bytes(vec![b'a'; 10])
+        .file_name(".miniserve-auth")
+        .mime_str("text/plain")?;
+    let form = multipart::Form::new()
+        .part("file_to_upload", kept)
+        .part("file_to_upload", refused);
+    let status = Client::new()
+        .post(server.url().join("/upload?path=/")?)
+        .multipart(form)
+        .send()?
+        .status();
+    assert_eq!(status, reqwest::StatusCode::BAD_REQUEST);
+    assert!(server.path().join("kept.txt").exists());
+
+    let used: u64 = std::fs::read_to_string(server.path().join(".miniserve-upload-quota"))?
+        .trim()
+        .parse()?;
+    assert_eq!(used, 1500);
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+mod fixtures;
+
+use fixtures::{server, Error, TestServer, DEEPLY_NESTED_FILE, DIRECTORIES, FILES};
+use rstest::rstest;
+
+/// Fetch the URL listing at `path`, returning the decoded URLs.
+fn fetch_urls(server: &TestServer, path: &str) -> Result<Vec<String>, Error> {
+    let resp = reqwest::blocking::get(server.url().join(path)?)?.error_for_status()?;
+    Ok(resp
+        .text()?
+        .lines()
+        .map(|url| {
+            percent_encoding::percent_decode_str(url)
+                .decode_utf8_lossy()
+                .to_string()
+        })
+        .collect())
+}
+
+#[rstest]
+fn urls_listing(server: TestServer) -> Result<(), Error> {
+    let urls = fetch_urls(&server, "?format=urls")?;
+
+    assert_eq!(urls.len(), FILES.len());
+    for &file in FILES {
+        let url = format!("http://localhost:{}/{file}", server.port());
+        assert!(urls.contains(&url), "{url} is missing");
+    }
+
+    Ok(())
+}
+
+#[rstest]
+fn urls_listing_recursive(
+    #[with(&["--route-prefix", "prefix"])] server: TestServer,
+) -> Result<(), Error> {
+    let base_url = format!("http://localhost:{}/prefix", server.port());
+    let urls = fetch_urls(&server, "prefix/?format=urls&recursive=1")?;
+
+    for &file in FILES {
+        assert!(urls.contains(&format!("{base_url}/{file}")));
+        for &dir in DIRECTORIES {
+            assert!(urls.contains(&format!("{base_url}/{dir}{file}")));
+        }
+    }
+    assert!(urls.contains(&format!("{base_url}/{DEEPLY_NESTED_FILE}")));
+
+    // Only the files under the requested directory are listed
+    let urls = fetch_urls(&server, "prefix/dira/?format=urls&recursive=1")?;
+    assert_eq!(urls.len(), FILES.len());
+    assert!(urls.contains(&format!("{base_url}/dira/test.txt")));
+
+    Ok(())
+}